@@ -0,0 +1,106 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::environment::EnvironmentLight;
+use crate::light::{self, Emission};
+use crate::material::color::Color;
+use crate::objects::aabb::Aabb;
+use crate::objects::onb::Onb;
+use crate::objects::vector3::Vector3;
+use crate::pdf::Pdf;
+use crate::ray::Ray;
+use crate::rng;
+use crate::sky::Sky;
+
+// Radiance of the rays escaping the scene.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Background {
+    Solid(Color),
+    Gradient { horizon: Color, zenith: Color }, // Blended along the ray elevation
+    Environment(Arc<EnvironmentLight>),         // Also worth sampling as a light
+    Sky(Arc<Sky>),                              // Daylight, with a sun worth sampling
+}
+
+impl Background {
+    pub fn black() -> Background {
+        Background::Solid(Color::new(0.0, 0.0, 0.0))
+    }
+
+    pub fn sky() -> Background {
+        Background::Gradient {
+            horizon: Color::new(1.0, 1.0, 1.0),
+            zenith: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Solid(color) => color.clone(),
+            Background::Gradient { horizon, zenith } => {
+                let a = 0.5 * (ray.direction().normalise().y() + 1.0);
+
+                (1.0 - a) * horizon + a * zenith
+            }
+            Background::Environment(light) => light.radiance(ray.direction()),
+            Background::Sky(sky) => sky.radiance(ray.direction()),
+        }
+    }
+
+    pub fn is_black(&self) -> bool {
+        match self {
+            Background::Solid(color) => color.is_black(),
+            Background::Gradient { horizon, zenith } => horizon.is_black() && zenith.is_black(),
+            Background::Environment(_) | Background::Sky(_) => false,
+        }
+    }
+
+    // Ray of light arriving from the background, which starts from a disk facing it just
+    // outside the sphere around the bounds, as for a directional light.
+    pub fn emit(&self, bounds: &Aabb) -> Option<Emission> {
+        let (center, radius) = light::bounding_sphere(bounds)?;
+        let towards = self.generate();
+        let pdf = self.value(&towards);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let radiance = self.color(&Ray::new(center.clone(), towards.clone()));
+        if radiance.is_black() {
+            return None;
+        }
+
+        let disk = Onb::new(&towards).transform(&Vector3::new_random_in_unit_disk());
+        Some(Emission {
+            origin: &center + radius * (disk + &towards),
+            direction: -towards,
+            power: PI * radius * radius / pdf * radiance,
+        })
+    }
+}
+
+// Directions towards the background. Environments and the sun are sampled half of the time,
+// and the whole sphere otherwise, so that no part of the background is left out.
+impl Pdf for Background {
+    fn value(&self, direction: &Vector3) -> f64 {
+        let uniform = 1.0 / (4.0 * PI);
+        match self {
+            Background::Solid(_) | Background::Gradient { .. } => uniform,
+            Background::Environment(light) => 0.5 * light.pdf(direction) + 0.5 * uniform,
+            Background::Sky(sky) => 0.5 * sky.pdf(direction) + 0.5 * uniform,
+        }
+    }
+
+    fn generate(&self) -> Vector3 {
+        let half = rng::random() < 0.5;
+        match self {
+            Background::Environment(light) if half => light.sample(),
+            Background::Sky(sky) if half => sky.sample(),
+            _ => Vector3::new_random_unit(),
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background::sky()
+    }
+}
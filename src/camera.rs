@@ -1,33 +1,108 @@
 extern crate fastrand;
 
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+
 use crate::{
+    background::Background,
+    film::Film,
+    fog::Fog,
+    integrator::{path::PathTracer, Context, Integrator, PathStats, Splat},
     interval::Interval,
+    light::Light,
     material::color::Color,
     objects::{point3::Point3, vector3::Vector3, Hittable, HittableList},
-    ppm::image::Ppm,
     ray::Ray,
     rng,
 };
 
+// How the image maps onto the camera rays.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Projection {
+    #[default]
+    Perspective, // Through a thin lens, with the vertical field of view
+    Orthographic, // Parallel rays, framing the window the perspective frames on the focus plane
+    Fisheye {
+        fov: f64,
+        mapping: Fisheye,
+    }, // Image circle across the shorter side, fov in degrees
+    Equirectangular, // Full turn around across the width, zenith to nadir down the height
+}
+
+// Angle from the axis of the rays of a fisheye image, against the distance from its center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fisheye {
+    Equidistant, // In proportion to the distance
+    Equisolid,   // Keeping areas in proportion to solid angles
+}
+
+// Pair of views rendered side by side or one above the other, the left eye first. Each eye
+// sits half the interpupillary distance away from the camera position, across the view, and
+// its rays turn inwards to meet those of the other eye at the convergence distance. With the
+// equirectangular projection, the eyes turn around the camera position with the view, for
+// omnidirectional stereo panoramas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stereo {
+    pub layout: Layout,
+    pub ipd: f64,         // Interpupillary distance, between the eyes
+    pub convergence: f64, // Distance at which the views meet, infinite for parallel views
+}
+
+// Arrangement of the views of both eyes in the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    SideBySide, // Left eye on the left
+    TopBottom,  // Left eye on top
+}
+
 #[derive(Debug, Default)]
 pub struct Camera {
-    position: Point3,         // Point camera is looking from
-    lookat: Point3,           // Point camera is looking at
-    vup: Vector3,             // Camera-relative "up" direction
-    aspect_ratio: f64,        // Ratio of image width over height
-    img_w: u32,               // Rendered image width in pixel count
-    img_h: u32,               // Rendered image height in pixel count
-    samples_per_pixel: usize, // Count of random samples for each pixel
-    max_depth: usize,
-    vfov: f64,               // Vertical view angle (field of view)
-    focus_dist: f64,         // Distance from camera lookfrom point to plane of perfect focus
-    defocus_angle: f64,      // Variation angle of rays through each pixel
+    position: Point3,                        // Point camera is looking from
+    lookat: Point3,                          // Point camera is looking at
+    vup: Vector3,                            // Camera-relative "up" direction
+    aspect_ratio: f64,                       // Ratio of image width over height
+    img_w: u32,                              // Rendered image width in pixel count
+    img_h: u32,                              // Rendered image height in pixel count
+    samples_per_pixel: usize,                // Count of random samples for each pixel
+    max_depth: usize, // Most bounces along a path, a safety net for Russian roulette
+    min_depth: usize, // Bounces before Russian roulette may end a path
+    threads: usize,   // Count of worker threads rendering tiles
+    tile_size: u32,   // Width and height of a square tile in pixel count
+    seed: Option<u64>, // Seed making renders reproducible, random if unset
+    progress: bool,   // Report rendering progress on the standard error
+    vfov: f64,        // Vertical view angle (field of view)
+    focus_dist: f64,  // Distance from camera lookfrom point to plane of perfect focus
+    defocus_angle: f64, // Variation angle of rays through each pixel
     defocus_disk_u: Vector3, // Defocus disk horizontal radius
     defocus_disk_v: Vector3, // Defocus disk vertical radius
+    projection: Projection, // Mapping of the image onto the rays, defocus only blurring perspective
+    stereo: Option<Stereo>, // Views of both eyes, each the size of the image, if any
+    background: Background, // Radiance of the rays leaving the scene
+    fog: Option<Fog>, // Medium filling the scene, if any
+    delta_lights: Vec<Arc<dyn Light>>, // Point-like lights, out of reach of the rays
+    integrator: Option<Arc<dyn Integrator>>, // Light transport algorithm, path tracing if unset
 
     viewport: Viewport,
 }
 
+#[derive(Debug, Clone)]
+struct Tile {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Pixel {
+    color: Color, // Mean radiance of the samples
+    alpha: f64,   // Fraction of the camera rays hitting the scene
+    depth: f64,   // Mean distance to the first surface hit, infinite if none
+}
+
 #[derive(Debug, Default)]
 pub struct Viewport {
     origin: Point3,
@@ -48,6 +123,94 @@ impl Camera {
         self
     }
 
+    pub fn set_minimum_depth(&mut self, min_depth: usize) -> &mut Self {
+        self.min_depth = min_depth;
+
+        self
+    }
+
+    pub fn set_threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = usize::max(threads, 1);
+
+        self
+    }
+
+    pub fn set_tile_size(&mut self, tile_size: u32) -> &mut Self {
+        self.tile_size = u32::max(tile_size, 1);
+
+        self
+    }
+
+    pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    pub fn set_image_width(&mut self, img_w: u32) -> &mut Self {
+        self.img_w = u32::max(img_w, 1);
+        self.update();
+
+        self
+    }
+
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f64) -> &mut Self {
+        self.aspect_ratio = aspect_ratio;
+        self.update();
+
+        self
+    }
+
+    pub fn set_vfov(&mut self, vfov: f64) -> &mut Self {
+        self.vfov = vfov;
+        self.update();
+
+        self
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) -> &mut Self {
+        self.projection = projection;
+
+        self
+    }
+
+    pub fn set_stereo(&mut self, stereo: Option<Stereo>) -> &mut Self {
+        self.stereo = stereo;
+
+        self
+    }
+
+    pub fn set_progress(&mut self, progress: bool) -> &mut Self {
+        self.progress = progress;
+
+        self
+    }
+
+    pub fn set_background(&mut self, background: Background) -> &mut Self {
+        self.background = background;
+
+        self
+    }
+
+    pub fn set_fog(&mut self, fog: Option<Fog>) -> &mut Self {
+        self.fog = fog;
+
+        self
+    }
+
+    pub fn set_delta_lights(&mut self, lights: Vec<Arc<dyn Light>>) -> &mut Self {
+        self.delta_lights = lights;
+
+        self
+    }
+
+    pub fn set_integrator(&mut self, integrator: Arc<dyn Integrator>) -> &mut Self {
+        self.integrator = Some(integrator);
+
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         position: Point3,
         lookat: Point3,
@@ -58,70 +221,570 @@ impl Camera {
         focus_dist: f64,
         defocus_angle: f64,
     ) -> Camera {
-        let img_h = if (f64::from(img_w) / aspect_ratio) < 1_f64 {
+        let mut camera = Camera {
+            position,
+            lookat,
+            vup,
+            aspect_ratio,
+            img_w,
+            samples_per_pixel: 10,
+            max_depth: 10,
+            min_depth: 3,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            tile_size: 32,
+            progress: true,
+            vfov,
+            focus_dist,
+            defocus_angle,
+            ..Camera::default()
+        };
+        camera.update();
+
+        camera
+    }
+
+    fn update(&mut self) {
+        // Derive the image height, viewport and defocus disk from the camera parameters.
+        self.img_h = if (f64::from(self.img_w) / self.aspect_ratio) < 1_f64 {
             1
         } else {
-            (f64::from(img_w) / aspect_ratio) as u32
+            (f64::from(self.img_w) / self.aspect_ratio) as u32
         };
 
         // Viewport dimensions
-        let theta = f64::to_radians(vfov);
+        let theta = f64::to_radians(self.vfov);
         let h = f64::tan(theta / 2.0);
-        let vh = 2.0 * h * focus_dist;
-        let vw = vh * aspect_ratio;
+        let vh = 2.0 * h * self.focus_dist;
+        let vw = vh * self.aspect_ratio;
 
         // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
-        let w = Vector3::from(&position - &lookat).normalise();
-        let u = vup.cross(&w).normalise();
+        let w = Vector3::from(&self.position - &self.lookat).normalise();
+        let u = self.vup.cross(&w).normalise();
         let v = w.cross(&u);
 
         // Calculate the camera defocus disk basis vectors.
-        let defocus_radius = focus_dist * f64::tan(f64::to_radians(defocus_angle / 4.0));
-        let defocus_disk_u = &u * defocus_radius;
-        let defocus_disk_v = &v * defocus_radius;
+        let defocus_radius = self.focus_dist * f64::tan(f64::to_radians(self.defocus_angle / 4.0));
+        self.defocus_disk_u = &u * defocus_radius;
+        self.defocus_disk_v = &v * defocus_radius;
 
-        Camera {
-            position: position.clone(),
-            lookat,
-            vup,
-            aspect_ratio,
-            img_w,
-            img_h,
-            samples_per_pixel: 10,
-            max_depth: 10,
-            vfov,
-            viewport: Viewport::new(&position, focus_dist, &u, &v, &w, (vw, vh), (img_w, img_h)),
-            focus_dist,
-            defocus_angle,
-            defocus_disk_u,
-            defocus_disk_v,
+        self.viewport = Viewport::new(
+            &self.position,
+            self.focus_dist,
+            &u,
+            &v,
+            &w,
+            (vw, vh),
+            (self.img_w, self.img_h),
+        );
+    }
+
+    pub fn position(&self) -> &Point3 {
+        &self.position
+    }
+
+    pub fn lookat(&self) -> &Point3 {
+        &self.lookat
+    }
+
+    pub fn vup(&self) -> &Vector3 {
+        &self.vup
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+
+    pub fn vfov(&self) -> f64 {
+        self.vfov
+    }
+
+    pub fn focus_dist(&self) -> f64 {
+        self.focus_dist
+    }
+
+    pub fn image_width(&self) -> u32 {
+        self.img_w
+    }
+
+    pub fn image_height(&self) -> u32 {
+        self.img_h
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    pub fn fog(&self) -> Option<&Fog> {
+        self.fog.as_ref()
+    }
+
+    pub fn delta_lights(&self) -> &[Arc<dyn Light>] {
+        &self.delta_lights
+    }
+
+    pub fn defocus_angle(&self) -> f64 {
+        self.defocus_angle
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn stereo(&self) -> Option<Stereo> {
+        self.stereo
+    }
+
+    // Width and height of the rendered image, holding the views of both eyes in stereo.
+    pub fn frame_size(&self) -> (u32, u32) {
+        match self.stereo.map(|stereo| stereo.layout) {
+            None => (self.img_w, self.img_h),
+            Some(Layout::SideBySide) => (2 * self.img_w, self.img_h),
+            Some(Layout::TopBottom) => (self.img_w, 2 * self.img_h),
+        }
+    }
+
+    // Whether all the rays leave from the camera position, so that every point seen lies on a
+    // single ray and can be projected back onto the image. Points seen in stereo show in both
+    // views.
+    pub fn is_pinhole(&self) -> bool {
+        if self.stereo.is_some() {
+            return false;
+        }
+
+        match self.projection {
+            Projection::Perspective => self.defocus_angle <= 0.0,
+            Projection::Orthographic => false,
+            Projection::Fisheye { .. } | Projection::Equirectangular => true,
+        }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn min_depth(&self) -> usize {
+        self.min_depth
+    }
+
+    pub fn integrator(&self) -> &dyn Integrator {
+        match &self.integrator {
+            Some(integrator) => integrator.as_ref(),
+            None => &PathTracer,
+        }
+    }
+
+    // Density, by solid angle, of the directions of the camera rays, for pinhole cameras.
+    // Orthographic rays all share the same direction, and have none.
+    pub fn direction_pdf(&self, direction: &Vector3) -> f64 {
+        let (w, h) = (f64::from(self.img_w), f64::from(self.img_h));
+        let (_, _, forward) = self.basis();
+        let cosine = forward.dot(direction) / direction.norm();
+
+        match self.projection {
+            Projection::Perspective => {
+                match self.image_point(direction) {
+                    Some((x, y)) if x >= 0.0 && y >= 0.0 && x < w && y < h => (),
+                    _ => return 0.0,
+                }
+
+                // The image spread over a plane at unit distance.
+                let (du, dv) = (self.viewport.du().norm(), self.viewport.dv().norm());
+                let area = w * du * h * dv / (self.focus_dist * self.focus_dist);
+
+                1.0 / (area * cosine * cosine * cosine)
+            }
+            Projection::Orthographic => 0.0,
+            Projection::Fisheye { fov, mapping } => {
+                // Pixels over solid angle, around the circle at the angle theta from the axis.
+                let theta = f64::acos(Interval::new(-1.0, 1.0).clamp(cosine));
+                let theta_max = f64::to_radians(fov / 2.0);
+                if theta > theta_max {
+                    return 0.0;
+                }
+                let radius = f64::min(w, h) / 2.0;
+                let pixels = match mapping {
+                    Fisheye::Equidistant if theta.sin() > 0.0 => {
+                        (radius / theta_max).powi(2) * theta / theta.sin()
+                    }
+                    Fisheye::Equidistant => (radius / theta_max).powi(2),
+                    Fisheye::Equisolid => (radius / (2.0 * f64::sin(theta_max / 2.0))).powi(2),
+                };
+
+                pixels / (w * h)
+            }
+            Projection::Equirectangular => {
+                let (_, up, _) = self.basis();
+                let sin = f64::sqrt(f64::max(
+                    1.0 - (up.dot(direction) / direction.norm()).powi(2),
+                    0.0,
+                ));
+                if sin <= 0.0 {
+                    return 0.0;
+                }
+
+                1.0 / (2.0 * PI * PI * sin)
+            }
+        }
+    }
+
+    // Pixel whose rays pass through the point, if any. Only pinhole cameras have a single ray
+    // through each point.
+    pub fn raster(&self, point: &Point3) -> Option<(u32, u32)> {
+        if !self.is_pinhole() {
+            return None;
+        }
+
+        let direction = Vector3::from(point - &self.position);
+        let (x, y) = self.image_point(&direction)?;
+        if x < 0.0 || y < 0.0 || x >= f64::from(self.img_w) || y >= f64::from(self.img_h) {
+            return None;
+        }
+
+        Some((x as u32, y as u32))
+    }
+
+    // Unit vectors to the right of the image, up the image and along the view.
+    fn basis(&self) -> (Vector3, Vector3, Vector3) {
+        let right = self.viewport.du().normalise();
+        let up = -self.viewport.dv().normalise();
+        let forward = up.cross(&right);
+
+        (right, up, forward)
+    }
+
+    // Direction of the rays through the image at x, y from its upper left corner, in pixels,
+    // for the projections from the camera position.
+    fn image_direction(&self, x: f64, y: f64) -> Option<Vector3> {
+        let (w, h) = (f64::from(self.img_w), f64::from(self.img_h));
+        let (right, up, forward) = self.basis();
+
+        let (theta, phi) = match self.projection {
+            Projection::Fisheye { fov, mapping } => {
+                let radius = f64::min(w, h) / 2.0;
+                let (dx, dy) = ((x - w / 2.0) / radius, (h / 2.0 - y) / radius);
+                let r = f64::hypot(dx, dy);
+                if r > 1.0 {
+                    return None;
+                }
+
+                let theta_max = f64::to_radians(fov / 2.0);
+                let theta = match mapping {
+                    Fisheye::Equidistant => r * theta_max,
+                    Fisheye::Equisolid => 2.0 * f64::asin(r * f64::sin(theta_max / 2.0)),
+                };
+                // Angle around the axis, from the right of the image.
+                let psi = f64::atan2(dy, dx);
+                return Some(
+                    theta.sin() * (psi.cos() * &right + psi.sin() * &up) + theta.cos() * forward,
+                );
+            }
+            Projection::Equirectangular => (PI * y / h, 2.0 * PI * (x / w - 0.5)),
+            Projection::Perspective | Projection::Orthographic => return None,
+        };
+
+        Some(theta.sin() * (phi.sin() * right + phi.cos() * forward) + theta.cos() * up)
+    }
+
+    // Point of the image, in pixels from its upper left corner, whose rays from the camera
+    // position go along the direction.
+    fn image_point(&self, direction: &Vector3) -> Option<(f64, f64)> {
+        let (w, h) = (f64::from(self.img_w), f64::from(self.img_h));
+        let (right, up, forward) = self.basis();
+        let d = direction.normalise();
+        let (x, y, z) = (d.dot(&right), d.dot(&up), d.dot(&forward));
+
+        match self.projection {
+            Projection::Perspective => {
+                if z <= 0.0 {
+                    return None;
+                }
+
+                // Where the ray crosses the viewport, from the corner of the image.
+                let (du, dv) = (self.viewport.du(), self.viewport.dv());
+                let corner = self.viewport.origin() - 0.5 * (du + dv);
+                let crossing = &self.position + (self.focus_dist / z) * &d;
+                let offset = Vector3::from(&crossing - &corner);
+
+                Some((offset.dot(du) / du.dot(du), offset.dot(dv) / dv.dot(dv)))
+            }
+            Projection::Orthographic => None,
+            Projection::Fisheye { fov, mapping } => {
+                let theta = f64::acos(Interval::new(-1.0, 1.0).clamp(z));
+                let theta_max = f64::to_radians(fov / 2.0);
+                if theta > theta_max {
+                    return None;
+                }
+                let r = match mapping {
+                    Fisheye::Equidistant => theta / theta_max,
+                    Fisheye::Equisolid => f64::sin(theta / 2.0) / f64::sin(theta_max / 2.0),
+                };
+                let psi = f64::atan2(y, x);
+                let radius = f64::min(w, h) / 2.0;
+
+                Some((
+                    w / 2.0 + r * radius * psi.cos(),
+                    h / 2.0 - r * radius * psi.sin(),
+                ))
+            }
+            Projection::Equirectangular => {
+                let theta = f64::acos(Interval::new(-1.0, 1.0).clamp(y));
+                let phi = f64::atan2(x, z);
+
+                Some((w * (0.5 + phi / (2.0 * PI)), h * theta / PI))
+            }
         }
     }
 
-    pub fn render(&self, world: &HittableList) {
-        let mut img = Ppm::new(self.img_w, self.img_h, 256);
+    pub fn render(&self, world: &dyn Hittable) -> Film {
+        self.render_with_lights(world, &HittableList::new())
+    }
+
+    // Renders the world, sampling scattered rays towards the given lights as well as by the
+    // materials. The lights must also be part of the world.
+    pub fn render_with_lights(&self, world: &dyn Hittable, lights: &HittableList) -> Film {
+        self.render_with_stats(world, lights).0
+    }
 
-        eprintln!("Rendering...");
-        for j in 0..self.img_h {
-            eprint!("\r\x1b[2KLine {}/{}", j, self.img_h,);
-            let line: Vec<_> = (0..self.img_w)
-                .map(|i| {
-                    let mut color = Color::new(0.0, 0.0, 0.0);
-                    for _ in 0..self.samples_per_pixel {
-                        color += self.ray_color(&self.get_ray(i, j), self.max_depth, world);
+    pub fn render_with_stats(
+        &self,
+        world: &dyn Hittable,
+        lights: &HittableList,
+    ) -> (Film, PathStats) {
+        let tiles = self.tiles();
+        let seed = self.seed.unwrap_or_else(rng::random_u64);
+        let (w, h) = self.frame_size();
+        let mut film = Film::new(w, h);
+        let mut stats = PathStats::default();
+        let context = Context {
+            camera: self,
+            world,
+            lights,
+        };
+        let integrator = self.integrator();
+        let passes = integrator
+            .passes()
+            .clamp(1, usize::max(self.samples_per_pixel, 1));
+
+        // Integrators needing several passes, such as progressive photon mapping, share the
+        // samples per pixel among them.
+        self.report(format_args!("Rendering...\n"));
+        let mut done = 0;
+        for pass in 0..passes {
+            let samples = self.samples_per_pixel * (pass + 1) / passes - done;
+            let seed = pass_seed(seed, pass);
+            rng::seed(tile_seed(seed, tiles.len() as u64));
+            let prepared = integrator.prepare(&context, pass);
+            let integrator = prepared.as_deref().unwrap_or(integrator);
+
+            let progress = if passes > 1 {
+                format!("Pass {}/{}, tile", pass, passes)
+            } else {
+                "Tile".to_string()
+            };
+            let (pass_film, pass_stats) =
+                self.render_pass(integrator, &tiles, samples, seed, &context, &progress);
+            stats.merge(&pass_stats);
+            done += samples;
+            if pass == 0 {
+                film = pass_film;
+                continue;
+            }
+
+            let weight = samples as f64 / done as f64;
+            for y in 0..h {
+                for x in 0..w {
+                    let color = (1.0 - weight) * film.get(x, y) + weight * pass_film.get(x, y);
+                    film.set(x, y, color);
+                    let alpha = film.alpha(x, y);
+                    film.set_alpha(x, y, alpha + weight * (pass_film.alpha(x, y) - alpha));
+
+                    // Depth is the mean over the samples hitting a surface, which the alpha
+                    // counts.
+                    let (old, new) = ((1.0 - weight) * alpha, weight * pass_film.alpha(x, y));
+                    if new > 0.0 {
+                        let depth = if old > 0.0 {
+                            (old * film.depth(x, y) + new * pass_film.depth(x, y)) / (old + new)
+                        } else {
+                            pass_film.depth(x, y)
+                        };
+                        film.set_depth(x, y, depth);
                     }
+                }
+            }
+        }
+        self.report(format_args!("\r\x1b[2KDone!\n"));
+        if stats.paths() > 0 {
+            self.report(format_args!("{}\n", stats));
+        }
+
+        (film, stats)
+    }
+
+    fn render_pass(
+        &self,
+        integrator: &dyn Integrator,
+        tiles: &[Tile],
+        samples: usize,
+        seed: u64,
+        context: &Context,
+        progress: &str,
+    ) -> (Film, PathStats) {
+        let (w, h) = self.frame_size();
+        let mut film = Film::new(w, h);
+        let mut stats = PathStats::default();
+        let mut splats = vec![Vec::new(); tiles.len()];
+
+        if self.threads <= 1 {
+            for (n, tile) in tiles.iter().enumerate() {
+                self.report(format_args!("\r\x1b[2K{} {}/{}", progress, n, tiles.len()));
+                let (pixels, tile_stats, tile_splats) =
+                    self.render_tile(integrator, tile, samples, seed, n, context);
+                self.store_tile(&mut film, tile, pixels);
+                stats.merge(&tile_stats);
+                splats[n] = tile_splats;
+            }
+        } else {
+            let next = AtomicUsize::new(0);
+            let (tx, rx) = mpsc::channel();
+
+            thread::scope(|s| {
+                for _ in 0..usize::min(self.threads, tiles.len()) {
+                    let tx = tx.clone();
+                    let next = &next;
+
+                    s.spawn(move || loop {
+                        let n = next.fetch_add(1, Ordering::Relaxed);
+                        if n >= tiles.len() {
+                            break;
+                        }
+
+                        let (pixels, stats, splats) =
+                            self.render_tile(integrator, &tiles[n], samples, seed, n, context);
+                        if tx.send((n, pixels, stats, splats)).is_err() {
+                            break;
+                        }
+                    });
+                }
+                drop(tx);
+
+                for (done, (n, pixels, tile_stats, tile_splats)) in rx.iter().enumerate() {
+                    self.report(format_args!(
+                        "\r\x1b[2K{} {}/{}",
+                        progress,
+                        done,
+                        tiles.len()
+                    ));
+                    self.store_tile(&mut film, &tiles[n], pixels);
+                    stats.merge(&tile_stats);
+                    splats[n] = tile_splats;
+                }
+            });
+        }
+        // Splats land anywhere, they are added once all the tiles are done and in a fixed order.
+        let samples = f64::from(samples as u32);
+        for splat in splats.into_iter().flatten() {
+            let color = film.get(splat.x, splat.y) + splat.color / samples;
+            film.set(splat.x, splat.y, color);
+        }
+
+        (film, stats)
+    }
+
+    fn report(&self, message: std::fmt::Arguments) {
+        if self.progress {
+            eprint!("{}", message);
+        }
+    }
 
-                    color / f64::from(self.samples_per_pixel as u32)
-                })
-                .collect();
+    fn tiles(&self) -> Vec<Tile> {
+        let mut tiles = Vec::new();
 
-            for (i, pixel_color) in line.iter().enumerate() {
-                img.set(i as u32, j, pixel_color);
+        let (w, h) = self.frame_size();
+        for y0 in (0..h).step_by(self.tile_size as usize) {
+            for x0 in (0..w).step_by(self.tile_size as usize) {
+                tiles.push(Tile {
+                    x0,
+                    y0,
+                    x1: u32::min(x0 + self.tile_size, w),
+                    y1: u32::min(y0 + self.tile_size, h),
+                });
             }
         }
 
-        eprintln!("\r\x1b[2KDone!");
-        println!("{}", img);
+        tiles
+    }
+
+    fn render_tile(
+        &self,
+        integrator: &dyn Integrator,
+        tile: &Tile,
+        samples: usize,
+        seed: u64,
+        n: usize,
+        context: &Context,
+    ) -> (Vec<Pixel>, PathStats, Vec<Splat>) {
+        // Every tile draws from its own random sequence so that the image does not depend on
+        // which thread rendered which tile.
+        rng::seed(tile_seed(seed, n as u64));
+
+        let mut pixels = Vec::with_capacity(((tile.x1 - tile.x0) * (tile.y1 - tile.y0)) as usize);
+        let mut stats = PathStats::default();
+        let mut splats = Vec::new();
+        for j in tile.y0..tile.y1 {
+            for i in tile.x0..tile.x1 {
+                let mut color = Color::new(0.0, 0.0, 0.0);
+                let mut hits = 0_u32;
+                let mut depth = 0.0;
+
+                for _ in 0..samples {
+                    // Samples off the image circle of fisheye projections see nothing.
+                    let Some(ray) = self.get_ray(i, j) else {
+                        continue;
+                    };
+                    let rec = context.hit(&ray);
+
+                    if let Some(rec) = &rec {
+                        hits += 1;
+                        depth += rec.t * ray.direction().norm();
+                    }
+                    color += integrator.radiance(ray, rec, context, &mut stats, &mut splats);
+                }
+
+                let count = f64::from(samples as u32);
+                pixels.push(Pixel {
+                    color: color / count,
+                    alpha: f64::from(hits) / count,
+                    depth: if hits > 0 {
+                        depth / f64::from(hits)
+                    } else {
+                        f64::INFINITY
+                    },
+                });
+            }
+        }
+
+        // Many splats go to the same pixels, merge them to keep the memory in check.
+        splats.sort_by_key(|splat| (splat.y, splat.x));
+        splats.dedup_by(|splat, kept| {
+            let same = (splat.x, splat.y) == (kept.x, kept.y);
+            if same {
+                kept.color += &splat.color;
+            }
+            same
+        });
+
+        (pixels, stats, splats)
+    }
+
+    fn store_tile(&self, film: &mut Film, tile: &Tile, pixels: Vec<Pixel>) {
+        let w = tile.x1 - tile.x0;
+
+        for (n, pixel) in pixels.into_iter().enumerate() {
+            let (x, y) = (tile.x0 + n as u32 % w, tile.y0 + n as u32 / w);
+            film.set(x, y, pixel.color);
+            film.set_alpha(x, y, pixel.alpha);
+            film.set_depth(x, y, pixel.depth);
+        }
     }
 
     fn sample_square(&self) -> Vector3 {
@@ -132,51 +795,107 @@ impl Camera {
         )
     }
 
-    fn defocus_disk_sample(&self) -> Point3 {
-        // Returns a random point in the camera defocus disk.
+    fn defocus_disk_sample(&self, center: &Point3) -> Point3 {
+        // Returns a random point in the camera defocus disk around the center.
         let p = Vector3::new_random_in_unit_disk();
-        &self.position + &(p.x() * &self.defocus_disk_u) + &(p.y() * &self.defocus_disk_v)
+        center + &(p.x() * &self.defocus_disk_u) + &(p.y() * &self.defocus_disk_v)
+    }
+
+    // Pixel of the view of an eye at the pixel i, j of the image, with the side of the eye: -1
+    // for the left one, 1 for the right one and 0 without stereo.
+    fn eye(&self, i: u32, j: u32) -> (u32, u32, f64) {
+        match self.stereo.map(|stereo| stereo.layout) {
+            None => (i, j, 0.0),
+            Some(Layout::SideBySide) if i < self.img_w => (i, j, -1.0),
+            Some(Layout::SideBySide) => (i - self.img_w, j, 1.0),
+            Some(Layout::TopBottom) if j < self.img_h => (i, j, -1.0),
+            Some(Layout::TopBottom) => (i, j - self.img_h, 1.0),
+        }
+    }
+
+    // Offset from the camera position of the eye on the side, looking along the direction.
+    fn eye_offset(&self, side: f64, direction: &Vector3) -> Vector3 {
+        let Some(stereo) = self.stereo else {
+            return Vector3::default();
+        };
+        let (right, up, _) = self.basis();
+
+        let across = match self.projection {
+            // Eyes turn around the vertical with the view, and meet looking straight up or down.
+            Projection::Equirectangular => {
+                let across = direction.cross(&up);
+                let norm = across.norm();
+                if norm <= 0.0 {
+                    return Vector3::default();
+                }
+                across / norm
+            }
+            _ => right,
+        };
+
+        (side * stereo.ipd / 2.0) * across
     }
 
-    fn get_ray(&self, i: u32, j: u32) -> Ray {
-        // Construct a camera ray originating from the defocus disk and directed at a randomly
-        // sampled point around the pixel location i, j.
+    // Camera ray through a randomly sampled point around the pixel location i, j, if the
+    // projection covers it.
+    fn get_ray(&self, i: u32, j: u32) -> Option<Ray> {
+        let (i, j, side) = self.eye(i, j);
+        let convergence = self
+            .stereo
+            .map_or(f64::INFINITY, |stereo| stereo.convergence);
         let offset = self.sample_square();
         let pixel_sample = self.viewport.origin()
             + ((f64::from(i) + offset.x()) * self.viewport.du())
             + ((f64::from(j) + offset.y()) * self.viewport.dv());
 
-        let ray_origin: Point3 = if self.defocus_angle <= 0.0 {
-            self.position.clone()
-        } else {
-            self.defocus_disk_sample()
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => {
+                // Rays originate from the defocus disk around the eye, towards the point of the
+                // focus plane where the views of the pixel sample meet at the convergence
+                // distance.
+                let eye = self.eye_offset(side, &Vector3::default());
+                let focus = &pixel_sample + (1.0 - self.focus_dist / convergence) * &eye;
+                let eye = &self.position + eye;
+                let origin = if self.defocus_angle <= 0.0 {
+                    eye
+                } else {
+                    self.defocus_disk_sample(&eye)
+                };
+                let direction = Vector3::from(&focus - &origin);
+                (origin, direction)
+            }
+            Projection::Orthographic => {
+                // Rays originate from the plane of the camera position, across the view.
+                let (_, _, forward) = self.basis();
+                let eye = self.eye_offset(side, &forward);
+                let origin = &pixel_sample - self.focus_dist * &forward + &eye;
+                (origin, forward - eye / convergence)
+            }
+            Projection::Fisheye { .. } | Projection::Equirectangular => {
+                let x = f64::from(i) + 0.5 + offset.x();
+                let y = f64::from(j) + 0.5 + offset.y();
+                let direction = self.image_direction(x, y)?;
+                let eye = self.eye_offset(side, &direction);
+                (&self.position + &eye, direction - eye / convergence)
+            }
         };
-        Ray::with_motion(
-            ray_origin.clone(),
-            Vector3::from(&pixel_sample - &ray_origin),
-            rng::random(),
-        )
+
+        Some(Ray::with_motion(origin, direction, rng::random()))
     }
+}
 
-    fn ray_color(&self, ray: &Ray, depth: usize, world: &HittableList) -> Color {
-        if depth == 0 {
-            return Color::default();
-        }
+fn pass_seed(seed: u64, pass: usize) -> u64 {
+    // The first pass keeps the seed, so that single pass images do not depend on the passes.
+    seed ^ (pass as u64).wrapping_mul(0xd1b5_4a32_d192_ed03)
+}
 
-        match world.hit(ray, &Interval::new(0.001, f64::INFINITY)) {
-            Some(rec) => match rec.mat.scatter(ray, &rec) {
-                Some((scattered, attenuation)) => {
-                    attenuation * self.ray_color(&scattered, depth - 1, world)
-                }
-                None => Color::default(),
-            },
-            None => {
-                let a = 0.5_f64 * (ray.direction().normalise().y() + 1.0_f64);
+fn tile_seed(seed: u64, n: u64) -> u64 {
+    // SplitMix64 finaliser, spreads consecutive tile indices over the whole seed space.
+    let mut z = seed.wrapping_add(n.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
 
-                (1.0 - a) * &Color::new(1.0, 1.0, 1.0) + a * &Color::new(0.5, 0.7, 1.0)
-            }
-        }
-    }
+    z ^ (z >> 31)
 }
 
 impl Viewport {
@@ -219,3 +938,292 @@ impl Viewport {
         &self.origin
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::material::{diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal};
+    use crate::objects::sphere::Sphere;
+
+    fn scene() -> (Camera, HittableList) {
+        let mut camera = Camera::new(
+            Point3::new(0.0, 1.0, 4.0),
+            Point3::default(),
+            Vector3::new(0.0, 1.0, 0.0),
+            4.0 / 3.0,
+            40,
+            40.0,
+            4.0,
+            0.5,
+        );
+        camera
+            .set_progress(false)
+            .set_antialiasing(4)
+            .set_maximum_depth(8)
+            .set_tile_size(7);
+
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0.0, -100.5, 0.0),
+            100.0,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )));
+        world.add(Arc::new(Sphere::with_motion(
+            Point3::default(),
+            Point3::new(0.0, 0.2, 0.0),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3)),
+        )));
+
+        (camera, world)
+    }
+
+    #[test]
+    fn test_tiles_cover_image() {
+        let (camera, _) = scene();
+        let covered: u32 = camera
+            .tiles()
+            .iter()
+            .map(|t| (t.x1 - t.x0) * (t.y1 - t.y0))
+            .sum();
+
+        assert_eq!(covered, camera.img_w * camera.img_h);
+    }
+
+    #[test]
+    fn test_threads_match_single_thread() {
+        let (mut camera, world) = scene();
+
+        camera.set_seed(7).set_threads(1);
+        let single = camera.render(&world);
+
+        camera.set_threads(4);
+        let multi = camera.render(&world);
+
+        assert_eq!(single, multi);
+    }
+
+    #[test]
+    fn test_emission_and_background() {
+        let (mut camera, _) = scene();
+        camera.set_background(Background::Solid(Color::new(0.25, 0.5, 1.0)));
+
+        // A light surrounding the camera is the only thing seen, whatever the background.
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::default(),
+            50.0,
+            Arc::new(DiffuseLight::new(Color::new(2.0, 3.0, 4.0))),
+        )));
+        let film = camera.render(&world);
+        assert_eq!(film.get(5, 5), &Color::new(2.0, 3.0, 4.0));
+
+        let film = camera.render(&HittableList::new());
+        assert_eq!(film.get(5, 5), &Color::new(0.25, 0.5, 1.0));
+        assert_eq!(film.alpha(5, 5), 0.0);
+    }
+
+    #[test]
+    fn test_fog() {
+        let (mut camera, _) = scene();
+        camera
+            .set_background(Background::Solid(Color::new(1.0, 1.0, 1.0)))
+            .set_fog(Some(Fog::new(1e3, Color::new(0.5, 0.25, 0.0))));
+
+        // Thick fog hides the light and the sky alike.
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::default(),
+            50.0,
+            Arc::new(DiffuseLight::new(Color::new(2.0, 3.0, 4.0))),
+        )));
+        let film = camera.render(&world);
+        assert_eq!(film.get(5, 5), &Color::new(0.5, 0.25, 0.0));
+
+        camera.set_fog(Some(Fog::new(1e-3, Color::new(0.5, 0.25, 0.0))));
+        // About 50 units away, the light keeps some 95% of its radiance.
+        let film = camera.render(&world);
+        let b = film.get(5, 5).b();
+        assert!(b > 4.0 * f64::exp(-1e-3 * 55.0) && b < 4.0 * f64::exp(-1e-3 * 45.0));
+    }
+
+    #[test]
+    fn test_russian_roulette() {
+        let (mut camera, _) = scene();
+        camera
+            .set_background(Background::Solid(Color::new(1.0, 1.0, 1.0)))
+            .set_seed(3)
+            .set_antialiasing(16);
+
+        // Light bounced once off a convex grey sphere under a white sky comes back halved,
+        // which roulette must keep on average while ending half of the paths.
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::default(),
+            1.0,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )));
+
+        camera.set_minimum_depth(0);
+        let (film, stats) = camera.render_with_stats(&world, &HittableList::new());
+        let (mut sum, mut count) = (0.0, 0);
+        for y in 0..camera.image_height() {
+            for x in 0..camera.image_width() {
+                if film.alpha(x, y) == 1.0 {
+                    sum += film.get(x, y).g();
+                    count += 1;
+                }
+            }
+        }
+        assert!(count > 100);
+        assert!((sum / count as f64 - 0.5).abs() < 0.05);
+        assert!(stats.terminated() > 0);
+        assert_eq!(stats.truncated(), 0);
+        assert_eq!(stats.longest(), 1);
+
+        camera.set_minimum_depth(8).set_maximum_depth(1);
+        let (_, stats) = camera.render_with_stats(&world, &HittableList::new());
+        assert_eq!(stats.terminated(), 0);
+        assert!(stats.truncated() > 0);
+        assert_eq!(stats.histogram()[0], stats.paths() - stats.truncated());
+        assert_eq!(
+            stats.paths(),
+            u64::from(camera.image_width() * camera.image_height()) * 16
+        );
+    }
+
+    #[test]
+    fn test_projections() {
+        let mut camera = Camera::new(
+            Point3::new(1.0, 2.0, 3.0),
+            Point3::default(),
+            Vector3::new(0.0, 1.0, 0.0),
+            2.0,
+            40,
+            60.0,
+            4.0,
+            0.0,
+        );
+        rng::seed(9);
+
+        let fisheyes = [
+            Projection::Fisheye {
+                fov: 180.0,
+                mapping: Fisheye::Equidistant,
+            },
+            Projection::Fisheye {
+                fov: 220.0,
+                mapping: Fisheye::Equisolid,
+            },
+        ];
+        let coverage = [1.0, PI * 100.0 / 800.0, PI * 100.0 / 800.0, 1.0];
+        let projections = [
+            Projection::Perspective,
+            fisheyes[0],
+            fisheyes[1],
+            Projection::Equirectangular,
+        ];
+        for (projection, coverage) in projections.into_iter().zip(coverage) {
+            camera.set_projection(projection);
+            assert!(camera.is_pinhole());
+
+            // Camera rays project back onto their own pixels.
+            for (i, j) in [(0, 0), (13, 7), (20, 10), (27, 15), (39, 19)] {
+                let Some(ray) = camera.get_ray(i, j) else {
+                    assert_ne!((i, j), (20, 10));
+                    continue;
+                };
+                assert_eq!(camera.raster(&ray.at(2.0)), Some((i, j)));
+            }
+
+            // The density of the directions adds up to the share of the image they cover.
+            let (steps_theta, steps_phi) = (200, 400);
+            let (d_theta, d_phi) = (PI / steps_theta as f64, 2.0 * PI / steps_phi as f64);
+            let mut integral = 0.0;
+            for i in 0..steps_theta {
+                let theta = (i as f64 + 0.5) * d_theta;
+                for j in 0..steps_phi {
+                    let phi = (j as f64 + 0.5) * d_phi;
+                    let d = Vector3::new(
+                        theta.sin() * phi.cos(),
+                        theta.cos(),
+                        theta.sin() * phi.sin(),
+                    );
+                    integral += camera.direction_pdf(&d) * theta.sin() * d_theta * d_phi;
+                }
+            }
+            assert!((integral - coverage).abs() < 0.01 * coverage);
+        }
+
+        // Orthographic rays all go the same way, from across the plane of the camera.
+        camera.set_projection(Projection::Orthographic);
+        assert!(!camera.is_pinhole());
+        let forward = Vector3::from(Point3::new(-1.0, -2.0, -3.0)).normalise();
+        let a = camera.get_ray(0, 0).unwrap();
+        let b = camera.get_ray(39, 19).unwrap();
+        assert!((a.direction().dot(&forward) - 1.0).abs() < 1e-12);
+        assert!((b.direction().dot(&forward) - 1.0).abs() < 1e-12);
+        let across = Vector3::from(b.origin() - a.origin());
+        assert!(across.dot(&forward).abs() < 1e-12);
+        assert!(across.norm() > 4.0);
+        assert_eq!(camera.raster(&Point3::default()), None);
+    }
+
+    #[test]
+    fn test_stereo() {
+        let mut camera = Camera::new(
+            Point3::new(0.0, 0.0, 5.0),
+            Point3::default(),
+            Vector3::new(0.0, 1.0, 0.0),
+            2.0,
+            40,
+            60.0,
+            5.0,
+            0.0,
+        );
+        camera.set_stereo(Some(Stereo {
+            layout: Layout::SideBySide,
+            ipd: 0.5,
+            convergence: 8.0,
+        }));
+        assert_eq!(camera.frame_size(), (80, 20));
+        assert!(!camera.is_pinhole());
+
+        // Rays through the same pixel of both views leave from either eye, and meet at the
+        // convergence distance.
+        let ray = |camera: &Camera, i: u32, j: u32| {
+            rng::seed(4);
+            camera.get_ray(i, j).unwrap()
+        };
+        let (left, right) = (ray(&camera, 13, 7), ray(&camera, 53, 7));
+        assert_eq!(left.origin(), &Point3::new(-0.25, 0.0, 5.0));
+        assert_eq!(right.origin(), &Point3::new(0.25, 0.0, 5.0));
+        let meeting = |ray: &Ray| ray.at(8.0 / -ray.direction().z());
+        let gap = Vector3::from(meeting(&left) - meeting(&right));
+        assert!(gap.norm() < 1e-12);
+
+        // Omnidirectional stereo eyes turn with the view, across every ray.
+        camera
+            .set_projection(Projection::Equirectangular)
+            .set_stereo(Some(Stereo {
+                layout: Layout::TopBottom,
+                ipd: 0.5,
+                convergence: f64::INFINITY,
+            }));
+        assert_eq!(camera.frame_size(), (40, 40));
+        for (i, j) in [(0, 3), (13, 7), (31, 15)] {
+            let (left, right) = (ray(&camera, i, j), ray(&camera, i, j + 20));
+            assert_eq!(left.direction(), right.direction());
+            for (eye, side) in [(&left, -1.0), (&right, 1.0)] {
+                let offset = Vector3::from(eye.origin() - camera.position());
+                assert!((offset.norm() - 0.25).abs() < 1e-12);
+                assert!(offset.y().abs() < 1e-12);
+                assert!(offset.dot(eye.direction()).abs() < 1e-12);
+                let turn = eye.direction().cross(&offset).y();
+                assert!(side * turn < 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use rustracer::film::ImageFormat;
+use rustracer::integrator;
+use rustracer::scene::builtin;
+
+pub const USAGE: &str = "\
+Usage: rustracer [OPTIONS] [SCENE]
+
+Renders SCENE, a JSON scene description, or a built-in scene.
+
+Options:
+  -b, --builtin NAME     Render a built-in scene (default: cover)
+  -o, --output PATH      Output image, '-' for the standard output (default: image.png)
+  -f, --format FORMAT    Output format: ppm, ppm-ascii, png, pfm, exr or hdr
+                         (default: guessed from the output extension)
+      --depth-output PATH
+                         Also write the distance to the camera of each pixel, as a PFM
+                         image or the Z channel of an EXR image
+  -w, --width PIXELS     Override the image width
+  -a, --aspect RATIO     Override the aspect ratio, as a number or W:H
+  -s, --spp COUNT        Override the samples per pixel
+  -d, --depth COUNT      Override the maximum ray bounce depth
+      --min-depth COUNT  Override the bounces before Russian roulette may end a path
+  -i, --integrator NAME  Light transport algorithm: path, bdpt, photon, sppm, whitted, ao,
+                         or the normal, albedo and depth debug views
+                         (default: set by the scene)
+      --seed SEED        Seed the random sequences for a reproducible image
+  -j, --threads COUNT    Number of rendering threads (default: all cores)
+  -q, --quiet            Do not report progress
+      --progress         Report progress on the standard error (default)
+  -h, --help             Print this help
+  -V, --version          Print the version";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    File(PathBuf),
+    Builtin(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    File(PathBuf),
+    Stdout,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub source: Source,
+    pub output: Output,
+    pub format: ImageFormat,
+    pub depth_output: Option<PathBuf>,
+    pub width: Option<u32>,
+    pub aspect_ratio: Option<f64>,
+    pub samples_per_pixel: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub min_depth: Option<usize>,
+    pub integrator: Option<String>,
+    pub seed: Option<u64>,
+    pub threads: Option<usize>,
+    pub progress: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Render(Box<Options>),
+    Help,
+    Version,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliError(pub String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+fn error<T>(message: String) -> Result<T, CliError> {
+    Err(CliError(message))
+}
+
+fn parse_format(value: &str) -> Result<ImageFormat, CliError> {
+    match value.to_ascii_lowercase().as_str() {
+        "ppm-ascii" => Ok(ImageFormat::PpmAscii),
+        other => ImageFormat::from_extension(other)
+            .map_or_else(|| error(format!("unknown output format '{}'", value)), Ok),
+    }
+}
+
+fn parse_unsigned<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, CliError> {
+    value.parse::<T>().map_or_else(
+        |_| {
+            error(format!(
+                "{} expects an unsigned integer, found '{}'",
+                option, value
+            ))
+        },
+        Ok,
+    )
+}
+
+fn parse_count<T: std::str::FromStr + PartialEq + Default>(
+    option: &str,
+    value: &str,
+) -> Result<T, CliError> {
+    match parse_unsigned::<T>(option, value) {
+        Ok(n) if n != T::default() => Ok(n),
+        _ => error(format!(
+            "{} expects a positive integer, found '{}'",
+            option, value
+        )),
+    }
+}
+
+fn parse_aspect(value: &str) -> Result<f64, CliError> {
+    let ratio = match value.split_once(':') {
+        Some((w, h)) => match (w.parse::<f64>(), h.parse::<f64>()) {
+            (Ok(w), Ok(h)) => w / h,
+            _ => f64::NAN,
+        },
+        None => value.parse::<f64>().unwrap_or(f64::NAN),
+    };
+
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return error(format!(
+            "--aspect expects a positive ratio such as 1.5 or 16:9, found '{}'",
+            value
+        ));
+    }
+
+    Ok(ratio)
+}
+
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, CliError> {
+    let mut args = args.into_iter();
+
+    let mut scene: Option<PathBuf> = None;
+    let mut builtin: Option<String> = None;
+    let mut output: Option<String> = None;
+    let mut format: Option<(ImageFormat, String)> = None;
+    let mut options = Options {
+        source: Source::Builtin("cover".to_string()),
+        output: Output::File(PathBuf::from("image.png")),
+        format: ImageFormat::Png,
+        depth_output: None,
+        width: None,
+        aspect_ratio: None,
+        samples_per_pixel: None,
+        max_depth: None,
+        min_depth: None,
+        integrator: None,
+        seed: None,
+        threads: None,
+        progress: true,
+    };
+
+    while let Some(arg) = args.next() {
+        // Accept both "--option value" and "--option=value".
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || match inline {
+            Some(v) => Ok(v.to_string()),
+            None => args
+                .next()
+                .map_or_else(|| error(format!("{} expects a value", name)), Ok),
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-b" | "--builtin" => builtin = Some(value()?),
+            "-o" | "--output" => output = Some(value()?),
+            "-f" | "--format" => {
+                let v = value()?;
+                format = Some((parse_format(&v)?, v))
+            }
+            "--depth-output" => {
+                let v = value()?;
+                let path = PathBuf::from(&v);
+                if !matches!(
+                    ImageFormat::from_path(&path),
+                    Some(ImageFormat::Pfm | ImageFormat::Exr)
+                ) {
+                    return error(format!(
+                        "--depth-output expects a .pfm or .exr path, found '{}'",
+                        v
+                    ));
+                }
+                options.depth_output = Some(path)
+            }
+            "-w" | "--width" => options.width = Some(parse_count("--width", &value()?)?),
+            "-a" | "--aspect" => options.aspect_ratio = Some(parse_aspect(&value()?)?),
+            "-s" | "--spp" => options.samples_per_pixel = Some(parse_count("--spp", &value()?)?),
+            "-d" | "--depth" => options.max_depth = Some(parse_unsigned("--depth", &value()?)?),
+            "--min-depth" => options.min_depth = Some(parse_unsigned("--min-depth", &value()?)?),
+            "-i" | "--integrator" => {
+                let v = value()?;
+                if !integrator::NAMES.contains(&v.as_str()) {
+                    return error(format!(
+                        "unknown integrator '{}', expected one of: {}",
+                        v,
+                        integrator::NAMES.join(", ")
+                    ));
+                }
+                options.integrator = Some(v)
+            }
+            "--seed" => options.seed = Some(parse_unsigned("--seed", &value()?)?),
+            "-j" | "--threads" => options.threads = Some(parse_count("--threads", &value()?)?),
+            "-q" | "--quiet" => options.progress = false,
+            "--progress" => options.progress = true,
+            _ if arg.starts_with('-') && arg != "-" => {
+                return error(format!("unknown option '{}'", arg))
+            }
+            _ => {
+                if scene.is_some() {
+                    return error(format!("unexpected argument '{}'", arg));
+                }
+                scene = Some(PathBuf::from(arg));
+            }
+        }
+    }
+
+    options.source = match (scene, builtin) {
+        (Some(_), Some(_)) => {
+            return error("a scene file and --builtin cannot be used together".to_string())
+        }
+        (Some(path), None) => Source::File(path),
+        (None, Some(name)) => {
+            if !builtin::NAMES.contains(&name.as_str()) {
+                return error(format!(
+                    "unknown built-in scene '{}', expected one of: {}",
+                    name,
+                    builtin::NAMES.join(", ")
+                ));
+            }
+            Source::Builtin(name)
+        }
+        (None, None) => Source::Builtin("cover".to_string()),
+    };
+
+    options.output = match output.as_deref() {
+        Some("-") => Output::Stdout,
+        Some(path) => Output::File(PathBuf::from(path)),
+        None => Output::File(PathBuf::from("image.png")),
+    };
+
+    let guessed = match &options.output {
+        Output::File(path) => ImageFormat::from_path(path),
+        Output::Stdout => None,
+    };
+    options.format = match (format, guessed) {
+        (Some((f, name)), Some(g))
+            if f != g && !(f == ImageFormat::PpmAscii && g == ImageFormat::Ppm) =>
+        {
+            return error(format!(
+                "--format {} does not match the output extension",
+                name
+            ))
+        }
+        (Some((f, _)), _) => f,
+        (None, Some(g)) => g,
+        (None, None) => {
+            return error(match &options.output {
+                Output::Stdout => "writing to the standard output requires --format".to_string(),
+                Output::File(path) => format!(
+                    "cannot guess the format of '{}', use a known extension or --format",
+                    path.display()
+                ),
+            })
+        }
+    };
+
+    Ok(Command::Render(Box::new(options)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn options(line: &str) -> Options {
+        match parse(args(line)).unwrap() {
+            Command::Render(options) => *options,
+            c => panic!("unexpected command {:?}", c),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let o = options("");
+
+        assert_eq!(o.source, Source::Builtin("cover".to_string()));
+        assert_eq!(o.output, Output::File(PathBuf::from("image.png")));
+        assert_eq!(o.format, ImageFormat::Png);
+        assert!(o.progress);
+        assert_eq!(parse(args("-s 4 --help")), Ok(Command::Help));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let o =
+            options("scenes/a.json -w 640 --aspect 4:3 --spp=16 -d 8 --min-depth 0 -i ao --seed 3 -j 2 -q -o out.exr --depth-output z.pfm");
+
+        assert_eq!(o.source, Source::File(PathBuf::from("scenes/a.json")));
+        assert_eq!(o.format, ImageFormat::Exr);
+        assert_eq!(o.depth_output, Some(PathBuf::from("z.pfm")));
+        assert_eq!(o.width, Some(640));
+        assert_eq!(o.aspect_ratio, Some(4.0 / 3.0));
+        assert_eq!(o.samples_per_pixel, Some(16));
+        assert_eq!(o.max_depth, Some(8));
+        assert_eq!(o.min_depth, Some(0));
+        assert_eq!(o.integrator, Some("ao".to_string()));
+        assert_eq!(o.seed, Some(3));
+        assert_eq!(o.threads, Some(2));
+        assert!(!o.progress);
+
+        // As in scene files, no bounce at all is allowed.
+        assert_eq!(options("-d 0").max_depth, Some(0));
+
+        let o = options("-o - -f ppm");
+        assert_eq!(o.output, Output::Stdout);
+        assert_eq!(o.format, ImageFormat::Ppm);
+    }
+
+    #[test]
+    fn test_errors() {
+        for line in [
+            "a.json -b cover",
+            "-b nothing",
+            "-i photons",
+            "-w 0",
+            "-w -3",
+            "-s many",
+            "-d -1",
+            "--min-depth -1",
+            "--seed x",
+            "--aspect 16:0",
+            "-j",
+            "-o out.png -f exr",
+            "-o out.tiff",
+            "--depth-output z.png",
+            "-o -",
+            "--frobnicate",
+            "a.json b.json",
+        ] {
+            assert!(parse(args(line)).is_err(), "accepted '{}'", line);
+        }
+    }
+}
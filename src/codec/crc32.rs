@@ -0,0 +1,73 @@
+const POLYNOMIAL: u32 = 0xedb8_8320;
+
+const fn make_table() -> [u32; 256] {
+    let mut table = [0_u32; 256];
+    let mut n = 0;
+
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                POLYNOMIAL ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+
+    table
+}
+
+const TABLE: [u32; 256] = make_table();
+
+#[derive(Debug, Clone)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32 { crc: 0xffff_ffff }
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        for byte in data {
+            self.crc = TABLE[((self.crc ^ u32::from(*byte)) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+
+        self
+    }
+
+    pub fn finish(&self) -> u32 {
+        self.crc ^ 0xffff_ffff
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Crc32 {
+        Crc32::new()
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    Crc32::new().update(data).finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(
+            Crc32::new().update(b"1234").update(b"56789").finish(),
+            0xcbf4_3926
+        );
+    }
+}
@@ -0,0 +1,522 @@
+// Minimal zlib (RFC 1950) stream encoder and decoder. Data is compressed as a single DEFLATE
+// (RFC 1951) block with the fixed Huffman codes, after LZ77 matching over hash chains. The decoder
+// handles every block type.
+
+use std::io;
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> BitWriter {
+        BitWriter {
+            out,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, count: u32) {
+        // Values are packed starting from the least significant bit.
+        self.bits |= u64::from(value) << self.count;
+        self.count += count;
+
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn write_code(&mut self, code: u32, count: u32) {
+        // Huffman codes are packed starting from their most significant bit.
+        let reversed = code.reverse_bits() >> (32 - count);
+        self.write(reversed, count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+
+        self.out
+    }
+}
+
+fn write_literal(writer: &mut BitWriter, symbol: u16) {
+    let symbol = u32::from(symbol);
+
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let lcode = LENGTH_BASE
+        .iter()
+        .rposition(|&b| usize::from(b) <= length)
+        .unwrap_or(0);
+    write_literal(writer, 257 + lcode as u16);
+    writer.write(
+        (length - usize::from(LENGTH_BASE[lcode])) as u32,
+        u32::from(LENGTH_EXTRA[lcode]),
+    );
+
+    let dcode = DIST_BASE
+        .iter()
+        .rposition(|&b| usize::from(b) <= distance)
+        .unwrap_or(0);
+    writer.write_code(dcode as u32, 5);
+    writer.write(
+        (distance - usize::from(DIST_BASE[dcode])) as u32,
+        u32::from(DIST_EXTRA[dcode]),
+    );
+}
+
+fn hash(data: &[u8], i: usize) -> usize {
+    let h = (u32::from(data[i]) << 16) | (u32::from(data[i + 1]) << 8) | u32::from(data[i + 2]);
+
+    (h.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
+
+fn insert(data: &[u8], head: &mut [usize], prev: &mut [usize], i: usize) {
+    if i + MIN_MATCH <= data.len() {
+        let h = hash(data, i);
+        prev[i % WINDOW_SIZE] = head[h];
+        head[h] = i;
+    }
+}
+
+fn deflate(data: &[u8], writer: &mut BitWriter) {
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+
+    // Final block, fixed Huffman codes.
+    writer.write(1, 1);
+    writer.write(1, 2);
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+
+        if i + MIN_MATCH <= data.len() {
+            let max_len = usize::min(MAX_MATCH, data.len() - i);
+            let mut candidate = head[hash(data, i)];
+            let mut chain = 0;
+
+            while candidate != usize::MAX && i - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[i..i + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+
+                let next = prev[candidate % WINDOW_SIZE];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            write_match(writer, best_len, best_dist);
+            for k in i..i + best_len {
+                insert(data, &mut head, &mut prev, k);
+            }
+            i += best_len;
+        } else {
+            write_literal(writer, u16::from(data[i]));
+            insert(data, &mut head, &mut prev, i);
+            i += 1;
+        }
+    }
+
+    // End of block.
+    write_literal(writer, 256);
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (mut a, mut b) = (1_u32, 0_u32);
+
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+
+    (b << 16) | a
+}
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    // CMF: deflate with a 32K window, FLG: default compression level, no dictionary.
+    let mut writer = BitWriter::new(vec![0x78, 0x9c]);
+
+    deflate(data, &mut writer);
+
+    let mut out = writer.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+
+    out
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bits: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            position: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn read(&mut self, count: u32) -> io::Result<u32> {
+        while self.count < count {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| invalid("unexpected end of compressed data"))?;
+            self.bits |= u64::from(byte) << self.count;
+            self.position += 1;
+            self.count += 8;
+        }
+
+        let value = (self.bits & ((1 << count) - 1)) as u32;
+        self.bits >>= count;
+        self.count -= count;
+
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        // Drop the bits left in the current byte.
+        let partial = self.count % 8;
+        self.bits >>= partial;
+        self.count -= partial;
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// Canonical Huffman code, as the number of codes of each length and the symbols sorted by code.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut counts = [0_u16; 16];
+        for &length in lengths {
+            counts[usize::from(length)] += 1;
+        }
+        counts[0] = 0;
+
+        // Reject over-subscribed codes; incomplete ones are allowed for single symbol codes.
+        let mut left = 1_i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(invalid("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0_u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[usize::from(offsets[usize::from(length)])] = symbol as u16;
+                offsets[usize::from(length)] += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        // Codes are read one bit at a time from their most significant bit.
+        let (mut code, mut first, mut index) = (0_i32, 0_i32, 0_i32);
+
+        for length in 1..16 {
+            code |= reader.read(1)? as i32;
+            let count = i32::from(self.counts[length]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(invalid("invalid Huffman code"))
+    }
+}
+
+fn fixed_codes() -> io::Result<(Huffman, Huffman)> {
+    let mut lengths = [0_u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    const ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+
+    let literals = reader.read(5)? as usize + 257;
+    let distances = reader.read(5)? as usize + 1;
+    let code_lengths = reader.read(4)? as usize + 4;
+    if literals > 286 || distances > 30 {
+        return Err(invalid("too many length or distance codes"));
+    }
+
+    let mut lengths = [0_u8; 19];
+    for &symbol in &ORDER[..code_lengths] {
+        lengths[symbol] = reader.read(3)? as u8;
+    }
+    let lencode = Huffman::new(&lengths)?;
+
+    let mut lengths = vec![0_u8; literals + distances];
+    let mut n = 0;
+    while n < lengths.len() {
+        let symbol = lencode.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => match n {
+                0 => return Err(invalid("repeated length without a previous one")),
+                _ => (lengths[n - 1], 3 + reader.read(2)? as usize),
+            },
+            17 => (0, 3 + reader.read(3)? as usize),
+            _ => (0, 11 + reader.read(7)? as usize),
+        };
+        if n + repeat > lengths.len() {
+            return Err(invalid("too many code lengths"));
+        }
+        lengths[n..n + repeat].fill(value);
+        n += repeat;
+    }
+
+    if lengths[256] == 0 {
+        return Err(invalid("missing end of block code"));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literals])?,
+        Huffman::new(&lengths[literals..])?,
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> io::Result<()> {
+    loop {
+        let symbol = literals.decode(reader)?;
+
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let l = usize::from(symbol - 257);
+                let length =
+                    usize::from(LENGTH_BASE[l]) + reader.read(u32::from(LENGTH_EXTRA[l]))? as usize;
+
+                let d = usize::from(distances.decode(reader)?);
+                if d >= DIST_BASE.len() {
+                    return Err(invalid("invalid distance code"));
+                }
+                let distance =
+                    usize::from(DIST_BASE[d]) + reader.read(u32::from(DIST_EXTRA[d]))? as usize;
+                if distance > out.len() {
+                    return Err(invalid("distance too far back"));
+                }
+
+                // Copies may overlap the bytes they produce.
+                let start = out.len() - distance;
+                for k in 0..length {
+                    out.push(out[start + k]);
+                }
+            }
+            _ => return Err(invalid("invalid literal or length code")),
+        }
+    }
+}
+
+fn inflate(reader: &mut BitReader) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+
+    loop {
+        let last = reader.read(1)? == 1;
+
+        match reader.read(2)? {
+            0 => {
+                reader.align();
+                let length = reader.read(16)?;
+                let complement = reader.read(16)?;
+                if length != !complement & 0xffff {
+                    return Err(invalid("corrupted stored block length"));
+                }
+                for _ in 0..length {
+                    out.push(reader.read(8)? as u8);
+                }
+            }
+            1 => {
+                let (literals, distances) = fixed_codes()?;
+                inflate_block(reader, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(reader)?;
+                inflate_block(reader, &mut out, &literals, &distances)?;
+            }
+            _ => return Err(invalid("invalid block type")),
+        }
+
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+pub fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < 6 || (u16::from(data[0]) << 8 | u16::from(data[1])) % 31 != 0 {
+        return Err(invalid("invalid zlib header"));
+    }
+    if data[0] & 0x0f != 8 {
+        return Err(invalid("unsupported zlib compression method"));
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(invalid("preset zlib dictionaries are not supported"));
+    }
+
+    let mut reader = BitReader::new(&data[2..]);
+    let out = inflate(&mut reader)?;
+
+    let end = 2 + reader.position;
+    match data.get(end..end + 4) {
+        Some(checksum) if checksum == adler32(&out).to_be_bytes() => Ok(out),
+        Some(_) => Err(invalid("zlib checksum mismatch")),
+        None => Err(invalid("missing zlib checksum")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_compress_repetitive() {
+        let data = vec![42_u8; 100_000];
+        let out = compress(&data);
+
+        assert_eq!(&out[..2], &[0x78, 0x9c]);
+        assert!(out.len() < 1000);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..50_000_u32).map(|i| (i * i % 251 % 19) as u8).collect();
+
+        assert_eq!(decompress(&compress(&data)).unwrap(), data);
+        assert_eq!(decompress(&compress(b"")).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_decompress_dynamic_and_stored() {
+        // Produced by zlib at level 9, with a dynamic Huffman block.
+        let dynamic = [
+            0x78, 0xda, 0xed, 0xcf, 0x49, 0x6e, 0x04, 0x31, 0x08, 0x40, 0xd1, 0xb3, 0x82, 0x01,
+            0x63, 0x9b, 0xf1, 0xfe, 0x9b, 0x54, 0x29, 0xbd, 0xe8, 0x5c, 0x21, 0xf2, 0x16, 0xa1,
+            0x0f, 0x0f, 0x90, 0x77, 0xaf, 0x51, 0x11, 0x35, 0x56, 0x6f, 0x16, 0x96, 0x15, 0xe4,
+            0x67, 0x32, 0xcf, 0x24, 0x63, 0x82, 0x06, 0xf5, 0x51, 0x5b, 0x54, 0x37, 0xcc, 0xb4,
+            0x49, 0x53, 0x73, 0x84, 0x74, 0x27, 0x92, 0x81, 0x0b, 0x23, 0x8b, 0xb3, 0x29, 0x22,
+            0xd1, 0x8e, 0x89, 0x76, 0xec, 0x94, 0x1c, 0x06, 0xb3, 0x80, 0x71, 0xa8, 0x5c, 0x97,
+            0xf5, 0x34, 0xc1, 0x6e, 0x14, 0xad, 0xf5, 0x26, 0x69, 0x95, 0x62, 0xba, 0x27, 0x7e,
+            0x0d, 0x7e, 0x37, 0x6c, 0xb6, 0x2d, 0xf5, 0xa2, 0x33, 0x20, 0xcc, 0x80, 0x8f, 0xd4,
+            0x93, 0x34, 0x9c, 0xb1, 0x89, 0x10, 0xd5, 0xf8, 0x73, 0x14, 0x8c, 0x30, 0xbb, 0x25,
+            0x46, 0xea, 0xf3, 0x96, 0xe5, 0x84, 0xad, 0x2a, 0xbb, 0x86, 0xeb, 0x9b, 0x64, 0xa3,
+            0x7c, 0x1d, 0xc7, 0x29, 0xd6, 0x03, 0xfb, 0x2b, 0x45, 0xb8, 0xf4, 0x4b, 0xbf, 0xf4,
+            0x4b, 0xbf, 0xf4, 0x4b, 0xff, 0x2f, 0xf4, 0x1f, 0x6d, 0x95, 0x31, 0x5a,
+        ];
+        let data: Vec<u8> = (0..2000_u32)
+            .map(|i| (i * i % 251 % 17) as u8 + b'a')
+            .collect();
+        assert_eq!(decompress(&dynamic).unwrap(), data);
+
+        let stored = [
+            0x78, 0x01, 0x01, 0x0c, 0x00, 0xf3, 0xff, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x64, 0x20,
+            0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x1f, 0x80, 0x04, 0xbd,
+        ];
+        assert_eq!(decompress(&stored).unwrap(), b"stored block");
+
+        let mut corrupted = stored;
+        corrupted[10] ^= 1;
+        assert!(decompress(&corrupted).is_err());
+        assert!(decompress(&dynamic[..80]).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::film::Film;
+use crate::interval::Interval;
+use crate::material::color::Color;
+use crate::objects::aabb::Aabb;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::rng;
+
+// Light arriving from infinitely far away, as seen in an equirectangular image: columns span
+// the full turn around the vertical axis with the middle column towards -z and the right edge
+// turning towards +x, rows go from the zenith at the top to the nadir at the bottom.
+//
+// As a background it gives the radiance of the escaping rays. As a light it draws directions
+// in proportion to the luminance of the image, so that small bright sources such as the sun
+// are found without waiting for rays to escape towards them; it is never hit, and must not be
+// part of the world.
+pub struct EnvironmentLight {
+    image: Film,
+    intensity: f64,             // Scale of the image radiance
+    rotation: f64,              // Turn of the image around the vertical axis, in degrees
+    rows: Distribution,         // Choice of a row
+    columns: Vec<Distribution>, // Choice of a column within each row
+    bbox: Aabb,
+}
+
+// Piecewise constant distribution over the indices of its weights.
+#[derive(Debug, Clone)]
+struct Distribution {
+    cdf: Vec<f64>,
+}
+
+impl Distribution {
+    // Uniform when no weight is positive.
+    fn new(weights: impl Iterator<Item = f64>) -> Distribution {
+        let mut cdf = Vec::new();
+        let mut total = 0.0;
+        for weight in weights {
+            total += f64::max(weight, 0.0);
+            cdf.push(total);
+        }
+
+        let n = cdf.len() as f64;
+        for (i, c) in cdf.iter_mut().enumerate() {
+            *c = if total > 0.0 {
+                *c / total
+            } else {
+                (i + 1) as f64 / n
+            };
+        }
+
+        Distribution { cdf }
+    }
+
+    fn sample(&self, u: f64) -> usize {
+        let i = self.cdf.partition_point(|&c| c <= u);
+
+        usize::min(i, self.cdf.len() - 1)
+    }
+
+    fn probability(&self, i: usize) -> f64 {
+        match i {
+            0 => self.cdf[0],
+            _ => self.cdf[i] - self.cdf[i - 1],
+        }
+    }
+}
+
+impl EnvironmentLight {
+    pub fn new(image: Film) -> EnvironmentLight {
+        let (w, h) = (image.width(), image.height());
+        let row_weights: Vec<Vec<f64>> = (0..h)
+            .map(|y| {
+                // Rows near the poles cover less of the sphere.
+                let sin = f64::sin(PI * (f64::from(y) + 0.5) / f64::from(h));
+                (0..w).map(|x| sin * image.get(x, y).luminance()).collect()
+            })
+            .collect();
+
+        EnvironmentLight {
+            rows: Distribution::new(row_weights.iter().map(|row| row.iter().sum())),
+            columns: row_weights
+                .into_iter()
+                .map(|row| Distribution::new(row.into_iter()))
+                .collect(),
+            image,
+            intensity: 1.0,
+            rotation: 0.0,
+            bbox: Aabb::empty(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<EnvironmentLight> {
+        Ok(EnvironmentLight::new(Film::load(path)?))
+    }
+
+    pub fn set_intensity(&mut self, intensity: f64) -> &mut Self {
+        self.intensity = intensity;
+
+        self
+    }
+
+    pub fn set_rotation(&mut self, degrees: f64) -> &mut Self {
+        self.rotation = degrees;
+
+        self
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    pub fn image(&self) -> &Film {
+        &self.image
+    }
+
+    // Radiance arriving from the direction.
+    pub fn radiance(&self, direction: &Vector3) -> Color {
+        match self.pixel(direction) {
+            Some((x, y, _)) => self.intensity * self.image.get(x, y),
+            None => Color::default(),
+        }
+    }
+
+    // Image coordinates of the direction, with the sine of its angle to the vertical.
+    fn pixel(&self, direction: &Vector3) -> Option<(u32, u32, f64)> {
+        let (w, h) = (self.image.width(), self.image.height());
+        let length = direction.norm();
+        if w == 0 || h == 0 || length <= 0.0 {
+            return None;
+        }
+
+        let (sin, cos) = f64::to_radians(self.rotation).sin_cos();
+        let d = direction / length;
+        let (x, z) = (cos * d.x() + sin * d.z(), -sin * d.x() + cos * d.z());
+        let theta = f64::acos(Interval::new(-1.0, 1.0).clamp(d.y()));
+        let u = 0.5 + f64::atan2(x, -z) / (2.0 * PI);
+        let v = theta / PI;
+
+        let column = ((u * f64::from(w)) as u32).min(w - 1);
+        let row = ((v * f64::from(h)) as u32).min(h - 1);
+        Some((column, row, theta.sin()))
+    }
+
+    // Density by solid angle of the directions drawn by sample.
+    pub fn pdf(&self, direction: &Vector3) -> f64 {
+        let Some((x, y, sin)) = self.pixel(direction) else {
+            return 0.0;
+        };
+        if sin <= 0.0 {
+            return 0.0;
+        }
+
+        let (w, h) = (
+            f64::from(self.image.width()),
+            f64::from(self.image.height()),
+        );
+        let probability =
+            self.rows.probability(y as usize) * self.columns[y as usize].probability(x as usize);
+
+        probability * w * h / (2.0 * PI * PI * sin)
+    }
+
+    // Unit direction drawn in proportion to the luminance arriving from it.
+    pub fn sample(&self) -> Vector3 {
+        let (w, h) = (self.image.width(), self.image.height());
+        if w == 0 || h == 0 {
+            return Vector3::new(0.0, 1.0, 0.0);
+        }
+
+        let y = self.rows.sample(rng::random());
+        let x = self.columns[y].sample(rng::random());
+        let u = (x as f64 + rng::random()) / f64::from(w);
+        let v = (y as f64 + rng::random()) / f64::from(h);
+
+        let (phi, theta) = (2.0 * PI * (u - 0.5), PI * v);
+        let (x, z) = (theta.sin() * phi.sin(), -theta.sin() * phi.cos());
+        let (sin, cos) = f64::to_radians(self.rotation).sin_cos();
+
+        Vector3::new(cos * x - sin * z, theta.cos(), sin * x + cos * z)
+    }
+}
+
+impl Hittable for EnvironmentLight {
+    fn hit(&self, _ray: &Ray, _rayt: &Interval) -> Option<HitRecord> {
+        None
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, _origin: &Point3, direction: &Vector3) -> f64 {
+        self.pdf(direction)
+    }
+
+    fn random(&self, _origin: &Point3) -> Vector3 {
+        self.sample()
+    }
+}
+
+impl fmt::Debug for EnvironmentLight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EnvironmentLight")
+            .field("width", &self.image.width())
+            .field("height", &self.image.height())
+            .field("intensity", &self.intensity)
+            .field("rotation", &self.rotation)
+            .finish()
+    }
+}
+
+impl PartialEq for EnvironmentLight {
+    fn eq(&self, other: &EnvironmentLight) -> bool {
+        self.image == other.image
+            && self.intensity == other.intensity
+            && self.rotation == other.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environment() -> EnvironmentLight {
+        // A dim sky with a bright spot just above the horizon, towards -z.
+        let mut image = Film::new(16, 8);
+        for y in 0..8 {
+            for x in 0..16 {
+                image.set(x, y, Color::new(0.1, 0.2, 0.4));
+            }
+        }
+        image.set(8, 3, Color::new(100.0, 100.0, 100.0));
+
+        EnvironmentLight::new(image)
+    }
+
+    #[test]
+    fn test_radiance() {
+        let mut light = environment();
+        let spot = Vector3::new(0.05, 0.1, -1.0);
+        assert_eq!(light.radiance(&spot), Color::new(100.0, 100.0, 100.0));
+        assert_eq!(
+            light.radiance(&Vector3::new(0.0, 1.0, 0.0)),
+            Color::new(0.1, 0.2, 0.4)
+        );
+
+        // Turning the image a quarter turn brings the spot to +x.
+        light.set_rotation(90.0).set_intensity(2.0);
+        assert_eq!(
+            light.radiance(&Vector3::new(1.0, 0.1, 0.05)),
+            Color::new(200.0, 200.0, 200.0)
+        );
+        assert_eq!(light.radiance(&spot), Color::new(0.2, 0.4, 0.8));
+    }
+
+    #[test]
+    fn test_sampling() {
+        let mut light = environment();
+        light.set_rotation(30.0);
+        rng::seed(3);
+
+        // The bright spot is drawn most of the time, consistently with the density.
+        let n = 10_000;
+        let spot = (0..n)
+            .map(|_| light.sample())
+            .filter(|d| light.radiance(d).r() > 50.0)
+            .count();
+        assert!(spot > n / 2);
+
+        // The density integrates to one over the sphere.
+        let (steps_theta, steps_phi) = (200, 400);
+        let (d_theta, d_phi) = (PI / steps_theta as f64, 2.0 * PI / steps_phi as f64);
+        let mut integral = 0.0;
+        for i in 0..steps_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..steps_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let d = Vector3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                integral += light.pdf(&d) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        assert!((integral - 1.0).abs() < 0.01);
+
+        // Drawn directions come back to their own pixel.
+        for _ in 0..100 {
+            let d = light.sample();
+            assert!((d.norm() - 1.0).abs() < 1e-9);
+            assert!(light.pdf(&d) > 0.0);
+        }
+    }
+}
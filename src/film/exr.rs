@@ -0,0 +1,318 @@
+use std::io::{self, Write};
+
+use crate::codec::zlib;
+
+use super::Film;
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const VERSION: [u8; 4] = [2, 0, 0, 0]; // Single part scanline image
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Zip, // Deflate over blocks of 16 scanlines
+}
+
+impl Compression {
+    fn id(&self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zip => 3,
+        }
+    }
+
+    fn scanlines_per_block(&self) -> u32 {
+        match self {
+            Compression::None => 1,
+            Compression::Zip => 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExrOptions {
+    pub compression: Compression,
+    pub alpha: bool, // Write the coverage as the A channel
+    pub depth: bool, // Write the distance to the camera as the Z channel
+}
+
+impl Default for ExrOptions {
+    fn default() -> ExrOptions {
+        ExrOptions {
+            compression: Compression::Zip,
+            alpha: true,
+            depth: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Channel {
+    A,
+    B,
+    G,
+    R,
+    Z,
+}
+
+impl Channel {
+    fn name(&self) -> &'static str {
+        match self {
+            Channel::A => "A",
+            Channel::B => "B",
+            Channel::G => "G",
+            Channel::R => "R",
+            Channel::Z => "Z",
+        }
+    }
+
+    fn sample(&self, film: &Film, x: u32, y: u32) -> f32 {
+        (match self {
+            Channel::A => film.alpha(x, y),
+            Channel::B => film.get(x, y).b(),
+            Channel::G => film.get(x, y).g(),
+            Channel::R => film.get(x, y).r(),
+            Channel::Z => film.depth(x, y),
+        }) as f32
+    }
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+fn header(film: &Film, channels: &[Channel], compression: Compression) -> Vec<u8> {
+    let mut header = Vec::new();
+
+    let mut chlist = Vec::new();
+    for channel in channels {
+        chlist.extend_from_slice(channel.name().as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        chlist.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved
+        chlist.extend_from_slice(&1_i32.to_le_bytes()); // xSampling
+        chlist.extend_from_slice(&1_i32.to_le_bytes()); // ySampling
+    }
+    chlist.push(0);
+
+    let mut window = Vec::with_capacity(16);
+    for v in [0, 0, film.width() as i32 - 1, film.height() as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+
+    // Attributes are sorted by name, as written by the reference implementation.
+    attribute(&mut header, "channels", "chlist", &chlist);
+    attribute(
+        &mut header,
+        "compression",
+        "compression",
+        &[compression.id()],
+    );
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1_f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1_f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    header
+}
+
+fn zip(data: &[u8]) -> Vec<u8> {
+    // Bytes are split into two halves holding the even and odd positions, then delta encoded,
+    // which helps deflate with the high and low bytes of floating point samples.
+    let mut buf = Vec::with_capacity(data.len());
+    buf.extend(data.iter().step_by(2));
+    buf.extend(data.iter().skip(1).step_by(2));
+
+    let mut previous = buf.first().copied().unwrap_or(0);
+    for v in buf.iter_mut().skip(1) {
+        let current = *v;
+        *v = current.wrapping_sub(previous).wrapping_add(128);
+        previous = current;
+    }
+
+    zlib::compress(&buf)
+}
+
+fn block(film: &Film, channels: &[Channel], y0: u32, y1: u32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(((y1 - y0) * film.width()) as usize * channels.len() * 4);
+
+    for y in y0..y1 {
+        for channel in channels {
+            for x in 0..film.width() {
+                data.extend_from_slice(&channel.sample(film, x, y).to_le_bytes());
+            }
+        }
+    }
+
+    data
+}
+
+pub fn write<W: Write>(film: &Film, out: &mut W, options: &ExrOptions) -> io::Result<()> {
+    // Channels must be listed in alphabetical order.
+    let channels: Vec<Channel> = [
+        (Channel::A, options.alpha),
+        (Channel::B, true),
+        (Channel::G, true),
+        (Channel::R, true),
+        (Channel::Z, options.depth),
+    ]
+    .into_iter()
+    .filter_map(|(channel, enabled)| enabled.then_some(channel))
+    .collect();
+
+    let lines = options.compression.scanlines_per_block();
+    let chunks: Vec<(u32, Vec<u8>)> = (0..film.height())
+        .step_by(lines as usize)
+        .map(|y0| {
+            let raw = block(film, &channels, y0, u32::min(y0 + lines, film.height()));
+            let data = match options.compression {
+                Compression::None => raw,
+                Compression::Zip => {
+                    // Blocks that do not shrink are stored uncompressed.
+                    let packed = zip(&raw);
+                    if packed.len() < raw.len() {
+                        packed
+                    } else {
+                        raw
+                    }
+                }
+            };
+
+            (y0, data)
+        })
+        .collect();
+
+    let header = header(film, &channels, options.compression);
+    out.write_all(&MAGIC)?;
+    out.write_all(&VERSION)?;
+    out.write_all(&header)?;
+
+    // Offset table, pointing at each chunk from the start of the file.
+    let mut offset = (MAGIC.len() + VERSION.len() + header.len() + chunks.len() * 8) as u64;
+    for (_, data) in &chunks {
+        out.write_all(&offset.to_le_bytes())?;
+        offset += 8 + data.len() as u64;
+    }
+
+    for (y, data) in &chunks {
+        out.write_all(&(*y as i32).to_le_bytes())?;
+        out.write_all(&(data.len() as i32).to_le_bytes())?;
+        out.write_all(data)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::color::Color;
+
+    #[test]
+    fn test_uncompressed_layout() {
+        let mut film = Film::new(2, 1);
+        film.set(0, 0, Color::new(1.0, 2.0, 3.0));
+        film.set(1, 0, Color::new(4.0, 5.0, 6.0));
+        film.set_alpha(1, 0, 0.5);
+
+        let options = ExrOptions {
+            compression: Compression::None,
+            alpha: true,
+            depth: false,
+        };
+        let mut out = Vec::new();
+        write(&film, &mut out, &options).unwrap();
+
+        assert_eq!(&out[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+        // Single chunk: offset table entry, then scanline y, size and A, B, G, R samples.
+        let header_len = header(
+            &film,
+            &[Channel::A, Channel::B, Channel::G, Channel::R],
+            options.compression,
+        )
+        .len();
+        let offset = u64::from_le_bytes(out[8 + header_len..16 + header_len].try_into().unwrap());
+        assert_eq!(offset as usize, 16 + header_len);
+
+        let chunk = &out[offset as usize..];
+        assert_eq!(i32::from_le_bytes(chunk[..4].try_into().unwrap()), 0);
+        assert_eq!(i32::from_le_bytes(chunk[4..8].try_into().unwrap()), 32);
+
+        let samples: Vec<f32> = chunk[8..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(samples, vec![1.0, 0.5, 3.0, 6.0, 2.0, 5.0, 1.0, 4.0]);
+    }
+
+    // Reverses the predictor and the interleaving of zip, after inflating.
+    fn unzip(data: &[u8]) -> Vec<u8> {
+        let mut buf = zlib::decompress(data).unwrap();
+        for i in 1..buf.len() {
+            buf[i] = buf[i - 1].wrapping_add(buf[i]).wrapping_sub(128);
+        }
+
+        let (even, odd) = buf.split_at(buf.len().div_ceil(2));
+        let mut raw = Vec::with_capacity(buf.len());
+        for i in 0..buf.len() {
+            raw.push(if i % 2 == 0 { even[i / 2] } else { odd[i / 2] });
+        }
+        raw
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let mut film = Film::new(9, 20);
+        for y in 0..20 {
+            for x in 0..9 {
+                let v = f64::from(x + 9 * y) / 180.0;
+                film.set(x, y, Color::new(v, 0.5, 1.0 - v));
+                film.set_depth(x, y, 1.0 + v);
+            }
+        }
+
+        let options = ExrOptions {
+            depth: true,
+            ..ExrOptions::default()
+        };
+        let mut out = Vec::new();
+        write(&film, &mut out, &options).unwrap();
+
+        // Two compressed chunks of 16 and 4 scanlines, decoding back to the samples of their
+        // blocks.
+        let channels = [Channel::A, Channel::B, Channel::G, Channel::R, Channel::Z];
+        let header_len = header(&film, &channels, Compression::Zip).len();
+        let table = 8 + header_len;
+        for (n, y0, y1) in [(0, 0, 16), (1, 16, 20)] {
+            let entry = &out[table + 8 * n..table + 8 * (n + 1)];
+            let offset = u64::from_le_bytes(entry.try_into().unwrap()) as usize;
+            let chunk = &out[offset..];
+            assert_eq!(i32::from_le_bytes(chunk[..4].try_into().unwrap()), y0);
+            let size = i32::from_le_bytes(chunk[4..8].try_into().unwrap()) as usize;
+
+            let raw = block(&film, &channels, y0 as u32, y1 as u32);
+            assert!(size < raw.len());
+            assert_eq!(unzip(&chunk[8..8 + size]), raw);
+        }
+    }
+}
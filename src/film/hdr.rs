@@ -0,0 +1,234 @@
+use std::io::{self, Read, Write};
+
+use crate::material::color::Color;
+
+use super::{readable_size, Film};
+
+// Radiance picture: a text header followed by RGBE pixels, three 8 bits mantissas sharing an
+// exponent. Scanlines are stored from the top of the image to the bottom, either flat or run
+// length encoded one channel after the other.
+pub fn write<W: Write>(film: &Film, out: &mut W) -> io::Result<()> {
+    write!(
+        out,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        film.height(),
+        film.width()
+    )?;
+
+    let mut body = Vec::with_capacity((film.width() * film.height() * 4) as usize);
+    for y in 0..film.height() {
+        for x in 0..film.width() {
+            body.extend_from_slice(&rgbe(film.get(x, y)));
+        }
+    }
+
+    out.write_all(&body)
+}
+
+fn rgbe(color: &Color) -> [u8; 4] {
+    let v = f64::max(color.r(), f64::max(color.g(), color.b()));
+    if v < 1e-32 || !v.is_finite() {
+        return [0, 0, 0, 0];
+    }
+
+    // Largest component as m * 2^e with m in [0.5, 1).
+    let mut e = v.log2().floor() as i32 + 1;
+    if v / f64::powi(2.0, e) >= 1.0 {
+        e += 1;
+    }
+    let scale = 256.0 / f64::powi(2.0, e);
+    let mantissa = |c: f64| (f64::max(c, 0.0) * scale) as u8;
+
+    [
+        mantissa(color.r()),
+        mantissa(color.g()),
+        mantissa(color.b()),
+        (e + 128).clamp(0, 255) as u8,
+    ]
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+pub fn read<R: Read>(input: &mut R) -> io::Result<Film> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    // Header lines up to a blank line, then the resolution line.
+    let mut lines = Vec::new();
+    let mut position = 0;
+    loop {
+        let end = data[position..]
+            .iter()
+            .position(|&c| c == b'\n')
+            .ok_or_else(|| invalid("truncated Radiance header"))?;
+        let line = String::from_utf8_lossy(&data[position..position + end]).into_owned();
+        position += end + 1;
+        let resolution = lines.len() > 1 && lines.last().is_some_and(String::is_empty);
+        lines.push(line);
+        if resolution {
+            break;
+        }
+    }
+
+    if !lines[0].starts_with("#?") {
+        return Err(invalid("not a Radiance picture"));
+    }
+    if lines
+        .iter()
+        .any(|line| line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe")
+    {
+        return Err(invalid("unsupported Radiance pixel format"));
+    }
+    let resolution: Vec<&str> = lines[lines.len() - 1].split_whitespace().collect();
+    let (h, w) = match resolution[..] {
+        ["-Y", h, "+X", w] => match (h.parse::<u32>(), w.parse::<u32>()) {
+            (Ok(h), Ok(w)) => (h, w),
+            _ => return Err(invalid("invalid Radiance resolution")),
+        },
+        _ => return Err(invalid("unsupported Radiance orientation")),
+    };
+    if !readable_size(w, h) {
+        return Err(invalid("unsupported Radiance resolution"));
+    }
+
+    let mut film = Film::new(w, h);
+    let mut scanline = vec![[0_u8; 4]; w as usize];
+    for y in 0..h {
+        position = read_scanline(&data, position, &mut scanline)?;
+        for (x, p) in scanline.iter().enumerate() {
+            film.set(x as u32, y, color(p));
+        }
+    }
+
+    Ok(film)
+}
+
+fn byte(data: &[u8], position: usize) -> io::Result<u8> {
+    data.get(position)
+        .copied()
+        .ok_or_else(|| invalid("truncated Radiance picture"))
+}
+
+// Decodes the scanline at the position, returning the position of the next one.
+fn read_scanline(data: &[u8], mut position: usize, scanline: &mut [[u8; 4]]) -> io::Result<usize> {
+    let w = scanline.len();
+    let head = [
+        byte(data, position)?,
+        byte(data, position + 1)?,
+        byte(data, position + 2)?,
+        byte(data, position + 3)?,
+    ];
+    let encoded = (8..0x8000).contains(&w)
+        && head[0] == 2
+        && head[1] == 2
+        && usize::from(head[2]) << 8 | usize::from(head[3]) == w;
+
+    if !encoded {
+        for pixel in scanline.iter_mut() {
+            for c in pixel.iter_mut() {
+                *c = byte(data, position)?;
+                position += 1;
+            }
+        }
+        return Ok(position);
+    }
+
+    position += 4;
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < w {
+            let count = usize::from(byte(data, position)?);
+            position += 1;
+            let (count, run) = if count > 128 {
+                (count - 128, true)
+            } else {
+                (count, false)
+            };
+            if count == 0 || x + count > w {
+                return Err(invalid("invalid Radiance run length"));
+            }
+
+            for pixel in &mut scanline[x..x + count] {
+                pixel[channel] = byte(data, position)?;
+                if !run {
+                    position += 1;
+                }
+            }
+            if run {
+                position += 1;
+            }
+            x += count;
+        }
+    }
+
+    Ok(position)
+}
+
+fn color(p: &[u8; 4]) -> Color {
+    if p[3] == 0 {
+        return Color::default();
+    }
+
+    // Mantissas are rounded down when encoded, their middle is the best guess.
+    let f = f64::powi(2.0, i32::from(p[3]) - (128 + 8));
+    let channel = |c: u8| {
+        if c == 0 {
+            0.0
+        } else {
+            (f64::from(c) + 0.5) * f
+        }
+    };
+
+    Color::new(channel(p[0]), channel(p[1]), channel(p[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut film = Film::new(3, 2);
+        film.set(1, 0, Color::new(12.5, 0.25, 0.0));
+        film.set(2, 1, Color::new(0.001, 1e6, 0.5));
+
+        let mut out = Vec::new();
+        write(&film, &mut out).unwrap();
+        assert!(out.starts_with(b"#?RADIANCE\n"));
+
+        let read = read(&mut &out[..]).unwrap();
+        assert_eq!((read.width(), read.height()), (3, 2));
+        for (a, b) in read.pixels().iter().zip(film.pixels()) {
+            let largest = f64::max(b.r(), f64::max(b.g(), b.b()));
+            for (x, y) in [(a.r(), b.r()), (a.g(), b.g()), (a.b(), b.b())] {
+                assert!((x - y).abs() <= largest / 128.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_run_length_encoded() {
+        // An 8 pixels wide scanline, runs for red and exponent, literals for green and blue.
+        let mut data = b"#?RGBE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+        data.extend_from_slice(&[2, 2, 0, 8]);
+        data.extend_from_slice(&[136, 128]);
+        data.extend_from_slice(&[8, 0, 64, 0, 64, 0, 64, 0, 64]);
+        data.extend_from_slice(&[8, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[136, 129]);
+
+        let film = read(&mut &data[..]).unwrap();
+        assert_eq!(film.width(), 8);
+        assert_eq!(film.get(0, 0), &Color::new(1.00390625, 0.0, 0.0));
+        assert_eq!(film.get(1, 0), &Color::new(1.00390625, 0.50390625, 0.0));
+
+        assert!(read(&mut &b"P6\n1 1\n255\n"[..]).is_err());
+        for resolution in ["-Y 0 +X 8", "-Y 2000000000 +X 2000000000"] {
+            let header = format!("#?RGBE\n\n{}\n", resolution);
+            assert!(read(&mut header.as_bytes()).is_err());
+        }
+        data.truncate(data.len() - 1);
+        assert!(read(&mut &data[..]).is_err());
+    }
+}
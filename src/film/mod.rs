@@ -0,0 +1,269 @@
+pub mod exr;
+pub mod hdr;
+pub mod pfm;
+pub mod png;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::material::color::Color;
+use crate::ppm::{color::PpmColor, image::Ppm};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm,      // Binary portable pixmap (P6)
+    PpmAscii, // Plain text portable pixmap (P3)
+    Png,
+    Pfm, // Portable float map, linear RGB
+    Exr, // OpenEXR, linear RGB with alpha
+    Hdr, // Radiance picture, shared exponent RGB
+}
+
+impl ImageFormat {
+    pub fn from_extension(extension: &str) -> Option<ImageFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "pfm" => Some(ImageFormat::Pfm),
+            "exr" => Some(ImageFormat::Exr),
+            "hdr" => Some(ImageFormat::Hdr),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        ImageFormat::from_extension(path.extension()?.to_str()?)
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        matches!(self, ImageFormat::Pfm | ImageFormat::Exr | ImageFormat::Hdr)
+    }
+}
+
+// Most pixels of an image read from a file, a 16k by 8k environment map, so that broken or
+// hostile headers cannot exhaust the memory.
+const MAX_PIXELS: usize = 1 << 27;
+
+// Whether an image of the size may be read from a file.
+pub(crate) fn readable_size(w: u32, h: u32) -> bool {
+    w > 0
+        && h > 0
+        && (w as usize)
+            .checked_mul(h as usize)
+            .is_some_and(|n| n <= MAX_PIXELS)
+}
+
+// Linear radiance of every pixel of a rendered image, stored row by row from the top left corner,
+// along with the coverage and the distance to the camera of the surfaces seen through the pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Film {
+    w: u32,
+    h: u32,
+    pixels: Vec<Color>,
+    alpha: Vec<f64>,
+    depth: Vec<f64>,
+}
+
+impl Film {
+    pub fn new(w: u32, h: u32) -> Film {
+        Film {
+            w,
+            h,
+            pixels: vec![Color::default(); (w * h) as usize],
+            alpha: vec![1.0; (w * h) as usize],
+            depth: vec![f64::INFINITY; (w * h) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.w
+    }
+
+    pub fn height(&self) -> u32 {
+        self.h
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> &Color {
+        &self.pixels[(y * self.w + x) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        self.pixels[(y * self.w + x) as usize] = color;
+    }
+
+    pub fn alpha(&self, x: u32, y: u32) -> f64 {
+        self.alpha[(y * self.w + x) as usize]
+    }
+
+    pub fn set_alpha(&mut self, x: u32, y: u32, alpha: f64) {
+        self.alpha[(y * self.w + x) as usize] = alpha;
+    }
+
+    pub fn depth(&self, x: u32, y: u32) -> f64 {
+        self.depth[(y * self.w + x) as usize]
+    }
+
+    pub fn set_depth(&mut self, x: u32, y: u32, depth: f64) {
+        self.depth[(y * self.w + x) as usize] = depth;
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        // Gamma encoded and quantised pixels, as displayed.
+        self.pixels
+            .iter()
+            .map(PpmColor::from)
+            .flat_map(|c| [c.r(), c.g(), c.b()])
+            .collect()
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W, format: ImageFormat) -> io::Result<()> {
+        match format {
+            ImageFormat::Ppm => {
+                let mut img = Ppm::new_binary(self.w, self.h);
+                img.set_film(self);
+                img.write(out)
+            }
+            ImageFormat::PpmAscii => Ppm::from(self).write(out),
+            ImageFormat::Png => png::write(self, out),
+            ImageFormat::Pfm => pfm::write(self, out),
+            ImageFormat::Exr => exr::write(self, out, &exr::ExrOptions::default()),
+            ImageFormat::Hdr => hdr::write(self, out),
+        }
+    }
+
+    // Writes the distance to the camera of every pixel, either alone as a greyscale PFM image,
+    // or as the Z channel of an EXR image along with the colors.
+    pub fn write_depth<W: Write>(&self, out: &mut W, format: ImageFormat) -> io::Result<()> {
+        match format {
+            ImageFormat::Pfm => pfm::write_depth(self, out),
+            ImageFormat::Exr => {
+                let options = exr::ExrOptions {
+                    depth: true,
+                    ..exr::ExrOptions::default()
+                };
+                exr::write(self, out, &options)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "depth maps are written as PFM or EXR images",
+            )),
+        }
+    }
+
+    pub fn write_depth_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let format = ImageFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: unsupported image format", path.display()),
+            )
+        })?;
+
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_depth(&mut out, format)?;
+        out.flush()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        // The image format is picked from the file extension.
+        let path = path.as_ref();
+        let format = ImageFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: unsupported image format", path.display()),
+            )
+        })?;
+
+        self.write_file(path, format)
+    }
+
+    pub fn write_file<P: AsRef<Path>>(&self, path: P, format: ImageFormat) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(&mut out, format)?;
+        out.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Film> {
+        // PPM, PNG, PFM and Radiance images can be read back, picked from the file extension.
+        let path = path.as_ref();
+        let mut input = BufReader::new(File::open(path)?);
+
+        match ImageFormat::from_path(path) {
+            Some(ImageFormat::Ppm) => Ok(Film::from(&Ppm::read(&mut input)?)),
+            Some(ImageFormat::Png) => png::read(&mut input),
+            Some(ImageFormat::Pfm) => pfm::read(&mut input),
+            Some(ImageFormat::Hdr) => hdr::read(&mut input),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: unsupported image format", path.display()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ImageFormat::from_path(Path::new("out/image.PNG")),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("image.ppm")),
+            Some(ImageFormat::Ppm)
+        );
+        assert_eq!(ImageFormat::from_path(Path::new("image.gif")), None);
+        assert_eq!(ImageFormat::from_path(Path::new("image")), None);
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let mut film = Film::new(2, 1);
+        film.set(0, 0, Color::new(1.0, 0.0, 0.25));
+        film.set(1, 0, Color::new(4.0, 0.0, 0.0));
+
+        let mut out = Vec::new();
+        film.write(&mut out, ImageFormat::Ppm).unwrap();
+        assert_eq!(out, b"P6\n2 1\n255\n\xff\x00\x7f\xff\x00\x00");
+
+        let mut out = Vec::new();
+        film.write(&mut out, ImageFormat::PpmAscii).unwrap();
+        assert!(out.starts_with(b"P3\n2 1\n255\n255 0 127\n255 0 0\n"));
+    }
+
+    #[test]
+    fn test_read_ppm() {
+        let film =
+            Film::from(&Ppm::read(&mut &b"P3\n# comment\n2 1\n15\n15 0 3\n0 15 15\n"[..]).unwrap());
+        assert_eq!(film.width(), 2);
+        assert_eq!(film.get(0, 0), &Color::new(1.0, 0.0, 0.2 * 0.2));
+
+        let mut out = Vec::new();
+        film.write(&mut out, ImageFormat::Ppm).unwrap();
+        assert_eq!(Film::from(&Ppm::read(&mut &out[..]).unwrap()), film);
+    }
+
+    #[test]
+    fn test_write_depth() {
+        let mut film = Film::new(2, 1);
+        film.set_depth(0, 0, 1.5);
+
+        let mut out = Vec::new();
+        film.write_depth(&mut out, ImageFormat::Pfm).unwrap();
+        assert!(out.starts_with(b"Pf\n2 1\n-1.0\n"));
+        assert_eq!(out[out.len() - 8..out.len() - 4], 1.5_f32.to_le_bytes());
+
+        let mut out = Vec::new();
+        film.write_depth(&mut out, ImageFormat::Exr).unwrap();
+        assert!(out.windows(3).any(|w| w == b"Z\0\x02"));
+        assert!(film.write_depth(&mut Vec::new(), ImageFormat::Png).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use std::io::{self, Read, Write};
+
+use crate::material::color::Color;
+
+use super::Film;
+
+// Portable float map: a text header followed by 32 bits floating point samples. A negative scale
+// marks little endian samples. Scanlines are stored from the bottom of the image to the top.
+pub fn write<W: Write>(film: &Film, out: &mut W) -> io::Result<()> {
+    write!(out, "PF\n{} {}\n-1.0\n", film.width(), film.height())?;
+
+    let mut body = Vec::with_capacity((film.width() * film.height() * 12) as usize);
+    for y in (0..film.height()).rev() {
+        for x in 0..film.width() {
+            let c = film.get(x, y);
+            for v in [c.r(), c.g(), c.b()] {
+                body.extend_from_slice(&(v as f32).to_le_bytes());
+            }
+        }
+    }
+
+    out.write_all(&body)
+}
+
+pub fn write_depth<W: Write>(film: &Film, out: &mut W) -> io::Result<()> {
+    // Greyscale variant holding the distance to the first surface hit.
+    write!(out, "Pf\n{} {}\n-1.0\n", film.width(), film.height())?;
+
+    let mut body = Vec::with_capacity((film.width() * film.height() * 4) as usize);
+    for y in (0..film.height()).rev() {
+        for x in 0..film.width() {
+            body.extend_from_slice(&(film.depth(x, y) as f32).to_le_bytes());
+        }
+    }
+
+    out.write_all(&body)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+pub fn read<R: Read>(input: &mut R) -> io::Result<Film> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    // The header is made of three whitespace separated tokens after the magic number.
+    let mut tokens = Vec::with_capacity(4);
+    let mut position = 0;
+    while tokens.len() < 4 {
+        while data.get(position).is_some_and(|c| c.is_ascii_whitespace()) {
+            position += 1;
+        }
+        let start = position;
+        while data.get(position).is_some_and(|c| !c.is_ascii_whitespace()) {
+            position += 1;
+        }
+        if start == position {
+            return Err(invalid("truncated PFM header"));
+        }
+        tokens.push(String::from_utf8_lossy(&data[start..position]).into_owned());
+    }
+    position += 1;
+
+    let channels = match tokens[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(invalid("not a PFM file")),
+    };
+    let (w, h, scale) = match (
+        tokens[1].parse::<u32>(),
+        tokens[2].parse::<u32>(),
+        tokens[3].parse::<f64>(),
+    ) {
+        (Ok(w), Ok(h), Ok(scale)) if scale != 0.0 => (w, h, scale),
+        _ => return Err(invalid("invalid PFM header")),
+    };
+
+    let count = w as usize * h as usize * channels;
+    let body = data
+        .get(position..position + count * 4)
+        .ok_or_else(|| invalid("truncated PFM image"))?;
+    let samples: Vec<f64> = body
+        .chunks_exact(4)
+        .map(|b| {
+            let bytes = [b[0], b[1], b[2], b[3]];
+            f64::from(if scale < 0.0 {
+                f32::from_le_bytes(bytes)
+            } else {
+                f32::from_be_bytes(bytes)
+            })
+        })
+        .collect();
+
+    let mut film = Film::new(w, h);
+    for (n, s) in samples.chunks_exact(channels).enumerate() {
+        let (x, y) = (n as u32 % w, h - 1 - n as u32 / w);
+        let color = match s {
+            [v] => Color::new(*v, *v, *v),
+            _ => Color::new(s[0], s[1], s[2]),
+        };
+        film.set(x, y, color);
+    }
+
+    Ok(film)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let mut film = Film::new(1, 2);
+        film.set(0, 0, Color::new(2.5, 0.0, -1.0));
+        film.set(0, 1, Color::new(0.5, 1.0, 0.0));
+
+        let mut out = Vec::new();
+        write(&film, &mut out).unwrap();
+
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&out[..header.len()], header);
+
+        let samples: Vec<f32> = out[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(samples, vec![0.5, 1.0, 0.0, 2.5, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn test_read() {
+        let mut film = Film::new(3, 2);
+        film.set(1, 0, Color::new(12.5, 0.25, -3.0));
+        film.set(2, 1, Color::new(0.0, 1e6, 0.5));
+
+        let mut out = Vec::new();
+        write(&film, &mut out).unwrap();
+        assert_eq!(read(&mut &out[..]).unwrap().pixels(), film.pixels());
+
+        // Big endian greyscale.
+        let mut data = b"Pf 1 1 1.0\n".to_vec();
+        data.extend_from_slice(&2.0_f32.to_be_bytes());
+        assert_eq!(
+            read(&mut &data[..]).unwrap().get(0, 0),
+            &Color::new(2.0, 2.0, 2.0)
+        );
+        assert!(read(&mut &data[..data.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,453 @@
+use std::io::{self, Read, Write};
+
+use crate::codec::{crc32::Crc32, zlib};
+
+use crate::material::color::Color;
+
+use super::{readable_size, Film};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Origin and spacing of the pixels of each of the seven Adam7 interlacing passes.
+const ADAM7: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = Crc32::new().update(kind).update(data).finish();
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn filter_row(filter: u8, row: &[u8], prior: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    out.push(filter);
+
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prior[i];
+        let c = if i >= bpp { prior[i - bpp] } else { 0 };
+
+        let predictor = match filter {
+            1 => a,
+            2 => b,
+            3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => 0,
+        };
+        out.push(row[i].wrapping_sub(predictor));
+    }
+}
+
+fn filter(data: &[u8], stride: usize, bpp: usize) -> Vec<u8> {
+    // Each scanline uses the filter type with the smallest sum of absolute differences, the
+    // heuristic recommended by the PNG specification.
+    let mut out = Vec::with_capacity(data.len() + data.len() / stride.max(1));
+    let zero = vec![0; stride];
+    let mut candidate = Vec::with_capacity(stride + 1);
+
+    for (n, row) in data.chunks(stride).enumerate() {
+        let prior = if n == 0 {
+            &zero[..]
+        } else {
+            &data[(n - 1) * stride..n * stride]
+        };
+
+        let mut best_cost = u64::MAX;
+        let mut best_filter = 0;
+        for filter in 0..5 {
+            candidate.clear();
+            filter_row(filter, row, prior, bpp, &mut candidate);
+
+            let cost: u64 = candidate[1..]
+                .iter()
+                .map(|&v| u64::from((v as i8).unsigned_abs()))
+                .sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_filter = filter;
+            }
+        }
+
+        filter_row(best_filter, row, prior, bpp, &mut out);
+    }
+
+    out
+}
+
+pub fn write<W: Write>(film: &Film, out: &mut W) -> io::Result<()> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&film.width().to_be_bytes());
+    header.extend_from_slice(&film.height().to_be_bytes());
+    // 8 bits per sample, truecolour, deflate compression, adaptive filtering, no interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let data = filter(&film.to_rgb8(), film.width() as usize * 3, 3);
+
+    out.write_all(&SIGNATURE)?;
+    write_chunk(out, b"IHDR", &header)?;
+    write_chunk(out, b"IDAT", &zlib::compress(&data))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn unfilter_row(filter: u8, row: &mut [u8], prior: &[u8], bpp: usize) -> io::Result<()> {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prior[i];
+        let c = if i >= bpp { prior[i - bpp] } else { 0 };
+
+        let predictor = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return Err(invalid(format!("invalid PNG filter type {}", filter))),
+        };
+        row[i] = row[i].wrapping_add(predictor);
+    }
+
+    Ok(())
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> io::Result<Header> {
+        if data.len() != 13 {
+            return Err(invalid("invalid PNG header".to_string()));
+        }
+
+        let header = Header {
+            width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            bit_depth: data[8],
+            color_type: data[9],
+            interlaced: data[12] == 1,
+        };
+
+        let depths: &[u8] = match header.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => &[],
+        };
+        if !depths.contains(&header.bit_depth) {
+            return Err(invalid(format!(
+                "unsupported PNG color type {} with bit depth {}",
+                header.color_type, header.bit_depth
+            )));
+        }
+        if data[10] != 0 || data[11] != 0 || data[12] > 1 {
+            return Err(invalid(
+                "unsupported PNG compression, filter or interlace method".to_string(),
+            ));
+        }
+        if header.width == 0 || header.height == 0 {
+            return Err(invalid("empty PNG image".to_string()));
+        }
+        if !readable_size(header.width, header.height) {
+            return Err(invalid(format!(
+                "unsupported PNG size {}x{}",
+                header.width, header.height
+            )));
+        }
+
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * usize::from(self.bit_depth)
+    }
+}
+
+fn sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[2 * index], row[2 * index + 1]]),
+        8 => u16::from(row[index]),
+        _ => {
+            // Packed samples, starting from the most significant bits of each byte.
+            let bit = index * usize::from(bit_depth);
+            let shift = 8 - usize::from(bit_depth) - bit % 8;
+            u16::from(row[bit / 8] >> shift) & ((1 << bit_depth) - 1)
+        }
+    }
+}
+
+// Decodes a PNG image of any standard color type and bit depth, interlaced or not. Samples are
+// gamma decoded to linear values, and transparency is kept as the film coverage.
+pub fn read<R: Read>(input: &mut R) -> io::Result<Film> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    if !data.starts_with(&SIGNATURE) {
+        return Err(invalid("not a PNG file".to_string()));
+    }
+
+    let mut header: Option<Header> = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut transparency: Vec<u8> = Vec::new();
+    let mut compressed = Vec::new();
+
+    let mut position = SIGNATURE.len();
+    loop {
+        let chunk = data
+            .get(position..position + 8)
+            .ok_or_else(|| invalid("truncated PNG file".to_string()))?;
+        let length = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as usize;
+        let kind = [chunk[4], chunk[5], chunk[6], chunk[7]];
+        let body = data
+            .get(position + 8..position + 8 + length)
+            .ok_or_else(|| invalid("truncated PNG file".to_string()))?;
+        let crc = data
+            .get(position + 8 + length..position + 12 + length)
+            .ok_or_else(|| invalid("truncated PNG file".to_string()))?;
+        if Crc32::new()
+            .update(&kind)
+            .update(body)
+            .finish()
+            .to_be_bytes()
+            != crc
+        {
+            return Err(invalid(format!(
+                "corrupted PNG chunk {}",
+                String::from_utf8_lossy(&kind)
+            )));
+        }
+        position += 12 + length;
+
+        match &kind {
+            b"IHDR" => header = Some(Header::parse(body)?),
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"tRNS" => transparency = body.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            // Ancillary chunks can be skipped, critical ones cannot.
+            _ if kind[0] & 0x20 != 0 => {}
+            _ => {
+                return Err(invalid(format!(
+                    "unsupported PNG chunk {}",
+                    String::from_utf8_lossy(&kind)
+                )))
+            }
+        }
+    }
+
+    let header = header.ok_or_else(|| invalid("missing PNG header".to_string()))?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(invalid("missing PNG palette".to_string()));
+    }
+
+    let raw = zlib::decompress(&compressed)?;
+    let mut film = Film::new(header.width, header.height);
+    let bits = header.bits_per_pixel();
+    let bpp = usize::max(bits / 8, 1);
+    let max = f64::from((1_u32 << header.bit_depth) - 1);
+    let channels = header.channels();
+
+    // Color key for greyscale and truecolor images, as 16 bits samples.
+    let key: Vec<u16> = transparency
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect();
+
+    let passes = if header.interlaced {
+        &ADAM7[..]
+    } else {
+        &[(0, 0, 1, 1)][..]
+    };
+
+    let mut offset = 0;
+    for &(x0, y0, dx, dy) in passes {
+        if x0 >= header.width || y0 >= header.height {
+            continue;
+        }
+        let (w, h) = (
+            (header.width - x0).div_ceil(dx),
+            (header.height - y0).div_ceil(dy),
+        );
+        let stride = (w as usize * bits).div_ceil(8);
+
+        let mut prior = vec![0; stride];
+        for j in 0..h {
+            let line = raw
+                .get(offset..offset + stride + 1)
+                .ok_or_else(|| invalid("truncated PNG image data".to_string()))?;
+            let mut row = line[1..].to_vec();
+            unfilter_row(line[0], &mut row, &prior, bpp)?;
+            offset += stride + 1;
+
+            for i in 0..w as usize {
+                let s = |c: usize| sample(&row, i * channels + c, header.bit_depth);
+                let v = |c: usize| f64::from(s(c)) / max;
+
+                let (r, g, b, a) = match header.color_type {
+                    0 => (
+                        v(0),
+                        v(0),
+                        v(0),
+                        if key.first() == Some(&s(0)) { 0.0 } else { 1.0 },
+                    ),
+                    2 => {
+                        let transparent = key.len() == 3 && key == [s(0), s(1), s(2)];
+                        (v(0), v(1), v(2), if transparent { 0.0 } else { 1.0 })
+                    }
+                    3 => {
+                        let index = usize::from(s(0));
+                        let [r, g, b] = *palette.get(index).ok_or_else(|| {
+                            invalid(format!("PNG palette index {} out of range", index))
+                        })?;
+                        let a = transparency.get(index).copied().unwrap_or(255);
+                        (
+                            f64::from(r) / 255.0,
+                            f64::from(g) / 255.0,
+                            f64::from(b) / 255.0,
+                            f64::from(a) / 255.0,
+                        )
+                    }
+                    4 => (v(0), v(0), v(0), v(1)),
+                    _ => (v(0), v(1), v(2), v(3)),
+                };
+
+                let (x, y) = (x0 + i as u32 * dx, y0 + j * dy);
+                film.set(x, y, Color::new(r * r, g * g, b * b));
+                film.set_alpha(x, y, a);
+            }
+
+            prior = row;
+        }
+    }
+
+    Ok(film)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut film = Film::new(7, 5);
+        for y in 0..5 {
+            for x in 0..7 {
+                let v = f64::from(x * 5 + y) / 40.0;
+                film.set(x, y, Color::new(v, 1.0 - v, v * v));
+            }
+        }
+
+        let mut out = Vec::new();
+        write(&film, &mut out).unwrap();
+        let read = read(&mut &out[..]).unwrap();
+
+        assert_eq!(read.width(), 7);
+        assert_eq!(read.height(), 5);
+        assert_eq!(read.to_rgb8(), film.to_rgb8());
+
+        // A header claiming a huge image is turned down before anything is allocated.
+        out[16..24].copy_from_slice(&[0x77, 0x35, 0x94, 0x00, 0x77, 0x35, 0x94, 0x00]);
+        let crc = Crc32::new().update(&out[12..29]).finish();
+        out[29..33].copy_from_slice(&crc.to_be_bytes());
+        let e = super::read(&mut &out[..]).unwrap_err();
+        assert_eq!(e.to_string(), "unsupported PNG size 2000000000x2000000000");
+    }
+
+    #[test]
+    fn test_read_interlaced_palette() {
+        // 5x3 Adam7 interlaced image with 2 bits palette indices, the last color being
+        // transparent, and an ancillary text chunk.
+        let data = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x03, 0x02, 0x03, 0x00, 0x00,
+            0x01, 0x51, 0x5f, 0x1d, 0xfd, 0x00, 0x00, 0x00, 0x0c, 0x50, 0x4c, 0x54, 0x45, 0xff,
+            0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xfb, 0x00, 0x60,
+            0xf6, 0x00, 0x00, 0x00, 0x04, 0x74, 0x52, 0x4e, 0x53, 0xff, 0xff, 0xff, 0x00, 0x40,
+            0x2a, 0xa9, 0xf4, 0x00, 0x00, 0x00, 0x0c, 0x74, 0x45, 0x58, 0x74, 0x43, 0x6f, 0x6d,
+            0x6d, 0x65, 0x6e, 0x74, 0x00, 0x74, 0x65, 0x73, 0x74, 0x57, 0x61, 0x2b, 0xe9, 0x00,
+            0x00, 0x00, 0x15, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x60, 0x00, 0x82, 0x06,
+            0x86, 0x0e, 0x86, 0x02, 0x86, 0x0b, 0x0c, 0x39, 0x0e, 0x00, 0x10, 0x47, 0x02, 0xf5,
+            0x03, 0x6d, 0x96, 0x7d, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42,
+            0x60, 0x82,
+        ];
+        let film = read(&mut &data[..]).unwrap();
+        let palette = [
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0),
+        ];
+
+        for y in 0..3 {
+            for x in 0..5 {
+                let index = ((x + y * 5) % 4) as usize;
+                assert_eq!(film.get(x, y), &palette[index], "pixel {} {}", x, y);
+                assert_eq!(film.alpha(x, y), if index == 3 { 0.0 } else { 1.0 });
+            }
+        }
+
+        let mut corrupted = data;
+        corrupted[20] ^= 1;
+        assert!(read(&mut &corrupted[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_16_bits_rgba() {
+        let data = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x10, 0x06, 0x00, 0x00,
+            0x00, 0xa4, 0xb2, 0xa3, 0xc9, 0x00, 0x00, 0x00, 0x14, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0x9c, 0x63, 0xf8, 0xff, 0x9f, 0x81, 0xa1, 0x81, 0x01, 0x44, 0x82, 0x30, 0x03, 0x03,
+            0x00, 0x42, 0xd4, 0x06, 0x7b, 0xd3, 0x82, 0x8a, 0xb9, 0x00, 0x00, 0x00, 0x00, 0x49,
+            0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+        ];
+        let film = read(&mut &data[..]).unwrap();
+
+        let half = 32768.0 / 65535.0;
+        assert_eq!(film.get(0, 0), &Color::new(1.0, 0.0, half * half));
+        assert_eq!(film.alpha(0, 0), 1.0);
+        assert_eq!(film.get(1, 0), &Color::new(0.0, 1.0, 0.0));
+        assert_eq!(film.alpha(1, 0), 0.0);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::material::color::Color;
+use crate::ray::Ray;
+
+// Homogeneous medium filling the whole scene. Its density is constant below the base height
+// when there is no falloff, and otherwise decreases exponentially with the height above it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fog {
+    density: f64,  // Extinction per unit of distance at the base height
+    albedo: Color, // Fraction of the sky light scattered towards the viewer
+    height: f64,   // Base height
+    falloff: f64,  // Exponential decay rate of the density with height
+}
+
+impl Fog {
+    pub fn new(density: f64, albedo: Color) -> Fog {
+        Fog {
+            density,
+            albedo,
+            height: 0.0,
+            falloff: 0.0,
+        }
+    }
+
+    pub fn set_falloff(&mut self, height: f64, falloff: f64) -> &mut Self {
+        self.height = height;
+        self.falloff = falloff;
+
+        self
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    pub fn albedo(&self) -> &Color {
+        &self.albedo
+    }
+
+    pub fn transmittance(&self, ray: &Ray, t: f64) -> f64 {
+        // Fraction of the light surviving from ray.at(t) back to the ray origin; t may be
+        // infinite for rays escaping the scene.
+        let norm = ray.direction().norm();
+        let distance = t * norm;
+        let y = ray.origin().y() - self.height;
+        let a = self.falloff * ray.direction().y() / norm;
+
+        // Integral of exp(-a s) over the distance, which stays finite for upward rays.
+        let length = if a == 0.0 {
+            distance
+        } else {
+            -f64::exp_m1(-a * distance) / a
+        };
+        let optical_depth = self.density * f64::exp(-self.falloff * y) * length;
+
+        f64::exp(-optical_depth)
+    }
+
+    pub fn apply(&self, ray: &Ray, t: f64, radiance: Color, sky: &Color) -> Color {
+        // Attenuates the radiance arriving along the ray segment and adds the sky light
+        // scattered into it.
+        let transmittance = self.transmittance(ray, t);
+
+        transmittance * radiance + (1.0 - transmittance) * (&self.albedo * sky)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::point3::Point3;
+    use crate::objects::vector3::Vector3;
+
+    #[test]
+    fn test_transmittance() {
+        let mut fog = Fog::new(0.5, Color::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0));
+
+        assert!((fog.transmittance(&ray, 1.0) - f64::exp(-1.0)).abs() < 1e-12);
+        assert_eq!(fog.transmittance(&ray, f64::INFINITY), 0.0);
+        assert_eq!(
+            fog.apply(
+                &ray,
+                f64::INFINITY,
+                Color::new(5.0, 5.0, 5.0),
+                &Color::new(0.5, 0.6, 0.7)
+            ),
+            Color::new(0.5, 0.6, 0.7)
+        );
+
+        // Rays going up through thinning fog only lose a finite fraction of their light,
+        // exp(-density / falloff) when starting at the base height.
+        fog.set_falloff(0.0, 2.0);
+        let up = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 3.0, 0.0));
+        let expected = f64::exp(-0.25);
+        assert!((fog.transmittance(&up, f64::INFINITY) - expected).abs() < 1e-12);
+        assert!(fog.transmittance(&up, 1.0) > expected);
+
+        // Thicker below the base height.
+        let low = Ray::new(Point3::new(0.0, -1.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(fog.transmittance(&low, 1.0) < fog.transmittance(&ray, 0.5));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::integrator::{Context, Integrator, PathEnd, PathStats, Splat};
+use crate::interval::Interval;
+use crate::material::color::Color;
+use crate::objects::onb::Onb;
+use crate::objects::vector3::Vector3;
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+
+// Shades the first surface hit by the fraction of the hemisphere around its normal left open
+// within the given distance, cosine weighted. Rays escaping the scene are fully open.
+#[derive(Debug, Clone)]
+pub struct AmbientOcclusion {
+    samples: usize, // Count of occlusion rays for each camera ray
+    distance: f64,  // Range of the occlusion rays, infinite to consider the whole scene
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: usize, distance: f64) -> AmbientOcclusion {
+        AmbientOcclusion {
+            samples: usize::max(samples, 1),
+            distance,
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn distance(&self) -> f64 {
+        self.distance
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(
+        &self,
+        ray: Ray,
+        rec: Option<HitRecord>,
+        context: &Context,
+        stats: &mut PathStats,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        let Some(rec) = rec else {
+            stats.record(0, PathEnd::Escaped);
+            return Color::new(1.0, 1.0, 1.0);
+        };
+
+        let uvw = Onb::new(&rec.normal);
+        let rayt = Interval::new(0.001, self.distance);
+        let open = (0..self.samples)
+            .filter(|_| {
+                let direction = uvw.transform(&Vector3::new_random_cosine());
+                let occlusion = Ray::with_motion(rec.p.clone(), direction, ray.time());
+
+                context.world.hit(&occlusion, &rayt).is_none()
+            })
+            .count();
+
+        stats.record(0, PathEnd::Absorbed);
+        let open = open as f64 / self.samples as f64;
+        Color::new(open, open, open)
+    }
+}
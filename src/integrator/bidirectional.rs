@@ -0,0 +1,543 @@
+use std::f64::consts::PI;
+
+use crate::integrator::{Context, Integrator, PathEnd, PathStats, Splat};
+use crate::interval::Interval;
+use crate::material::color::Color;
+use crate::material::Scatter;
+use crate::objects::onb::Onb;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::rng;
+
+// Bidirectional path tracer. Each camera sample traces a subpath from the camera and another
+// one from a point drawn on the lights, then joins every prefix of the first to every prefix
+// of the second, down to single light vertices splatted straight onto the image. All these
+// strategies are weighted with the balance heuristic, so that every path is mostly accounted
+// for by the strategies most likely to find it, such as caustics which are found from the
+// lights far more easily than from the camera.
+//
+// Materials must scatter their sampled directions with a density matching scattering_pdf, in
+// both directions of travel, and emitters are only sampled when they are part of the lights.
+// Light subpaths are only splatted for pinhole cameras, and only start from emitters.
+#[derive(Debug, Clone, Default)]
+pub struct Bidirectional;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Camera,
+    Light,
+    Surface,
+}
+
+#[derive(Clone)]
+struct Vertex {
+    kind: Kind,
+    rec: HitRecord,     // Point on a surface or in a medium, or the camera position
+    incoming: Ray,      // Ray which reached the vertex, for surface vertices
+    beta: Color,        // Throughput of the subpath up to the vertex
+    attenuation: Color, // Of the material at surface vertices
+    delta: bool,        // Scatters along a single direction, which connections cannot follow
+    pdf_fwd: f64,       // Density by area of the vertex, as sampled along its subpath
+    pdf_rev: f64,       // Density by area of the vertex, as sampled from the other end
+}
+
+// Densities and delta flag of a vertex, as used by the MIS weights.
+type Densities = (f64, f64, bool);
+
+impl Vertex {
+    fn camera(ray: &Ray) -> Vertex {
+        let mut rec = HitRecord::new();
+        rec.p = ray.origin().clone();
+
+        Vertex {
+            kind: Kind::Camera,
+            rec,
+            incoming: ray.clone(),
+            beta: Color::new(1.0, 1.0, 1.0),
+            attenuation: Color::default(),
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+        }
+    }
+
+    fn light(rec: HitRecord, pdf: f64) -> Vertex {
+        Vertex {
+            kind: Kind::Light,
+            incoming: Ray::new(rec.p.clone(), rec.normal.clone()),
+            rec,
+            beta: Color::new(1.0 / pdf, 1.0 / pdf, 1.0 / pdf),
+            attenuation: Color::default(),
+            delta: false,
+            pdf_fwd: pdf,
+            pdf_rev: 0.0,
+        }
+    }
+
+    fn p(&self) -> &Point3 {
+        &self.rec.p
+    }
+
+    fn towards(&self, other: &Vertex) -> Vector3 {
+        Vector3::from(other.p() - self.p())
+    }
+
+    // Cosine between the normal and the direction, or one where there is no surface.
+    fn cosine(&self, direction: &Vector3) -> f64 {
+        if self.kind == Kind::Camera || self.rec.mat.is_volumetric() {
+            return 1.0;
+        }
+
+        f64::abs(self.rec.normal.dot(direction)) / direction.norm()
+    }
+
+    // Converts a density by solid angle around this vertex into a density by area at the next.
+    fn convert(&self, pdf: f64, next: &Vertex) -> f64 {
+        let direction = self.towards(next);
+
+        pdf * next.cosine(&direction) / direction.dot(&direction)
+    }
+
+    // Radiance leaving a light vertex towards the next one, or the fraction of the light
+    // arriving at a surface vertex sent there, cosines included.
+    fn f(&self, next: &Vertex) -> Color {
+        let direction = self.towards(next);
+        let scattered = Ray::with_motion(self.p().clone(), direction, self.incoming.time());
+
+        match self.kind {
+            Kind::Camera => Color::default(),
+            Kind::Light => {
+                self.cosine(scattered.direction()) * self.rec.mat.emitted(&scattered, &self.rec)
+            }
+            Kind::Surface => {
+                let pdf = self
+                    .rec
+                    .mat
+                    .scattering_pdf(&self.incoming, &self.rec, &scattered);
+                pdf * &self.attenuation
+            }
+        }
+    }
+
+    // Density by area of sampling the next vertex from this one, reached from the previous.
+    fn pdf(&self, previous: Option<&Vertex>, next: &Vertex, context: &Context) -> f64 {
+        let direction = self.towards(next);
+
+        let pdf = match self.kind {
+            Kind::Camera => context.camera.direction_pdf(&direction),
+            Kind::Light => self.cosine(&direction) / (2.0 * PI),
+            Kind::Surface => {
+                let incoming = match previous {
+                    Some(previous) => Ray::with_motion(
+                        previous.p().clone(),
+                        previous.towards(self),
+                        self.incoming.time(),
+                    ),
+                    None => self.incoming.clone(),
+                };
+                let scattered = Ray::with_motion(self.p().clone(), direction, incoming.time());
+
+                self.rec
+                    .mat
+                    .scattering_pdf(&incoming, &self.rec, &scattered)
+            }
+        };
+
+        self.convert(pdf, next)
+    }
+
+    // Density by area of a light subpath starting at this vertex.
+    fn light_origin_pdf(&self, context: &Context) -> f64 {
+        context.lights.surface_pdf(&self.incoming, self.rec.t)
+    }
+
+    // Density by area of the next vertex, for a light subpath starting at this one.
+    fn emission_pdf(&self, next: &Vertex) -> f64 {
+        let direction = self.towards(next);
+
+        self.convert(self.cosine(&direction) / (2.0 * PI), next)
+    }
+
+    fn densities(&self) -> Densities {
+        (self.pdf_fwd, self.pdf_rev, self.delta)
+    }
+}
+
+impl Bidirectional {
+    // Extends the subpath from its last vertex along the ray, whose first hit is known. Camera
+    // subpaths also collect the light of the sky and of the fog along the way.
+    #[allow(clippy::too_many_arguments)]
+    fn walk(
+        &self,
+        mut ray: Ray,
+        mut next: Option<HitRecord>,
+        mut beta: Color,
+        mut pdf_fwd: f64,
+        max_vertices: usize,
+        path: &mut Vec<Vertex>,
+        context: &Context,
+        mut radiance: Option<&mut Color>,
+    ) -> PathEnd {
+        let camera = context.camera;
+
+        loop {
+            if path.len() >= max_vertices {
+                return PathEnd::Truncated;
+            }
+
+            if let Some(fog) = camera.fog() {
+                let t = next.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+                let transmittance = fog.transmittance(&ray, t);
+                if let Some(radiance) = radiance.as_deref_mut() {
+                    let inscattered = fog.albedo() * &camera.background().color(&ray);
+                    *radiance += &beta * ((1.0 - transmittance) * inscattered);
+                }
+                beta = transmittance * beta;
+            }
+
+            let Some(rec) = next else {
+                if let Some(radiance) = radiance {
+                    *radiance += beta * camera.background().color(&ray);
+                }
+                return PathEnd::Escaped;
+            };
+
+            let previous = path.last_mut().expect("subpaths start with a vertex");
+            let mut vertex = Vertex {
+                kind: Kind::Surface,
+                rec,
+                incoming: ray.clone(),
+                beta: beta.clone(),
+                attenuation: Color::default(),
+                delta: false,
+                pdf_fwd: 0.0,
+                pdf_rev: 0.0,
+            };
+            vertex.pdf_fwd = previous.convert(pdf_fwd, &vertex);
+
+            let Some(srec) = vertex.rec.mat.scatter(&ray, &vertex.rec) else {
+                path.push(vertex);
+                return PathEnd::Absorbed;
+            };
+            let (scattered, weight, pdf_rev) = match srec.scatter {
+                Scatter::Specular(scattered) => {
+                    vertex.delta = true;
+                    pdf_fwd = 0.0;
+                    (scattered, srec.attenuation.clone(), 0.0)
+                }
+                Scatter::Pdf(pdf) => {
+                    let rec = &vertex.rec;
+                    let scattered = Ray::with_motion(rec.p.clone(), pdf.generate(), ray.time());
+                    let pdf_value = pdf.value(scattered.direction());
+                    let scattering_pdf = rec.mat.scattering_pdf(&ray, rec, &scattered);
+                    if pdf_value <= 0.0 || !pdf_value.is_finite() || scattering_pdf <= 0.0 {
+                        path.push(vertex);
+                        return PathEnd::Absorbed;
+                    }
+
+                    // The same scattering, travelled the other way.
+                    let back = Ray::with_motion(rec.p.clone(), -ray.direction(), ray.time());
+                    let arriving =
+                        Ray::with_motion(scattered.at(1.0), -scattered.direction(), ray.time());
+                    let pdf_rev = rec.mat.scattering_pdf(&arriving, rec, &back);
+
+                    pdf_fwd = pdf_value;
+                    let weight = scattering_pdf / pdf_value * &srec.attenuation;
+                    (scattered, weight, pdf_rev)
+                }
+            };
+            vertex.attenuation = srec.attenuation;
+            previous.pdf_rev = vertex.convert(pdf_rev, previous);
+            path.push(vertex);
+
+            beta = beta * weight;
+            // The first vertex is no bounce, like the camera ray in the path tracer.
+            let bounces = path.len() - 1;
+            if bounces >= camera.min_depth() {
+                let survival = f64::min(beta.max_component(), 0.95);
+                if rng::random() >= survival {
+                    return PathEnd::Terminated;
+                }
+                beta /= survival;
+            }
+
+            next = context.hit(&scattered);
+            ray = scattered;
+        }
+    }
+
+    fn light_subpath(&self, time: f64, context: &Context) -> Vec<Vertex> {
+        let mut path = Vec::new();
+        let max_vertices = context.camera.max_depth();
+        if max_vertices == 0 {
+            return path;
+        }
+        let Some((rec, pdf)) = context.lights.sample_surface() else {
+            return path;
+        };
+        if pdf <= 0.0 {
+            return path;
+        }
+
+        // Lights emit on both sides, with a cosine distribution.
+        let mut direction = Onb::new(&rec.normal).transform(&Vector3::new_random_cosine());
+        if rng::random() < 0.5 {
+            direction = -direction;
+        }
+
+        let light = Vertex::light(rec, pdf);
+        let ray = Ray::with_motion(light.p().clone(), direction, time);
+        let pdf_dir = light.cosine(ray.direction()) / (2.0 * PI);
+        let emitted = light.cosine(ray.direction()) * light.rec.mat.emitted(&ray, &light.rec);
+        let beta = &light.beta * emitted / pdf_dir;
+        path.push(light);
+
+        if pdf_dir > 0.0 {
+            let next = context.hit(&ray);
+            self.walk(
+                ray,
+                next,
+                beta,
+                pdf_dir,
+                max_vertices,
+                &mut path,
+                context,
+                None,
+            );
+        }
+
+        path
+    }
+
+    // Weight of the strategy joining s light vertices to t camera vertices among all those
+    // which may build the same path, with the sampled light vertex replacing the first light
+    // vertex when given.
+    fn mis_weight(
+        &self,
+        camera_path: &[Vertex],
+        light_path: &[Vertex],
+        sampled: Option<&Vertex>,
+        s: usize,
+        t: usize,
+        context: &Context,
+    ) -> f64 {
+        if s + t == 2 {
+            return 1.0;
+        }
+
+        let mut cameras: Vec<Densities> = camera_path[..t].iter().map(Vertex::densities).collect();
+        let mut lights: Vec<Densities> = light_path[..s].iter().map(Vertex::densities).collect();
+
+        // Vertices at both ends of the connection and their predecessors.
+        let qs = match (s, sampled) {
+            (0, _) => None,
+            (1, Some(sampled)) => Some(sampled),
+            _ => Some(&light_path[s - 1]),
+        };
+        if let (1, Some(sampled)) = (s, sampled) {
+            lights[0] = sampled.densities();
+        }
+        let pt = &camera_path[t - 1];
+        let qs_minus = if s >= 2 {
+            Some(&light_path[s - 2])
+        } else {
+            None
+        };
+        let pt_minus = if t >= 2 {
+            Some(&camera_path[t - 2])
+        } else {
+            None
+        };
+
+        // Densities of the vertices around the connection when reached from its other side.
+        cameras[t - 1].1 = match qs {
+            Some(qs) => qs.pdf(qs_minus, pt, context),
+            None => pt.light_origin_pdf(context),
+        };
+        if s == 0 && cameras[t - 1].1 == 0.0 {
+            // Emitters which are not lights are only ever found from the camera.
+            return 1.0;
+        }
+        if let Some(pt_minus) = pt_minus {
+            cameras[t - 2].1 = match qs {
+                Some(qs) => pt.pdf(Some(qs), pt_minus, context),
+                None => pt.emission_pdf(pt_minus),
+            };
+        }
+        if let Some(qs) = qs {
+            lights[s - 1].1 = pt.pdf(pt_minus, qs, context);
+            lights[s - 1].2 = false;
+        }
+        if let (Some(qs), Some(qs_minus)) = (qs, qs_minus) {
+            lights[s - 2].1 = qs.pdf(Some(pt), qs_minus, context);
+        }
+        cameras[t - 1].2 = false;
+
+        // Ratios of the density of every other strategy to this one, walking the connection
+        // along the camera subpath then along the light subpath. Delta vertices have no
+        // density, which cancels out in the ratios.
+        let remap = |pdf: f64| if pdf == 0.0 { 1.0 } else { pdf };
+        let splatting = context.camera.is_pinhole();
+        let mut sum = 0.0;
+
+        let mut ratio = 1.0;
+        for i in (1..t).rev() {
+            ratio *= remap(cameras[i].1) / remap(cameras[i].0);
+            if !cameras[i].2 && !cameras[i - 1].2 && (i > 1 || splatting) {
+                sum += ratio;
+            }
+        }
+
+        let mut ratio = 1.0;
+        for i in (0..s).rev() {
+            ratio *= remap(lights[i].1) / remap(lights[i].0);
+            if !lights[i].2 && (i == 0 || !lights[i - 1].2) {
+                sum += ratio;
+            }
+        }
+
+        1.0 / (1.0 + sum)
+    }
+}
+
+// Whether nothing stands between the two points.
+fn visible(context: &Context, from: &Point3, to: &Point3, time: f64) -> bool {
+    let direction = Vector3::from(to - from);
+    let distance = direction.norm();
+    let ray = Ray::with_motion(from.clone(), direction / distance, time);
+
+    context
+        .world
+        .hit(&ray, &Interval::new(0.001, distance - 0.001))
+        .is_none()
+}
+
+// Fraction of the light crossing the fog between the two points.
+fn transmittance(context: &Context, from: &Point3, to: &Point3) -> f64 {
+    match context.camera.fog() {
+        Some(fog) => fog.transmittance(&Ray::new(from.clone(), Vector3::from(to - from)), 1.0),
+        None => 1.0,
+    }
+}
+
+impl Integrator for Bidirectional {
+    fn radiance(
+        &self,
+        ray: Ray,
+        rec: Option<HitRecord>,
+        context: &Context,
+        stats: &mut PathStats,
+        splats: &mut Vec<Splat>,
+    ) -> Color {
+        let camera = context.camera;
+        let time = ray.time();
+        let mut radiance = Color::default();
+
+        let mut camera_path = vec![Vertex::camera(&ray)];
+        let pdf = camera.direction_pdf(ray.direction());
+        let end = self.walk(
+            ray,
+            rec,
+            Color::new(1.0, 1.0, 1.0),
+            pdf,
+            camera.max_depth() + 1,
+            &mut camera_path,
+            context,
+            Some(&mut radiance),
+        );
+        let scattered = camera_path.len() - 1 - usize::from(end == PathEnd::Absorbed);
+        stats.record(scattered, end);
+
+        let light_path = self.light_subpath(time, context);
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                // Lights seen straight from the camera are left to the camera subpath.
+                if s + t < 2 || (s == 1 && t == 1) || s + t - 1 > camera.max_depth() {
+                    continue;
+                }
+
+                let pt = &camera_path[t - 1];
+                if t == 1 {
+                    // Light vertex seen straight from the camera.
+                    let qs = &light_path[s - 1];
+                    if qs.delta {
+                        continue;
+                    }
+                    let Some((x, y)) = camera.raster(qs.p()) else {
+                        continue;
+                    };
+                    if !visible(context, qs.p(), pt.p(), time) {
+                        continue;
+                    }
+
+                    let direction = qs.towards(pt);
+                    let importance = camera.direction_pdf(&-&direction);
+                    let color = &qs.beta * qs.f(pt) * importance / direction.dot(&direction)
+                        * transmittance(context, qs.p(), pt.p());
+                    if !color.is_black() {
+                        let weight =
+                            self.mis_weight(&camera_path, &light_path, None, s, t, context);
+                        splats.push(Splat {
+                            x,
+                            y,
+                            color: weight * color,
+                        });
+                    }
+                    continue;
+                }
+
+                if pt.delta {
+                    continue;
+                }
+
+                let mut sampled = None;
+                let color = if s == 0 {
+                    // Camera subpath hitting an emitter.
+                    pt.beta.clone() * pt.rec.mat.emitted(&pt.incoming, &pt.rec)
+                } else {
+                    let qs = if s == 1 {
+                        // Fresh point on the lights, for each camera vertex.
+                        let Some((rec, pdf)) = context.lights.sample_surface() else {
+                            continue;
+                        };
+                        sampled = Some(Vertex::light(rec, pdf));
+                        sampled.as_ref().unwrap()
+                    } else {
+                        &light_path[s - 1]
+                    };
+                    if qs.delta {
+                        continue;
+                    }
+
+                    let direction = qs.towards(pt);
+                    let color =
+                        &qs.beta * qs.f(pt) * pt.f(qs) * &pt.beta / direction.dot(&direction);
+                    if color.is_black() || !visible(context, pt.p(), qs.p(), time) {
+                        continue;
+                    }
+                    transmittance(context, qs.p(), pt.p()) * color
+                };
+
+                if !color.is_black() {
+                    let weight =
+                        self.mis_weight(&camera_path, &light_path, sampled.as_ref(), s, t, context);
+                    radiance += weight * color;
+                }
+            }
+        }
+
+        // Delta lights start no light subpaths and are never hit, joining them to the camera
+        // vertices is the only strategy finding them.
+        for pt in camera_path.iter().take(camera.max_depth()).skip(1) {
+            if !pt.delta {
+                let lighting = context.delta_lighting(&pt.incoming, &pt.rec);
+                radiance += &pt.beta * (&pt.attenuation * lighting);
+            }
+        }
+
+        radiance
+    }
+}
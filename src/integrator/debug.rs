@@ -0,0 +1,64 @@
+use crate::integrator::{Context, Integrator, PathEnd, PathStats, Splat};
+use crate::material::color::Color;
+use crate::objects::vector3::Vector3;
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+
+// Property of the first surface hit shown by the debug view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    Normal, // Normal facing the camera, mapped from [-1, 1] to [0, 1] on each axis
+    Albedo, // Attenuation of the scattered light, or the emitted radiance for lights
+    Depth,  // exp(-distance / d), d being the distance from the camera to the point it looks at
+}
+
+// Shows a property of the surfaces seen by the camera instead of the light they send back,
+// black where nothing is hit.
+#[derive(Debug, Clone)]
+pub struct DebugView {
+    channel: Channel,
+}
+
+impl DebugView {
+    pub fn new(channel: Channel) -> DebugView {
+        DebugView { channel }
+    }
+
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+}
+
+impl Integrator for DebugView {
+    fn radiance(
+        &self,
+        ray: Ray,
+        rec: Option<HitRecord>,
+        context: &Context,
+        stats: &mut PathStats,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        let Some(rec) = rec else {
+            stats.record(0, PathEnd::Escaped);
+            return Color::default();
+        };
+        stats.record(0, PathEnd::Absorbed);
+
+        match self.channel {
+            Channel::Normal => {
+                let n = 0.5 * (&rec.normal + Vector3::new(1.0, 1.0, 1.0));
+                Color::new(n.x(), n.y(), n.z())
+            }
+            Channel::Albedo => match rec.mat.scatter(&ray, &rec) {
+                Some(srec) => srec.attenuation,
+                None => rec.mat.emitted(&ray, &rec),
+            },
+            Channel::Depth => {
+                let camera = context.camera;
+                let reference = Vector3::from(camera.lookat() - camera.position()).norm();
+                let shade = f64::exp(-rec.t * ray.direction().norm() / reference);
+                Color::new(shade, shade, shade)
+            }
+        }
+    }
+}
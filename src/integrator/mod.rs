@@ -0,0 +1,606 @@
+pub mod ambient_occlusion;
+pub mod bidirectional;
+pub mod debug;
+pub mod path;
+pub mod photon_map;
+pub mod photon_mapping;
+pub mod whitted;
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::interval::Interval;
+use crate::material::color::Color;
+use crate::objects::{HitRecord, Hittable, HittableList};
+use crate::ray::Ray;
+
+use ambient_occlusion::AmbientOcclusion;
+use bidirectional::Bidirectional;
+use debug::{Channel, DebugView};
+use path::PathTracer;
+use photon_mapping::PhotonMapping;
+use whitted::Whitted;
+
+pub const NAMES: [&str; 9] = [
+    "path", "bdpt", "photon", "sppm", "whitted", "ao", "normal", "albedo", "depth",
+];
+
+// Light transport algorithm estimating the radiance carried by the camera rays.
+pub trait Integrator: fmt::Debug + Send + Sync {
+    // Returns the radiance arriving at the camera along the ray. Its first hit is already
+    // known, as the camera also needs it for the alpha and depth channels. Light reaching other
+    // pixels, as found by tracing paths from the lights, goes to the splats.
+    fn radiance(
+        &self,
+        ray: Ray,
+        rec: Option<HitRecord>,
+        context: &Context,
+        stats: &mut PathStats,
+        splats: &mut Vec<Splat>,
+    ) -> Color;
+
+    // Count of passes over the image, whose images are averaged.
+    fn passes(&self) -> usize {
+        1
+    }
+
+    // Runs before each pass, for integrators which must look at the scene first. Returns the
+    // integrator tracing the camera rays of the pass, if not this one.
+    fn prepare(&self, _context: &Context, _pass: usize) -> Option<Arc<dyn Integrator>> {
+        None
+    }
+}
+
+// Radiance added to a pixel regardless of the camera ray being traced. Like the camera
+// samples, splats are averaged over the samples per pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Splat {
+    pub x: u32,
+    pub y: u32,
+    pub color: Color,
+}
+
+// What the integrators see of the render: the camera settings and the scene.
+pub struct Context<'a> {
+    pub camera: &'a Camera,
+    pub world: &'a dyn Hittable,
+    pub lights: &'a HittableList, // Objects worth sampling directly, also part of the world
+}
+
+impl Context<'_> {
+    pub fn hit(&self, ray: &Ray) -> Option<HitRecord> {
+        self.world.hit(ray, &Interval::new(0.001, f64::INFINITY))
+    }
+
+    // Light reaching the point straight from the delta lights, weighted by the scattering
+    // density of its material towards each of them but not yet by its albedo.
+    pub fn delta_lighting(&self, ray: &Ray, rec: &HitRecord) -> Color {
+        let mut color = Color::default();
+
+        for light in self.camera.delta_lights() {
+            let Some(incident) = light.illuminate(&rec.p) else {
+                continue;
+            };
+            let shadow = Ray::with_motion(rec.p.clone(), incident.direction, ray.time());
+            let scattering_pdf = rec.mat.scattering_pdf(ray, rec, &shadow);
+            if scattering_pdf <= 0.0 {
+                continue;
+            }
+
+            let distance = incident.distance - 0.001;
+            if self
+                .world
+                .hit(&shadow, &Interval::new(0.001, distance))
+                .is_some()
+            {
+                continue;
+            }
+            let irradiance = match self.camera.fog() {
+                Some(fog) => fog.transmittance(&shadow, incident.distance) * incident.irradiance,
+                None => incident.irradiance,
+            };
+            color += scattering_pdf * irradiance;
+        }
+
+        color
+    }
+}
+
+// Integrators selected by name, with their default settings.
+pub fn by_name(name: &str) -> Option<Arc<dyn Integrator>> {
+    match name {
+        "path" => Some(Arc::new(PathTracer)),
+        "bdpt" => Some(Arc::new(Bidirectional)),
+        "photon" => Some(Arc::new(PhotonMapping::new(100_000, None))),
+        "sppm" => Some(Arc::new(PhotonMapping::progressive(
+            100_000,
+            None,
+            64,
+            2.0 / 3.0,
+        ))),
+        "whitted" => Some(Arc::new(Whitted)),
+        "ao" => Some(Arc::new(AmbientOcclusion::new(16, f64::INFINITY))),
+        "normal" => Some(Arc::new(DebugView::new(Channel::Normal))),
+        "albedo" => Some(Arc::new(DebugView::new(Channel::Albedo))),
+        "depth" => Some(Arc::new(DebugView::new(Channel::Depth))),
+        _ => None,
+    }
+}
+
+// Lengths of the paths traced by a render, counted in bounces.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathStats {
+    lengths: Vec<u64>, // Count of paths of each length
+    terminated: u64,   // Paths ended by Russian roulette
+    truncated: u64,    // Paths cut at the maximum depth
+}
+
+// How a path came to an end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathEnd {
+    Absorbed,
+    Escaped,
+    Terminated,
+    Truncated,
+}
+
+impl PathStats {
+    pub fn record(&mut self, bounces: usize, end: PathEnd) {
+        if self.lengths.len() <= bounces {
+            self.lengths.resize(bounces + 1, 0);
+        }
+        self.lengths[bounces] += 1;
+
+        match end {
+            PathEnd::Terminated => self.terminated += 1,
+            PathEnd::Truncated => self.truncated += 1,
+            PathEnd::Absorbed | PathEnd::Escaped => (),
+        }
+    }
+
+    pub fn merge(&mut self, other: &PathStats) {
+        if self.lengths.len() < other.lengths.len() {
+            self.lengths.resize(other.lengths.len(), 0);
+        }
+        for (count, other) in self.lengths.iter_mut().zip(&other.lengths) {
+            *count += other;
+        }
+        self.terminated += other.terminated;
+        self.truncated += other.truncated;
+    }
+
+    // Count of paths of each length, indexed by their number of bounces.
+    pub fn histogram(&self) -> &[u64] {
+        &self.lengths
+    }
+
+    pub fn paths(&self) -> u64 {
+        self.lengths.iter().sum()
+    }
+
+    pub fn bounces(&self) -> u64 {
+        self.lengths
+            .iter()
+            .enumerate()
+            .map(|(length, count)| length as u64 * count)
+            .sum()
+    }
+
+    pub fn mean_length(&self) -> f64 {
+        match self.paths() {
+            0 => 0.0,
+            paths => self.bounces() as f64 / paths as f64,
+        }
+    }
+
+    pub fn longest(&self) -> usize {
+        self.lengths
+            .iter()
+            .rposition(|&count| count > 0)
+            .unwrap_or(0)
+    }
+
+    pub fn terminated(&self) -> u64 {
+        self.terminated
+    }
+
+    pub fn truncated(&self) -> u64 {
+        self.truncated
+    }
+}
+
+impl fmt::Display for PathStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = |count: u64| 100.0 * count as f64 / u64::max(self.paths(), 1) as f64;
+
+        write!(
+            f,
+            "{} paths, mean length {:.2}, longest {}, {:.1}% ended by Russian roulette, \
+             {:.1}% cut at the maximum depth",
+            self.paths(),
+            self.mean_length(),
+            self.longest(),
+            percent(self.terminated),
+            percent(self.truncated)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::background::Background;
+    use crate::environment::EnvironmentLight;
+    use crate::film::Film;
+    use crate::light::PointLight;
+    use crate::material::diffuse_light::DiffuseLight;
+    use crate::material::lambertian::Lambertian;
+    use crate::objects::point3::Point3;
+    use crate::objects::quad::Quad;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::vector3::Vector3;
+
+    fn camera() -> Camera {
+        let mut camera = Camera::new(
+            Point3::new(0.0, 0.0, 4.0),
+            Point3::default(),
+            Vector3::new(0.0, 1.0, 0.0),
+            1.0,
+            9,
+            40.0,
+            4.0,
+            0.0,
+        );
+        camera
+            .set_progress(false)
+            .set_antialiasing(4)
+            .set_seed(5)
+            .set_background(Background::Solid(Color::new(1.0, 1.0, 1.0)));
+
+        camera
+    }
+
+    fn sphere() -> HittableList {
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::default(),
+            1.0,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.25, 0.125))),
+        )));
+
+        world
+    }
+
+    #[test]
+    fn test_debug_views() {
+        let mut camera = camera();
+        let world = sphere();
+
+        camera.set_integrator(by_name("normal").unwrap());
+        let film = camera.render(&world);
+        let center = film.get(4, 4);
+        assert!(center.b() > 0.99 && (center.r() - 0.5).abs() < 0.05);
+        assert_eq!(film.get(0, 0), &Color::default());
+
+        camera.set_integrator(by_name("albedo").unwrap());
+        assert_eq!(
+            camera.render(&world).get(4, 4),
+            &Color::new(0.5, 0.25, 0.125)
+        );
+
+        // The front of the sphere is 3 units away, the point looked at 4.
+        camera.set_integrator(by_name("depth").unwrap());
+        let depth = camera.render(&world).get(4, 4).r();
+        assert!((depth - f64::exp(-0.75)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_ambient_occlusion() {
+        let mut camera = camera();
+        let mut world = sphere();
+        camera.set_integrator(Arc::new(AmbientOcclusion::new(8, f64::INFINITY)));
+
+        // A lone convex object never occludes itself.
+        let film = camera.render(&world);
+        assert_eq!(film.get(4, 4), &Color::new(1.0, 1.0, 1.0));
+        assert_eq!(film.get(0, 0), &Color::new(1.0, 1.0, 1.0));
+
+        // A wall right behind the camera hides about half of the sky of the surfaces facing it.
+        world.add(Arc::new(Quad::new(
+            Point3::new(-10.0, -10.0, 5.0),
+            Vector3::new(20.0, 0.0, 0.0),
+            Vector3::new(0.0, 20.0, 0.0),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )));
+        let center = camera.render(&world).get(4, 4).r();
+        assert!(center > 0.0 && center < 1.0);
+
+        camera.set_integrator(Arc::new(AmbientOcclusion::new(8, 0.5)));
+        assert_eq!(camera.render(&world).get(4, 4), &Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_whitted() {
+        let mut camera = camera();
+        camera
+            .set_integrator(Arc::new(Whitted))
+            .set_background(Background::black());
+
+        // A small light straight above the top of the sphere.
+        let mut world = sphere();
+        let light = Arc::new(Quad::new(
+            Point3::new(-0.05, 3.0, -0.05),
+            Vector3::new(0.1, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 0.1),
+            Arc::new(DiffuseLight::new(Color::new(100.0, 100.0, 100.0))),
+        ));
+        world.add(light.clone());
+        let mut lights = HittableList::new();
+        lights.add(light);
+
+        // The top of the sphere receives the light, the bottom only the black sky.
+        let (film, stats) = camera.render_with_stats(&world, &lights);
+        assert!(film.get(4, 2).r() > 0.0);
+        assert_eq!(film.get(4, 6), &Color::default());
+        assert_eq!(stats.longest(), 0);
+        assert_eq!(stats.truncated(), 0);
+    }
+
+    // A closed box, but for the side facing the camera, lit by a small light in its ceiling.
+    fn cornell_box() -> (HittableList, HittableList) {
+        let mut world = HittableList::new();
+        let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+        let (x, y, z) = (
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Vector3::new(0.0, 0.0, 2.0),
+        );
+        let corner = Point3::new(-1.0, -1.0, -1.0);
+        for (q, u, v) in [
+            (corner.clone(), &x, &z),
+            (&corner + &y, &x, &z),
+            (corner.clone(), &x, &y),
+            (corner.clone(), &y, &z),
+            (&corner + &x, &y, &z),
+        ] {
+            world.add(Arc::new(Quad::new(q, u.clone(), v.clone(), white.clone())));
+        }
+
+        let light = Arc::new(Quad::new(
+            Point3::new(-0.1, 0.999, -0.1),
+            Vector3::new(0.2, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 0.2),
+            Arc::new(DiffuseLight::new(Color::new(100.0, 100.0, 100.0))),
+        ));
+        world.add(light.clone());
+        let mut lights = HittableList::new();
+        lights.add(light);
+
+        (world, lights)
+    }
+
+    #[test]
+    fn test_light_sampling() {
+        let mut camera = camera();
+        // Looking at the back of the box, below the light, with a single indirect bounce.
+        camera
+            .set_vfov(20.0)
+            .set_maximum_depth(3)
+            .set_minimum_depth(3)
+            .set_antialiasing(1024)
+            .set_background(Background::black());
+        let (world, lights) = cornell_box();
+
+        // Mean of two renders with different seeds, and the variance of their pixels.
+        let estimate = |camera: &mut Camera, lights: &HittableList| {
+            let a = camera.set_seed(1).render_with_lights(&world, lights);
+            let b = camera.set_seed(2).render_with_lights(&world, lights);
+            let n = a.pixels().len() as f64;
+            let pixels = a.pixels().iter().zip(b.pixels());
+            let mean = pixels.clone().map(|(a, b)| a.g() + b.g()).sum::<f64>() / (2.0 * n);
+            let variance = pixels.map(|(a, b)| (a.g() - b.g()).powi(2)).sum::<f64>() / (2.0 * n);
+            (mean, variance)
+        };
+
+        // Sampling the light finds the same image, with far less noise.
+        let (mean, variance) = estimate(&mut camera, &HittableList::new());
+        let (sampled_mean, sampled_variance) = estimate(&mut camera, &lights);
+        assert!(mean > 0.0);
+        assert!((sampled_mean / mean - 1.0).abs() < 0.05);
+        assert!(sampled_variance < 0.1 * variance);
+    }
+
+    fn sphere_on_floor() -> HittableList {
+        let mut world = sphere();
+        world.add(Arc::new(Quad::new(
+            Point3::new(-10.0, -1.0, -10.0),
+            Vector3::new(20.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 20.0),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )));
+
+        world
+    }
+
+    // A diffuse sphere on a floor under a light, seen from slightly above.
+    fn lit_sphere() -> (HittableList, HittableList) {
+        let mut world = sphere_on_floor();
+        let light = Arc::new(Quad::new(
+            Point3::new(-1.5, 2.0, -1.5),
+            Vector3::new(3.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 3.0),
+            Arc::new(DiffuseLight::new(Color::new(2.0, 2.0, 2.0))),
+        ));
+        world.add(light.clone());
+        let mut lights = HittableList::new();
+        lights.add(light);
+
+        (world, lights)
+    }
+
+    fn mean_green(camera: &Camera, world: &HittableList, lights: &HittableList) -> f64 {
+        let (film, stats) = camera.render_with_stats(world, lights);
+        assert!(stats.longest() <= camera.max_depth());
+
+        film.pixels().iter().map(Color::g).sum::<f64>() / film.pixels().len() as f64
+    }
+
+    #[test]
+    fn test_bidirectional() {
+        let mut camera = camera();
+        camera
+            .set_antialiasing(1024)
+            .set_maximum_depth(4)
+            .set_minimum_depth(4)
+            .set_background(Background::black());
+        let (world, lights) = lit_sphere();
+
+        let path = mean_green(&camera, &world, &lights);
+        camera.set_integrator(by_name("bdpt").unwrap());
+        let bidirectional = mean_green(&camera, &world, &lights);
+        assert!(path > 0.0);
+        assert!((bidirectional / path - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_photon_mapping() {
+        let mut camera = camera();
+        camera
+            .set_antialiasing(256)
+            .set_maximum_depth(4)
+            .set_minimum_depth(4)
+            .set_background(Background::black());
+        let (world, lights) = lit_sphere();
+        let path = mean_green(&camera, &world, &lights);
+
+        // Blurring the light over the radius hardly changes the average over the image.
+        camera.set_integrator(Arc::new(PhotonMapping::new(20_000, Some(0.1))));
+        let photons = mean_green(&camera, &world, &lights);
+        assert!((photons / path - 1.0).abs() < 0.1);
+
+        // The passes share the samples per pixel.
+        camera.set_integrator(Arc::new(PhotonMapping::progressive(
+            5_000,
+            Some(0.2),
+            8,
+            2.0 / 3.0,
+        )));
+        let (film, stats) = camera.render_with_stats(&world, &lights);
+        assert_eq!(stats.paths(), 81 * 256);
+        let progressive =
+            film.pixels().iter().map(Color::g).sum::<f64>() / film.pixels().len() as f64;
+        assert!((progressive / path - 1.0).abs() < 0.05);
+
+        // Depths are averaged over all the passes, as over the samples of a single one.
+        let single = camera
+            .set_integrator(by_name("path").unwrap())
+            .render_with_lights(&world, &lights);
+        for y in 0..9 {
+            for x in 0..9 {
+                if single.alpha(x, y) > 0.0 && film.alpha(x, y) > 0.0 {
+                    assert!((film.depth(x, y) / single.depth(x, y) - 1.0).abs() < 0.05);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_photon_mapping_background() {
+        let mut camera = camera();
+        camera
+            .set_antialiasing(256)
+            .set_maximum_depth(4)
+            .set_minimum_depth(4)
+            .set_background(Background::sky());
+        let (world, lights) = (sphere_on_floor(), HittableList::new());
+        let path = mean_green(&camera, &world, &lights);
+
+        // With no other light, the sky alone lights the sphere and the floor.
+        camera.set_integrator(Arc::new(PhotonMapping::new(200_000, Some(0.2))));
+        let photons = mean_green(&camera, &world, &lights);
+        assert!((photons / path - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_environment_light() {
+        // A sky with a small sun, lighting a diffuse sphere.
+        let mut image = Film::new(32, 16);
+        for y in 0..16 {
+            for x in 0..32 {
+                let sky = if y < 8 { 0.5 } else { 0.1 };
+                image.set(x, y, Color::new(sky, sky, sky));
+            }
+        }
+        image.set(20, 4, Color::new(200.0, 200.0, 200.0));
+        let environment = Arc::new(EnvironmentLight::new(image));
+
+        let mut camera = camera();
+        camera
+            .set_antialiasing(512)
+            .set_maximum_depth(4)
+            .set_minimum_depth(4)
+            .set_background(Background::Environment(environment.clone()));
+        let world = sphere();
+        let mut lights = HittableList::new();
+        lights.add(environment);
+
+        // Sampling the sun gives the same image as waiting for rays to escape towards it.
+        let escaping = mean_green(&camera, &world, &HittableList::new());
+        let sampled = mean_green(&camera, &world, &lights);
+        assert!((sampled / escaping - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_delta_lights() {
+        let mut camera = camera();
+        camera
+            .set_antialiasing(1024)
+            .set_maximum_depth(4)
+            .set_minimum_depth(4)
+            .set_background(Background::black());
+
+        // A small spherical light above the sphere, out of view, shines like a point light of
+        // the same intensity.
+        let mut world = sphere_on_floor();
+        let bulb = Arc::new(Sphere::new(
+            Point3::new(0.0, 2.0, 0.0),
+            0.05,
+            Arc::new(DiffuseLight::new(Color::new(100.0, 100.0, 100.0))),
+        ));
+        world.add(bulb.clone());
+        let mut lights = HittableList::new();
+        lights.add(bulb);
+        let bulb = mean_green(&camera, &world, &lights);
+
+        let intensity = 100.0 * PI * 0.05 * 0.05;
+        camera.set_delta_lights(vec![Arc::new(PointLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Color::new(intensity, intensity, intensity),
+        ))]);
+        let (world, lights) = (sphere_on_floor(), HittableList::new());
+        let point = mean_green(&camera, &world, &lights);
+        assert!((point / bulb - 1.0).abs() < 0.1);
+
+        camera.set_integrator(by_name("bdpt").unwrap());
+        let bidirectional = mean_green(&camera, &world, &lights);
+        assert!((bidirectional / point - 1.0).abs() < 0.1);
+
+        camera.set_integrator(Arc::new(PhotonMapping::progressive(
+            10_000,
+            Some(0.1),
+            8,
+            2.0 / 3.0,
+        )));
+        let photons = mean_green(&camera, &world, &lights);
+        assert!((photons / point - 1.0).abs() < 0.1);
+
+        // Only the top of the sphere is lit, with no light bouncing around.
+        camera.set_integrator(by_name("whitted").unwrap());
+        let film = camera.render(&world);
+        assert!(film.get(4, 2).g() > 0.0);
+        assert_eq!(film.get(4, 7), &Color::default());
+    }
+}
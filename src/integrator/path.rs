@@ -0,0 +1,118 @@
+use crate::integrator::{Context, Integrator, PathEnd, PathStats, Splat};
+use crate::material::color::Color;
+use crate::material::{Scatter, ScatterRecord};
+use crate::objects::{HitRecord, HittableList};
+use crate::pdf::{HittablePdf, MixturePdf, Pdf};
+use crate::ray::Ray;
+use crate::rng;
+
+// Unidirectional path tracer, sampling the lights and the materials alike. Once past the
+// minimum depth, paths are ended at random with a probability growing as their throughput
+// drops, and the survivors are weighted up by as much, which keeps the estimate unbiased.
+#[derive(Debug, Clone, Default)]
+pub struct PathTracer;
+
+impl PathTracer {
+    // Draws the next direction of the path and returns the ray along it with the factor by
+    // which it scales the light coming back, or None when it carries nothing.
+    fn sample(
+        &self,
+        ray: &Ray,
+        rec: &HitRecord,
+        srec: ScatterRecord,
+        lights: &HittableList,
+    ) -> Option<(Ray, Color)> {
+        let material_pdf = match srec.scatter {
+            Scatter::Specular(scattered) => return Some((scattered, srec.attenuation)),
+            Scatter::Pdf(pdf) => pdf,
+        };
+
+        // Directions are drawn half of the time towards the lights, and weighted by the
+        // combined density whichever strategy picked them.
+        let light_pdf = HittablePdf::new(lights, rec.p.clone());
+        let mixture = MixturePdf::new(&light_pdf, material_pdf.as_ref());
+        let pdf: &dyn Pdf = if lights.is_empty() {
+            material_pdf.as_ref()
+        } else {
+            &mixture
+        };
+
+        let scattered = Ray::with_motion(rec.p.clone(), pdf.generate(), ray.time());
+        let pdf_value = pdf.value(scattered.direction());
+        if pdf_value <= 0.0 || !pdf_value.is_finite() {
+            return None;
+        }
+
+        let scattering_pdf = rec.mat.scattering_pdf(ray, rec, &scattered);
+        if scattering_pdf <= 0.0 {
+            return None;
+        }
+
+        Some((scattered, scattering_pdf / pdf_value * srec.attenuation))
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(
+        &self,
+        ray: Ray,
+        rec: Option<HitRecord>,
+        context: &Context,
+        stats: &mut PathStats,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        let mut radiance = Color::default();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let (mut ray, mut next) = (ray, rec);
+        let mut bounces = 0;
+
+        let end = loop {
+            if bounces >= context.camera.max_depth() {
+                break PathEnd::Truncated;
+            }
+
+            // Every segment of the path crosses the fog between its two ends.
+            if let Some(fog) = context.camera.fog() {
+                let t = next.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+                let transmittance = fog.transmittance(&ray, t);
+                let inscattered = fog.albedo() * &context.camera.background().color(&ray);
+                radiance += &throughput * ((1.0 - transmittance) * inscattered);
+                throughput = transmittance * throughput;
+            }
+
+            let Some(rec) = next else {
+                radiance += throughput * context.camera.background().color(&ray);
+                break PathEnd::Escaped;
+            };
+            radiance += &throughput * rec.mat.emitted(&ray, &rec);
+
+            let Some(srec) = rec.mat.scatter(&ray, &rec) else {
+                break PathEnd::Absorbed;
+            };
+            // Delta lights are only ever reached by sampling them.
+            if let Scatter::Pdf(_) = srec.scatter {
+                let lighting = context.delta_lighting(&ray, &rec);
+                radiance += &throughput * (&srec.attenuation * lighting);
+            }
+            let Some((scattered, weight)) = self.sample(&ray, &rec, srec, context.lights) else {
+                break PathEnd::Absorbed;
+            };
+            throughput = throughput * weight;
+            bounces += 1;
+
+            if bounces >= context.camera.min_depth() {
+                let survival = f64::min(throughput.max_component(), 0.95);
+                if rng::random() >= survival {
+                    break PathEnd::Terminated;
+                }
+                throughput /= survival;
+            }
+
+            next = context.hit(&scattered);
+            ray = scattered;
+        };
+
+        stats.record(bounces, end);
+        radiance
+    }
+}
@@ -0,0 +1,143 @@
+use crate::material::color::Color;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+
+// Light brought to a surface by a photon.
+#[derive(Debug, Clone)]
+pub struct Photon {
+    pub p: Point3,          // Where it landed
+    pub direction: Vector3, // Unit vector towards where it came from
+    pub normal: Vector3,    // Normal of the surface, on the side it arrived from
+    pub power: Color,       // Flux it carries
+}
+
+// Photons sorted into a balanced kd-tree for range queries. The tree is implicit: each slice of
+// the array holds the splitting photon in its middle, the photons below it on the left and those
+// above it on the right, so that only the splitting axes need storing.
+#[derive(Debug, Default)]
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    axes: Vec<usize>,
+}
+
+impl PhotonMap {
+    pub fn new(mut photons: Vec<Photon>) -> PhotonMap {
+        let mut axes = vec![0; photons.len()];
+        build(&mut photons, &mut axes);
+
+        PhotonMap { photons, axes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    // Calls the function on every photon within the radius of the point.
+    pub fn for_each_near<F: FnMut(&Photon)>(&self, p: &Point3, radius: f64, mut f: F) {
+        self.search(0, self.photons.len(), p, radius * radius, &mut f);
+    }
+
+    fn search<F: FnMut(&Photon)>(&self, lo: usize, hi: usize, p: &Point3, r2: f64, f: &mut F) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        let photon = &self.photons[mid];
+        let offset = Vector3::from(p - &photon.p);
+        if offset.dot(&offset) <= r2 {
+            f(photon);
+        }
+
+        // The far side is only worth visiting when the sphere crosses the splitting plane.
+        let d = coordinate(p, self.axes[mid]) - coordinate(&photon.p, self.axes[mid]);
+        let (near, far) = if d < 0.0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search(near.0, near.1, p, r2, f);
+        if d * d <= r2 {
+            self.search(far.0, far.1, p, r2, f);
+        }
+    }
+}
+
+fn coordinate(p: &Point3, axis: usize) -> f64 {
+    match axis {
+        0 => p.x(),
+        1 => p.y(),
+        _ => p.z(),
+    }
+}
+
+fn build(photons: &mut [Photon], axes: &mut [usize]) {
+    if photons.is_empty() {
+        return;
+    }
+
+    // Split at the median along the axis where the photons spread the most.
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for photon in photons.iter() {
+        for axis in 0..3 {
+            min[axis] = f64::min(min[axis], coordinate(&photon.p, axis));
+            max[axis] = f64::max(max[axis], coordinate(&photon.p, axis));
+        }
+    }
+    let axis = (0..3)
+        .max_by(|&a, &b| (max[a] - min[a]).total_cmp(&(max[b] - min[b])))
+        .unwrap_or(0);
+
+    let mid = photons.len() / 2;
+    photons.select_nth_unstable_by(mid, |a, b| {
+        coordinate(&a.p, axis).total_cmp(&coordinate(&b.p, axis))
+    });
+    axes[mid] = axis;
+
+    let (left, right) = photons.split_at_mut(mid);
+    let (left_axes, right_axes) = axes.split_at_mut(mid);
+    build(left, left_axes);
+    build(&mut right[1..], &mut right_axes[1..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng;
+
+    #[test]
+    fn test_range_query() {
+        rng::seed(11);
+        let photons: Vec<Photon> = (0..500)
+            .map(|n| Photon {
+                p: Point3::new(rng::random(), rng::random(), 0.1 * rng::random()),
+                direction: Vector3::new(0.0, 0.0, 1.0),
+                normal: Vector3::new(0.0, 0.0, 1.0),
+                power: Color::new(n as f64, 0.0, 0.0),
+            })
+            .collect();
+        let map = PhotonMap::new(photons.clone());
+        assert_eq!(map.len(), 500);
+
+        for _ in 0..20 {
+            let p = Point3::new(rng::random(), rng::random(), 0.0);
+            let radius = 0.2 * rng::random();
+            let mut expected: Vec<f64> = photons
+                .iter()
+                .filter(|photon| Vector3::from(&p - &photon.p).norm() <= radius)
+                .map(|photon| photon.power.r())
+                .collect();
+            let mut found = Vec::new();
+            map.for_each_near(&p, radius, |photon| found.push(photon.power.r()));
+
+            expected.sort_by(f64::total_cmp);
+            found.sort_by(f64::total_cmp);
+            assert_eq!(found, expected);
+        }
+    }
+}
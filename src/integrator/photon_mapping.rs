@@ -0,0 +1,364 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::integrator::photon_map::{Photon, PhotonMap};
+use crate::integrator::{Context, Integrator, PathEnd, PathStats, Splat};
+use crate::light::Emission;
+use crate::material::color::Color;
+use crate::material::Scatter;
+use crate::objects::onb::Onb;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::pdf::{MixturePdf, Pdf};
+use crate::ray::Ray;
+use crate::rng;
+
+// Gather radius used when none is given, as a fraction of the distance from the camera to the
+// point it looks at.
+const DEFAULT_RADIUS: f64 = 0.01;
+
+// Photon mapping. Before each pass, photons are shot from the lights and the background, and
+// stored wherever they land on a diffuse surface. Camera rays are followed through mirrors,
+// glass and media up to the first diffuse surface, whose radiance is estimated from the photons
+// within the gather radius. Caustics are then found as easily as direct light, at the cost of
+// blurring the lighting over the radius. Light arriving there straight from the background is
+// sampled instead, since its photons are spread over the whole scene.
+//
+// The progressive variant shrinks the radius from a pass to the next and averages them, after
+// the probabilistic formulation of stochastic progressive photon mapping by Knaus and Zwicker:
+// the blur of the first passes fades away while their noise averages out, so that the image
+// converges as the passes add up.
+#[derive(Debug, Clone)]
+pub struct PhotonMapping {
+    photons: usize,      // Count of photons shot for each pass
+    radius: Option<f64>, // Gather radius of the first pass, scaled to the scene if unset
+    passes: usize,       // Count of passes sharing the samples per pixel
+    alpha: f64,          // Share of the photons kept from a pass to the next, in (0, 1]
+}
+
+impl PhotonMapping {
+    pub fn new(photons: usize, radius: Option<f64>) -> PhotonMapping {
+        PhotonMapping {
+            photons: usize::max(photons, 1),
+            radius,
+            passes: 1,
+            alpha: 1.0,
+        }
+    }
+
+    pub fn progressive(
+        photons: usize,
+        radius: Option<f64>,
+        passes: usize,
+        alpha: f64,
+    ) -> PhotonMapping {
+        PhotonMapping {
+            photons: usize::max(photons, 1),
+            radius,
+            passes: usize::max(passes, 1),
+            alpha: alpha.clamp(f64::EPSILON, 1.0),
+        }
+    }
+
+    pub fn photons(&self) -> usize {
+        self.photons
+    }
+
+    pub fn radius(&self) -> Option<f64> {
+        self.radius
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    // Gather radius of the pass, each one keeping alpha of the photons of the previous pass
+    // within the radius.
+    fn pass_radius(&self, context: &Context, pass: usize) -> f64 {
+        let camera = context.camera;
+        let radius = self.radius.unwrap_or_else(|| {
+            DEFAULT_RADIUS * Vector3::from(camera.lookat() - camera.position()).norm()
+        });
+        let shrink: f64 = (1..=pass)
+            .map(|i| (i as f64 + self.alpha) / (i as f64 + 1.0))
+            .product();
+
+        radius * shrink.sqrt()
+    }
+
+    // Ray leaving a point drawn on the emitters, with the flux it carries over its density.
+    fn emit(context: &Context) -> Option<Emission> {
+        let (rec, pdf) = context.lights.sample_surface()?;
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        // Lights emit on both sides, with a cosine distribution.
+        let mut direction = Onb::new(&rec.normal).transform(&Vector3::new_random_cosine());
+        if rng::random() < 0.5 {
+            direction = -direction;
+        }
+        let cosine = f64::abs(rec.normal.dot(&direction)) / direction.norm();
+        if cosine <= 0.0 {
+            return None;
+        }
+        let emitted = rec
+            .mat
+            .emitted(&Ray::new(rec.p.clone(), direction.clone()), &rec);
+
+        Some(Emission {
+            origin: rec.p,
+            direction,
+            power: 2.0 * PI / pdf * emitted,
+        })
+    }
+
+    fn shoot(&self, context: &Context) -> Vec<Photon> {
+        let camera = context.camera;
+        let mut photons = Vec::new();
+
+        // Photons leave one of the delta lights, the emitters or the background, picked with
+        // equal probability.
+        let delta_lights = camera.delta_lights();
+        let emitters = !context.lights.is_empty();
+        let background = !camera.background().is_black();
+        let sources = delta_lights.len() + usize::from(emitters) + usize::from(background);
+        if sources == 0 {
+            return photons;
+        }
+        let bounds = context.world.bounding_box();
+
+        for _ in 0..self.photons {
+            let n = rng::random_range_u32(0, sources as u32) as usize;
+            let from_background = n == delta_lights.len() + usize::from(emitters);
+            let emission = match delta_lights.get(n) {
+                Some(light) => light.emit(bounds),
+                None if !from_background => PhotonMapping::emit(context),
+                None => camera.background().emit(bounds),
+            };
+            let Some(emission) = emission else {
+                continue;
+            };
+            let mut ray = Ray::with_motion(emission.origin, emission.direction, rng::random());
+            let power = sources as f64 / self.photons as f64 * emission.power;
+            let mut throughput = Color::new(1.0, 1.0, 1.0);
+
+            for bounces in 0..camera.max_depth() {
+                let next = context.hit(&ray);
+                if let Some(fog) = camera.fog() {
+                    let t = next.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+                    throughput = fog.transmittance(&ray, t) * throughput;
+                }
+                let Some(rec) = next else {
+                    break;
+                };
+                let Some(srec) = rec.mat.scatter(&ray, &rec) else {
+                    break;
+                };
+
+                let (scattered, weight) = match srec.scatter {
+                    Scatter::Specular(scattered) => (scattered, srec.attenuation),
+                    Scatter::Pdf(pdf) => {
+                        // The light straight from the background is found from the camera.
+                        if !rec.mat.is_volumetric() && (bounces > 0 || !from_background) {
+                            photons.push(Photon {
+                                p: rec.p.clone(),
+                                direction: -ray.direction().normalise(),
+                                normal: rec.normal.clone(),
+                                power: &throughput * &power,
+                            });
+                        }
+
+                        let scattered = Ray::with_motion(rec.p.clone(), pdf.generate(), ray.time());
+                        let pdf_value = pdf.value(scattered.direction());
+                        let scattering_pdf = rec.mat.scattering_pdf(&ray, &rec, &scattered);
+                        if pdf_value <= 0.0 || !pdf_value.is_finite() || scattering_pdf <= 0.0 {
+                            break;
+                        }
+                        (scattered, scattering_pdf / pdf_value * srec.attenuation)
+                    }
+                };
+                throughput = throughput * weight;
+
+                if bounces + 1 >= camera.min_depth() {
+                    let survival = f64::min(throughput.max_component(), 0.95);
+                    if rng::random() >= survival {
+                        break;
+                    }
+                    throughput /= survival;
+                }
+                ray = scattered;
+            }
+        }
+
+        photons
+    }
+}
+
+impl Integrator for PhotonMapping {
+    // Only reached when the camera did not prepare the pass, with no photons to gather.
+    fn radiance(
+        &self,
+        ray: Ray,
+        rec: Option<HitRecord>,
+        context: &Context,
+        stats: &mut PathStats,
+        splats: &mut Vec<Splat>,
+    ) -> Color {
+        let estimate = Estimate {
+            map: PhotonMap::default(),
+            radius: self.pass_radius(context, 0),
+        };
+
+        estimate.radiance(ray, rec, context, stats, splats)
+    }
+
+    fn passes(&self) -> usize {
+        self.passes
+    }
+
+    fn prepare(&self, context: &Context, pass: usize) -> Option<Arc<dyn Integrator>> {
+        Some(Arc::new(Estimate {
+            map: PhotonMap::new(self.shoot(context)),
+            radius: self.pass_radius(context, pass),
+        }))
+    }
+}
+
+// Camera side of a pass, estimating the radiance from its photons.
+#[derive(Debug)]
+struct Estimate {
+    map: PhotonMap,
+    radius: f64,
+}
+
+impl Estimate {
+    // Radiance leaving a diffuse surface along the ray, from the photons around the point.
+    fn gather(&self, ray: &Ray, rec: &HitRecord, attenuation: &Color) -> Color {
+        let mut flux = Color::default();
+
+        self.map.for_each_near(&rec.p, self.radius, |photon| {
+            // Photons on the other side of thin surfaces or around corners do not count.
+            let cosine = rec.normal.dot(&photon.direction);
+            if photon.normal.dot(&rec.normal) <= 0.0 || cosine <= 0.0 {
+                return;
+            }
+
+            // The scattering density includes the cosine towards the light, which the flux
+            // already accounts for.
+            let towards = Ray::with_motion(rec.p.clone(), photon.direction.clone(), ray.time());
+            let pdf = rec.mat.scattering_pdf(ray, rec, &towards);
+            flux += pdf / cosine * &photon.power;
+        });
+
+        attenuation * flux / (PI * self.radius * self.radius)
+    }
+
+    // Radiance leaving a diffuse surface along the ray, from the background in plain view of
+    // the point. Photons leave it out, as they would have to cover the whole scene to find it.
+    fn background(
+        ray: &Ray,
+        rec: &HitRecord,
+        attenuation: &Color,
+        pdf: &dyn Pdf,
+        context: &Context,
+    ) -> Color {
+        let background = context.camera.background();
+        if background.is_black() {
+            return Color::default();
+        }
+
+        let mixture = MixturePdf::new(background, pdf);
+        let scattered = Ray::with_motion(rec.p.clone(), mixture.generate(), ray.time());
+        let pdf_value = mixture.value(scattered.direction());
+        let scattering_pdf = rec.mat.scattering_pdf(ray, rec, &scattered);
+        if pdf_value <= 0.0 || !pdf_value.is_finite() || scattering_pdf <= 0.0 {
+            return Color::default();
+        }
+        if context.hit(&scattered).is_some() {
+            return Color::default();
+        }
+        let mut light = background.color(&scattered);
+        if let Some(fog) = context.camera.fog() {
+            light = fog.transmittance(&scattered, f64::INFINITY) * light;
+        }
+
+        scattering_pdf / pdf_value * attenuation * light
+    }
+}
+
+impl Integrator for Estimate {
+    fn radiance(
+        &self,
+        ray: Ray,
+        rec: Option<HitRecord>,
+        context: &Context,
+        stats: &mut PathStats,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        let camera = context.camera;
+        let mut radiance = Color::default();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let (mut ray, mut next) = (ray, rec);
+        let mut bounces = 0;
+
+        let end = loop {
+            if bounces >= camera.max_depth() {
+                break PathEnd::Truncated;
+            }
+
+            if let Some(fog) = camera.fog() {
+                let t = next.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+                let transmittance = fog.transmittance(&ray, t);
+                let inscattered = fog.albedo() * &camera.background().color(&ray);
+                radiance += &throughput * ((1.0 - transmittance) * inscattered);
+                throughput = transmittance * throughput;
+            }
+
+            let Some(rec) = next else {
+                radiance += throughput * camera.background().color(&ray);
+                break PathEnd::Escaped;
+            };
+            radiance += &throughput * rec.mat.emitted(&ray, &rec);
+            let Some(srec) = rec.mat.scatter(&ray, &rec) else {
+                break PathEnd::Absorbed;
+            };
+
+            // Paths end at the first diffuse surface, and go on through media.
+            let (scattered, weight) = match srec.scatter {
+                Scatter::Specular(scattered) => (scattered, srec.attenuation),
+                Scatter::Pdf(pdf) if !rec.mat.is_volumetric() => {
+                    let gathered = self.gather(&ray, &rec, &srec.attenuation)
+                        + Estimate::background(&ray, &rec, &srec.attenuation, &*pdf, context);
+                    radiance += throughput * gathered;
+                    break PathEnd::Absorbed;
+                }
+                Scatter::Pdf(pdf) => {
+                    let scattered = Ray::with_motion(rec.p.clone(), pdf.generate(), ray.time());
+                    let pdf_value = pdf.value(scattered.direction());
+                    let scattering_pdf = rec.mat.scattering_pdf(&ray, &rec, &scattered);
+                    if pdf_value <= 0.0 || !pdf_value.is_finite() || scattering_pdf <= 0.0 {
+                        break PathEnd::Absorbed;
+                    }
+                    (scattered, scattering_pdf / pdf_value * srec.attenuation)
+                }
+            };
+            throughput = throughput * weight;
+            bounces += 1;
+
+            if bounces >= camera.min_depth() {
+                let survival = f64::min(throughput.max_component(), 0.95);
+                if rng::random() >= survival {
+                    break PathEnd::Terminated;
+                }
+                throughput /= survival;
+            }
+
+            next = context.hit(&scattered);
+            ray = scattered;
+        };
+
+        stats.record(bounces, end);
+        radiance
+    }
+}
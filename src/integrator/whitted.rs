@@ -0,0 +1,116 @@
+use crate::integrator::{Context, Integrator, PathEnd, PathStats, Splat};
+use crate::material::color::Color;
+use crate::material::{Scatter, ScatterRecord};
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+
+// Whitted-style ray tracer. Mirrors and glass are followed up to the maximum depth, while
+// diffuse surfaces only gather the light arriving straight from the lights, with one shadow
+// ray each, plus the sky above them as an ambient term.
+#[derive(Debug, Clone, Default)]
+pub struct Whitted;
+
+impl Whitted {
+    fn trace(
+        &self,
+        ray: &Ray,
+        rec: Option<HitRecord>,
+        depth: usize,
+        context: &Context,
+        stats: &mut PathStats,
+    ) -> Color {
+        let camera = context.camera;
+        if depth >= camera.max_depth() {
+            stats.record(depth, PathEnd::Truncated);
+            return Color::default();
+        }
+
+        let t = rec.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+        let color = match rec {
+            Some(rec) => {
+                let emitted = rec.mat.emitted(ray, &rec);
+
+                match rec.mat.scatter(ray, &rec) {
+                    Some(ScatterRecord {
+                        attenuation,
+                        scatter: Scatter::Specular(scattered),
+                    }) => {
+                        let rec = context.hit(&scattered);
+                        emitted
+                            + attenuation * self.trace(&scattered, rec, depth + 1, context, stats)
+                    }
+                    Some(ScatterRecord { attenuation, .. }) => {
+                        stats.record(depth, PathEnd::Absorbed);
+                        emitted
+                            + attenuation
+                                * (self.direct(ray, &rec, context) + self.ambient(&rec, context))
+                    }
+                    None => {
+                        stats.record(depth, PathEnd::Absorbed);
+                        emitted
+                    }
+                }
+            }
+            None => {
+                stats.record(depth, PathEnd::Escaped);
+                camera.background().color(ray)
+            }
+        };
+
+        match camera.fog() {
+            Some(fog) => fog.apply(ray, t, color, &camera.background().color(ray)),
+            None => color,
+        }
+    }
+
+    // Light scattered off a diffuse surface under an unoccluded sky, divided by the albedo.
+    fn ambient(&self, rec: &HitRecord, context: &Context) -> Color {
+        let up = Ray::new(rec.p.clone(), rec.normal.clone());
+
+        context.camera.background().color(&up)
+    }
+
+    // Light reaching the point from the lights, divided by the albedo.
+    fn direct(&self, ray: &Ray, rec: &HitRecord, context: &Context) -> Color {
+        let mut color = context.delta_lighting(ray, rec);
+
+        for light in context.lights.objects() {
+            let direction = light.random(&rec.p);
+            let pdf = light.pdf_value(&rec.p, &direction);
+            if pdf <= 0.0 || !pdf.is_finite() {
+                continue;
+            }
+
+            let shadow = Ray::with_motion(rec.p.clone(), direction, ray.time());
+            let scattering_pdf = rec.mat.scattering_pdf(ray, rec, &shadow);
+            if scattering_pdf <= 0.0 {
+                continue;
+            }
+
+            // Whatever the shadow ray hits first, only emitters let light through.
+            if let Some(hit) = context.hit(&shadow) {
+                let emitted = hit.mat.emitted(&shadow, &hit);
+                let emitted = match context.camera.fog() {
+                    Some(fog) => fog.transmittance(&shadow, hit.t) * emitted,
+                    None => emitted,
+                };
+                color += scattering_pdf / pdf * emitted;
+            }
+        }
+
+        color
+    }
+}
+
+impl Integrator for Whitted {
+    fn radiance(
+        &self,
+        ray: Ray,
+        rec: Option<HitRecord>,
+        context: &Context,
+        stats: &mut PathStats,
+        _splats: &mut Vec<Splat>,
+    ) -> Color {
+        self.trace(&ray, rec, 0, context, stats)
+    }
+}
@@ -64,18 +64,15 @@ impl Interval {
 
 impl std::default::Default for Interval {
     fn default() -> Interval {
-        Interval {
-            min: f64::NEG_INFINITY,
-            max: f64::INFINITY,
-        }
+        UNIVERSE
     }
 }
 
-const UNIVERSE: Interval = Interval {
+pub const UNIVERSE: Interval = Interval {
     min: f64::NEG_INFINITY,
     max: f64::INFINITY,
 };
-const EMPTY: Interval = Interval {
+pub const EMPTY: Interval = Interval {
     min: f64::INFINITY,
     max: f64::NEG_INFINITY,
 };
@@ -0,0 +1,18 @@
+pub mod background;
+pub mod camera;
+pub mod codec;
+pub mod environment;
+pub mod film;
+pub mod fog;
+pub mod integrator;
+pub mod interval;
+pub mod light;
+pub mod material;
+pub mod objects;
+pub mod pdf;
+pub mod ppm;
+pub mod ray;
+pub mod rng;
+pub mod scene;
+pub mod sky;
+pub mod texture;
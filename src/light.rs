@@ -0,0 +1,282 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::material::color::Color;
+use crate::objects::aabb::Aabb;
+use crate::objects::onb::Onb;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::rng;
+
+// Light arriving at a point from a light.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incident {
+    pub direction: Vector3, // Unit vector towards the light
+    pub distance: f64,      // Distance to the light, infinite for lights at infinity
+    pub irradiance: Color,  // On a surface facing the light
+}
+
+// Ray leaving a light, as the start of a light path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Emission {
+    pub origin: Point3,
+    pub direction: Vector3,
+    pub power: Color, // Flux carried along the ray, over its density
+}
+
+// Light concentrated at a point or along a single direction. Rays can never hit such lights,
+// so integrators sample them directly, with a shadow ray towards them from each point lit.
+pub trait Light: Send + Sync + fmt::Debug {
+    // Light arriving at the point, regardless of what stands in the way.
+    fn illuminate(&self, p: &Point3) -> Option<Incident>;
+
+    // Ray drawn from the light towards the scene, which the bounds enclose.
+    fn emit(&self, bounds: &Aabb) -> Option<Emission>;
+}
+
+// Light arriving at the point from the position, with the intensity it sends towards the point.
+fn from_position(position: &Point3, p: &Point3, intensity: Color) -> Option<Incident> {
+    let direction = Vector3::from(position - p);
+    let distance = direction.norm();
+    if distance <= 0.0 || intensity.is_black() {
+        return None;
+    }
+
+    Some(Incident {
+        direction: direction / distance,
+        distance,
+        irradiance: intensity / (distance * distance),
+    })
+}
+
+// Light shining evenly in every direction from a point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointLight {
+    position: Point3,
+    intensity: Color, // Power per solid angle
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> PointLight {
+        PointLight {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn illuminate(&self, p: &Point3) -> Option<Incident> {
+        from_position(&self.position, p, self.intensity.clone())
+    }
+
+    fn emit(&self, _bounds: &Aabb) -> Option<Emission> {
+        Some(Emission {
+            origin: self.position.clone(),
+            direction: Vector3::new_random_unit(),
+            power: 4.0 * PI * &self.intensity,
+        })
+    }
+}
+
+// Point light shining within a cone, whose edge fades out smoothly over the falloff angle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotLight {
+    position: Point3,
+    direction: Vector3, // Unit vector along the axis of the cone
+    intensity: Color,   // Power per solid angle along the axis
+    angle: f64,         // Angle between the axis and the edge of the cone, in degrees
+    falloff: f64,       // Angle within the edge over which the light fades, in degrees
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point3,
+        direction: Vector3,
+        intensity: Color,
+        angle: f64,
+        falloff: f64,
+    ) -> SpotLight {
+        let angle = angle.clamp(0.0, 180.0);
+
+        SpotLight {
+            position,
+            direction: direction.normalise(),
+            intensity,
+            angle,
+            falloff: falloff.clamp(0.0, angle),
+        }
+    }
+
+    // Fraction of the intensity sent at an angle from the axis of the given cosine.
+    fn fade(&self, cosine: f64) -> f64 {
+        let edge = self.angle.to_radians().cos();
+        let start = (self.angle - self.falloff).to_radians().cos();
+        if cosine >= start {
+            return 1.0;
+        }
+        if cosine <= edge {
+            return 0.0;
+        }
+
+        let x = (cosine - edge) / (start - edge);
+        x * x * (3.0 - 2.0 * x)
+    }
+}
+
+impl Light for SpotLight {
+    fn illuminate(&self, p: &Point3) -> Option<Incident> {
+        let outwards = Vector3::from(p - &self.position).normalise();
+        let fade = self.fade(self.direction.dot(&outwards));
+        if fade <= 0.0 {
+            return None;
+        }
+
+        from_position(&self.position, p, fade * &self.intensity)
+    }
+
+    fn emit(&self, _bounds: &Aabb) -> Option<Emission> {
+        // Uniform over the cone.
+        let edge = self.angle.to_radians().cos();
+        let (r1, r2) = (rng::random(), rng::random());
+        let z = 1.0 - r2 * (1.0 - edge);
+        let phi = 2.0 * PI * r1;
+        let sin = f64::sqrt(1.0 - z * z);
+        let local = Vector3::new(phi.cos() * sin, phi.sin() * sin, z);
+        let solid_angle = 2.0 * PI * (1.0 - edge);
+
+        Some(Emission {
+            origin: self.position.clone(),
+            direction: Onb::new(&self.direction).transform(&local),
+            power: self.fade(z) * solid_angle * &self.intensity,
+        })
+    }
+}
+
+// Light from infinitely far away, arriving along a single direction everywhere, like sunlight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionalLight {
+    direction: Vector3, // Unit vector along which the light travels
+    irradiance: Color,  // On surfaces facing the light
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector3, irradiance: Color) -> DirectionalLight {
+        DirectionalLight {
+            direction: direction.normalise(),
+            irradiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn illuminate(&self, _p: &Point3) -> Option<Incident> {
+        if self.irradiance.is_black() {
+            return None;
+        }
+
+        Some(Incident {
+            direction: -&self.direction,
+            distance: f64::INFINITY,
+            irradiance: self.irradiance.clone(),
+        })
+    }
+
+    // Rays start from a disk facing the light, just outside the sphere around the bounds,
+    // which must be finite.
+    fn emit(&self, bounds: &Aabb) -> Option<Emission> {
+        let (center, radius) = bounding_sphere(bounds)?;
+        let disk = Onb::new(&self.direction).transform(&Vector3::new_random_in_unit_disk());
+        Some(Emission {
+            origin: &center + radius * (disk - &self.direction),
+            direction: self.direction.clone(),
+            power: PI * radius * radius * &self.irradiance,
+        })
+    }
+}
+
+// Center and radius of the sphere around the bounds, if they are finite and not empty.
+pub(crate) fn bounding_sphere(bounds: &Aabb) -> Option<(Point3, f64)> {
+    if bounds.is_empty() {
+        return None;
+    }
+    let axes = [bounds.axis(0), bounds.axis(1), bounds.axis(2)];
+    let center = Point3::new(bounds.centroid(0), bounds.centroid(1), bounds.centroid(2));
+    let radius = 0.5 * axes.iter().map(|a| a.size() * a.size()).sum::<f64>().sqrt();
+    if !radius.is_finite() || radius <= 0.0 {
+        return None;
+    }
+
+    Some((center, radius))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_illuminate() {
+        let p = Point3::new(0.0, 0.0, 0.0);
+        let point = PointLight::new(Point3::new(0.0, 2.0, 0.0), Color::new(4.0, 8.0, 0.0));
+        let incident = point.illuminate(&p).unwrap();
+        assert_eq!(incident.direction, Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(incident.distance, 2.0);
+        assert_eq!(incident.irradiance, Color::new(1.0, 2.0, 0.0));
+
+        // Full light within the cone, none outside, and a fade in between.
+        let spot = SpotLight::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            30.0,
+            10.0,
+        );
+        let at = |x: f64| spot.illuminate(&Point3::new(x, 0.0, 0.0));
+        assert_eq!(at(0.0).unwrap().irradiance, Color::new(1.0, 1.0, 1.0));
+        assert!(at(1.0).is_none());
+        let edge = at(f64::tan(25_f64.to_radians())).unwrap().irradiance.r();
+        let inside = at(f64::tan(15_f64.to_radians())).unwrap().irradiance.r();
+        assert!(0.0 < edge && edge < inside);
+
+        let directional =
+            DirectionalLight::new(Vector3::new(0.0, -2.0, 0.0), Color::new(3.0, 3.0, 3.0));
+        let incident = directional.illuminate(&p).unwrap();
+        assert_eq!(incident.direction, Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(incident.distance, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_emit() {
+        rng::seed(7);
+        let bounds = Aabb::from(&Point3::new(-1.0, -1.0, -1.0), &Point3::new(1.0, 1.0, 1.0));
+
+        // The power of the rays adds up to the flux of the light.
+        let n = 20_000;
+        let spot = SpotLight::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0),
+            40.0,
+            40.0,
+        );
+        let flux: f64 = (0..n)
+            .map(|_| spot.emit(&bounds).unwrap().power.r())
+            .sum::<f64>()
+            / n as f64;
+        // Integral of the smooth step over the cone, in closed form for the cosine.
+        let edge = 40_f64.to_radians().cos();
+        let expected = 2.0 * PI * (1.0 - edge) / 2.0;
+        assert!((flux - expected).abs() < 0.02 * expected);
+
+        // Rays from a directional light start behind the sphere around the bounds.
+        let directional =
+            DirectionalLight::new(Vector3::new(1.0, -1.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        for _ in 0..100 {
+            let emission = directional.emit(&bounds).unwrap();
+            let behind = Vector3::from(emission.origin).dot(&emission.direction);
+            assert!((behind + 3_f64.sqrt()).abs() < 1e-9);
+            assert!((emission.power.r() - 3.0 * PI).abs() < 1e-9);
+        }
+        assert!(directional.emit(&Aabb::empty()).is_none());
+    }
+}
@@ -1,106 +1,133 @@
-mod camera;
-mod interval;
-mod material;
-mod objects;
-mod ppm;
-mod ray;
-mod rng;
+mod cli;
 
-use std::rc::Rc;
-use std::vec::Vec;
+use std::env;
+use std::io::{self, Write};
+use std::process;
 
-use camera::Camera;
-use material::{color::Color, dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
-use objects::point3::Point3;
-use objects::vector3::Vector3;
-use objects::{sphere, Hittable, HittableList};
+use cli::{Command, Options, Output, Source};
+use rustracer::integrator;
+use rustracer::rng;
+use rustracer::scene::{builtin, Scene};
 
-fn main() {
-    let mut camera = Camera::new(
-        Point3::new(13.0, 2.0, 3.0),
-        Point3::default(),
-        Vector3::new(0.0, 1.0, 0.0),
-        16.0 / 9.0,
-        1200,
-        20.0,
-        10.0,
-        0.6,
-    );
-    camera.set_antialiasing(500).set_maximum_depth(50);
+// Loads the scene, seeding the random sequences first when asked, as scenes may draw from
+// them while they are built.
+fn load(options: &Options) -> Result<Scene, String> {
+    if let Some(seed) = options.seed {
+        rng::seed(seed);
+    }
 
-    let mut world = HittableList::new();
+    match &options.source {
+        Source::File(path) => Scene::load(path).map_err(|e| e.to_string()),
+        Source::Builtin(name) => {
+            builtin::by_name(name).ok_or_else(|| format!("unknown built-in scene '{}'", name))
+        }
+    }
+}
 
-    let ground_material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    let binding = sphere::Sphere::new(Point3::new(0.0, -1000.0, -1.0), 1000.0, ground_material);
-    world.add(&binding);
+fn run(options: &Options) -> Result<(), String> {
+    let mut scene = load(options)?;
 
-    let p = Point3::new(4.0, 0.2, 0.0);
-    let mut sphere = Vec::<sphere::Sphere>::new();
-    for a in 0..22_usize {
-        for b in 0..22_usize {
-            let choose_mat = rng::random();
-            let center = Point3::new(
-                f64::from(a as i32 - 11) + 0.9 * rng::random(),
-                0.2,
-                f64::from(b as i32 - 11) + 0.9 * rng::random(),
-            );
-            let motion: Option<Point3>;
+    let camera = &mut scene.camera;
+    if let Some(width) = options.width {
+        camera.set_image_width(width);
+    }
+    if let Some(aspect_ratio) = options.aspect_ratio {
+        camera.set_aspect_ratio(aspect_ratio);
+    }
+    if let Some(samples_per_pixel) = options.samples_per_pixel {
+        camera.set_antialiasing(samples_per_pixel);
+    }
+    if let Some(max_depth) = options.max_depth {
+        camera.set_maximum_depth(max_depth);
+    }
+    if let Some(min_depth) = options.min_depth {
+        camera.set_minimum_depth(min_depth);
+    }
+    if let Some(name) = &options.integrator {
+        let integrator =
+            integrator::by_name(name).ok_or_else(|| format!("unknown integrator '{}'", name))?;
+        camera.set_integrator(integrator);
+    }
+    if let Some(seed) = options.seed {
+        camera.set_seed(seed);
+    }
+    if let Some(threads) = options.threads {
+        camera.set_threads(threads);
+    }
+    camera.set_progress(options.progress);
 
-            if Vector3::from(&center - &p).norm() > 0.9 {
-                let sphere_material: Rc<dyn material::Material> = match choose_mat {
-                    0.0..0.8 => {
-                        // diffuse
-                        let albedo = Color::new(
-                            rng::random() * rng::random(),
-                            rng::random() * rng::random(),
-                            rng::random() * rng::random(),
-                        );
-                        motion =
-                            Some(&center + Vector3::new(0.0, rng::random_range(0.0, 0.5), 0.0));
-                        Rc::new(Lambertian::new(albedo))
-                    }
-                    0.8..0.95 => {
-                        // metal
-                        let albedo = Color::new(
-                            rng::random_range(0.5, 1.0),
-                            rng::random_range(0.5, 1.0),
-                            rng::random_range(0.5, 1.0),
-                        );
-                        let fuzz = rng::random_range(0.0, 0.5);
-                        motion = None;
-                        Rc::new(Metal::new(albedo, fuzz))
-                    }
-                    _ => {
-                        // glass
-                        motion = None;
-                        Rc::new(Dielectric::new(1.50))
-                    }
-                };
+    let film = scene.render();
+    if let Some(path) = &options.depth_output {
+        film.write_depth_file(path)
+            .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    }
 
-                let binding = match motion {
-                    Some(c2) => sphere::Sphere::with_motion(center, c2, 0.2, sphere_material),
-                    None => sphere::Sphere::new(center, 0.2, sphere_material),
-                };
-                sphere.push(binding);
-            }
+    match &options.output {
+        Output::File(path) => film
+            .write_file(path, options.format)
+            .map_err(|e| format!("cannot write {}: {}", path.display(), e)),
+        Output::Stdout => {
+            let mut out = io::stdout().lock();
+            film.write(&mut out, options.format)
+                .and_then(|_| out.flush())
+                .map_err(|e| format!("cannot write image: {}", e))
         }
     }
+}
+
+fn main() {
+    let command = match cli::parse(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("rustracer: {}", e);
+            eprintln!("Try 'rustracer --help' for more information.");
+            process::exit(2);
+        }
+    };
 
-    for s in sphere.iter() {
-        world.add(s as &dyn Hittable);
+    match command {
+        Command::Help => println!("{}", cli::USAGE),
+        Command::Version => println!("rustracer {}", env!("CARGO_PKG_VERSION")),
+        Command::Render(options) => {
+            if let Err(e) = run(&options) {
+                eprintln!("rustracer: {}", e);
+                process::exit(1);
+            }
+        }
     }
+}
 
-    let material1 = Rc::new(Dielectric::new(1.5));
-    let binding = sphere::Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, material1);
-    world.add(&binding);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustracer::interval::Interval;
+    use rustracer::objects::point3::Point3;
+    use rustracer::objects::vector3::Vector3;
+    use rustracer::objects::Hittable;
+    use rustracer::ray::Ray;
 
-    let material2 = Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    let binding = sphere::Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, material2);
-    world.add(&binding);
+    #[test]
+    fn test_seeded_scene() {
+        let options = match cli::parse(["--seed".to_string(), "3".to_string()]) {
+            Ok(Command::Render(options)) => options,
+            c => panic!("unexpected command {:?}", c),
+        };
 
-    let material3 = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
-    let binding = sphere::Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, material3);
-    world.add(&binding);
+        // Distances to the spheres of the cover scene, placed at random, along a grid of rays.
+        let distances = || {
+            let world = load(&options).unwrap().bvh();
+            let mut distances = Vec::new();
+            for x in -11..11 {
+                for z in -11..11 {
+                    let origin = Point3::new(f64::from(x) + 0.5, 3.0, f64::from(z) + 0.5);
+                    let ray = Ray::new(origin, Vector3::new(0.0, -1.0, 0.0));
+                    let rec = world.hit(&ray, &Interval::new(0.001, f64::INFINITY));
+                    distances.push(rec.map(|rec| rec.t));
+                }
+            }
+            distances
+        };
 
-    camera.render(&world);
+        assert_eq!(distances(), distances());
+    }
 }
@@ -2,7 +2,7 @@ use std::ops::{Add, AddAssign, Div, DivAssign, Mul};
 
 use crate::interval::Interval;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Color {
     r: f64,
     g: f64,
@@ -11,14 +11,35 @@ pub struct Color {
 
 impl Color {
     pub fn new(r: f64, g: f64, b: f64) -> Color {
-        let validity = Interval::new(0.0, 1.0);
+        // Channels hold linear radiance and are left unbounded: clamping is the job of the
+        // output stage, see `clamp` and `saturate`.
+        Color { r, g, b }
+    }
+
+    pub fn clamp(&self, range: &Interval) -> Color {
         Color {
-            r: validity.clamp(r),
-            g: validity.clamp(g),
-            b: validity.clamp(b),
+            r: range.clamp(self.r),
+            g: range.clamp(self.g),
+            b: range.clamp(self.b),
         }
     }
 
+    pub fn saturate(&self) -> Color {
+        self.clamp(&Interval::new(0.0, 1.0))
+    }
+
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    pub fn max_component(&self) -> f64 {
+        f64::max(self.r, f64::max(self.g, self.b))
+    }
+
+    pub fn is_black(&self) -> bool {
+        self.r <= 0.0 && self.g <= 0.0 && self.b <= 0.0
+    }
+
     pub fn r(&self) -> f64 {
         self.r
     }
@@ -255,3 +276,37 @@ impl DivAssign<&f64> for Color {
         self.b /= other;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unclamped_arithmetic() {
+        let c = Color::new(4.0, 0.5, -1.0);
+        assert_eq!(c.r(), 4.0);
+        assert_eq!(c.b(), -1.0);
+
+        let mut acc = Color::default();
+        for _ in 0..3 {
+            acc += Color::new(0.8, 2.0, 0.1);
+        }
+        assert_eq!(acc, Color::new(0.8 * 3.0, 6.0, 0.1 + 0.1 + 0.1));
+
+        assert_eq!(&c + &c, Color::new(8.0, 1.0, -2.0));
+        assert_eq!(&c * 2.0, Color::new(8.0, 1.0, -2.0));
+        assert_eq!(&c * &Color::new(0.5, 4.0, 1.0), Color::new(2.0, 2.0, -1.0));
+        assert_eq!(c.clone() / 2.0, Color::new(2.0, 0.25, -0.5));
+    }
+
+    #[test]
+    fn test_saturate() {
+        let c = Color::new(4.0, 0.5, -1.0);
+
+        assert_eq!(c.saturate(), Color::new(1.0, 0.5, 0.0));
+        assert_eq!(c.clamp(&Interval::new(0.0, 2.0)), Color::new(2.0, 0.5, 0.0));
+        assert_eq!(c.max_component(), 4.0);
+        assert!(!c.is_black());
+        assert!(Color::default().is_black());
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     rng,
 };
 
-use super::{color::Color, Material};
+use super::{color::Color, Material, Scatter, ScatterRecord};
 
 #[derive(Debug, Default, Clone)]
 pub struct Dielectric {
@@ -25,7 +25,7 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
         let ri = if rec.front_face {
             1.0 / self.refraction_index
         } else {
@@ -43,9 +43,9 @@ impl Material for Dielectric {
             Vector3::refract(&unit_direction, &rec.normal, ri)
         };
 
-        Some((
-            Ray::with_motion(rec.p.clone(), direction, ray.time()),
-            Color::new(1.0, 1.0, 1.0),
-        ))
+        Some(ScatterRecord {
+            attenuation: Color::new(1.0, 1.0, 1.0),
+            scatter: Scatter::Specular(Ray::with_motion(rec.p.clone(), direction, ray.time())),
+        })
     }
 }
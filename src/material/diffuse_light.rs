@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use crate::texture::{SolidColor, Texture};
+use crate::{objects::HitRecord, ray::Ray};
+
+use super::{color::Color, Material};
+
+#[derive(Clone)]
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+    intensity: f64,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> DiffuseLight {
+        DiffuseLight::from_texture(Arc::new(SolidColor::new(emit)), 1.0)
+    }
+
+    // The emitted radiance is the texture scaled by the intensity.
+    pub fn from_texture(emit: Arc<dyn Texture>, intensity: f64) -> DiffuseLight {
+        DiffuseLight { emit, intensity }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn emitted(&self, _ray: &Ray, rec: &HitRecord) -> Color {
+        self.intensity * self.emit.value(rec.u, rec.v, &rec.p)
+    }
+}
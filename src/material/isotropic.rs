@@ -0,0 +1,41 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::pdf::SpherePdf;
+use crate::texture::{SolidColor, Texture};
+use crate::{objects::HitRecord, ray::Ray};
+
+use super::{color::Color, Material, Scatter, ScatterRecord};
+
+// Phase function of participating media, scattering uniformly in all directions.
+#[derive(Clone)]
+pub struct Isotropic {
+    albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Isotropic {
+        Isotropic::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Isotropic {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _ray: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord {
+            attenuation: self.albedo.value(rec.u, rec.v, &rec.p),
+            scatter: Scatter::Pdf(Box::new(SpherePdf)),
+        })
+    }
+
+    fn scattering_pdf(&self, _ray: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn is_volumetric(&self) -> bool {
+        true
+    }
+}
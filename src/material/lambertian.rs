@@ -1,32 +1,41 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
 use crate::{
-    objects::{vector3::Vector3, HitRecord},
+    objects::HitRecord,
+    pdf::CosinePdf,
     ray::Ray,
+    texture::{SolidColor, Texture},
 };
 
-use super::{color::Color, Material};
+use super::{color::Color, Material, Scatter, ScatterRecord};
 
-#[derive(Debug, Default, Clone)]
+#[derive(Clone)]
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Lambertian {
+        Lambertian::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Lambertian {
         Lambertian { albedo }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
-        let mut scatter_direction = &rec.normal + Vector3::new_random_unit();
+    fn scatter(&self, _ray: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord {
+            attenuation: self.albedo.value(rec.u, rec.v, &rec.p),
+            scatter: Scatter::Pdf(Box::new(CosinePdf::new(&rec.normal))),
+        })
+    }
 
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal.clone();
-        }
+    fn scattering_pdf(&self, _ray: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = rec.normal.dot(&scattered.direction().normalise());
 
-        Some((
-            Ray::with_motion(rec.p.clone(), scatter_direction, ray.time()),
-            self.albedo.clone(),
-        ))
+        f64::max(cosine / PI, 0.0)
     }
 }
@@ -1,16 +1,23 @@
+use std::sync::Arc;
+
+use crate::texture::{SolidColor, Texture};
 use crate::{interval::Interval, objects::vector3::Vector3, objects::HitRecord, ray::Ray};
 
 use super::color::Color;
-use super::Material;
+use super::{Material, Scatter, ScatterRecord};
 
-#[derive(Debug, Default, Clone)]
+#[derive(Clone)]
 pub struct Metal {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Metal {
+        Metal::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>, fuzz: f64) -> Metal {
         Metal {
             albedo,
             fuzz: Interval::new(0.0, 1.0).clamp(fuzz),
@@ -19,13 +26,16 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
         let mut reflected = Vector3::reflect(ray.direction(), &rec.normal);
 
         reflected = reflected.normalise() + (self.fuzz * Vector3::new_random_unit());
         let scattered = Ray::with_motion(rec.p.clone(), reflected, ray.time());
         if scattered.direction().dot(&rec.normal) > 0.0 {
-            return Some((scattered, self.albedo.clone()));
+            return Some(ScatterRecord {
+                attenuation: self.albedo.value(rec.u, rec.v, &rec.p),
+                scatter: Scatter::Specular(scattered),
+            });
         }
 
         None
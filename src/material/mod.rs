@@ -1,16 +1,46 @@
 pub mod color;
 
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
 
 use super::material::color::Color;
-use crate::{objects::HitRecord, ray::Ray};
+use crate::{objects::HitRecord, pdf::Pdf, ray::Ray};
 
-pub trait Material {
-    fn scatter(&self, _ray: &Ray, _rec: &HitRecord) -> Option<(Ray, Color)> {
+pub enum Scatter {
+    // Single direction chosen by the material, as for mirrors and glass.
+    Specular(Ray),
+    // Directions drawn from a density, which may be mixed with light sampling.
+    Pdf(Box<dyn Pdf>),
+}
+
+// Outcome of a scattering event. A path following a sampled direction d is weighted by
+// attenuation * scattering_pdf(d) / pdf(d), and a specular one by the attenuation alone.
+pub struct ScatterRecord {
+    pub attenuation: Color,
+    pub scatter: Scatter,
+}
+
+pub trait Material: Send + Sync {
+    fn scatter(&self, _ray: &Ray, _rec: &HitRecord) -> Option<ScatterRecord> {
         None
     }
+
+    fn scattering_pdf(&self, _ray: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    fn emitted(&self, _ray: &Ray, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    // Whether the material scatters light inside a volume, where hit normals carry no meaning
+    // and no cosine applies, rather than off a surface.
+    fn is_volumetric(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -1,4 +1,4 @@
-use crate::interval::Interval;
+use crate::interval::{self, Interval};
 use crate::objects::point3::Point3;
 use crate::ray::Ray;
 
@@ -11,19 +11,40 @@ pub struct Aabb {
 
 impl Aabb {
     pub fn new(x: Interval, y: Interval, z: Interval) -> Aabb {
-        Aabb {
-            x,
-            y,
-            z,
-        }
+        let mut bbox = Aabb { x, y, z };
+        bbox.pad_to_minimums();
+
+        bbox
     }
 
     pub fn from(a: &Point3, b: &Point3) -> Aabb {
-        Aabb {
+        let mut bbox = Aabb {
             x: if a.x() <= b.x() { Interval::new(a.x(), b.x()) } else { Interval::new(b.x(), a.x()) },
             y: if a.y() <= b.y() { Interval::new(a.y(), b.y()) } else { Interval::new(b.y(), a.y()) },
             z: if a.z() <= b.z() { Interval::new(a.z(), b.z()) } else { Interval::new(b.z(), a.z()) },
+        };
+        bbox.pad_to_minimums();
+
+        bbox
+    }
+
+    fn pad_to_minimums(&mut self) {
+        // Adjust the box so that no side is narrower than some delta, padding if necessary.
+        // Flat primitives such as axis aligned triangles would otherwise never be hit.
+        let delta = 0.0001;
+        if self.x.size() >= 0.0 && self.x.size() < delta {
+            self.x = self.x.expand(&delta);
+        }
+        if self.y.size() >= 0.0 && self.y.size() < delta {
+            self.y = self.y.expand(&delta);
         }
+        if self.z.size() >= 0.0 && self.z.size() < delta {
+            self.z = self.z.expand(&delta);
+        }
+    }
+
+    pub fn empty() -> Aabb {
+        Aabb::new(interval::EMPTY, interval::EMPTY, interval::EMPTY)
     }
 
     pub fn new_enclosing(box1: &Aabb, box2: &Aabb) -> Aabb {
@@ -43,12 +64,49 @@ impl Aabb {
         }
     }
 
+    pub fn axis(&self, n: usize) -> &Interval {
+        self.get(n).unwrap_or(&interval::EMPTY)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.size() < 0.0 || self.y.size() < 0.0 || self.z.size() < 0.0
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        // Returns the index of the longest axis of the bounding box.
+        if self.x.size() > self.y.size() {
+            if self.x.size() > self.z.size() {
+                0
+            } else {
+                2
+            }
+        } else if self.y.size() > self.z.size() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn centroid(&self, axis: usize) -> f64 {
+        let ax = self.axis(axis);
+        0.5 * (ax.min() + ax.max())
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
     pub fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<Interval> {
         let ray_origin = ray.origin();
         let ray_direction = ray.direction();
         let mut rt = rayt.clone();
 
-        for axis in 0..2 {
+        for axis in 0..3 {
             let ax = self.get(axis)?;
             let adinv = 1.0 / match axis { 0 => ray_direction.x(), 1 => ray_direction.y(), 2 => ray_direction.z(), _ => return None, };
             
@@ -69,6 +127,10 @@ impl Aabb {
                     rt.max()
                 };
 
+                if max <= min {
+                    return None;
+                }
+
                 rt.set_min(min);
                 rt.set_max(max);
             } else {
@@ -95,3 +157,42 @@ impl Aabb {
         Some(rt)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::vector3::Vector3;
+
+    #[test]
+    fn test_hit_all_axes() {
+        let bbox = Aabb::from(&Point3::new(-1.0, -1.0, -1.0), &Point3::new(1.0, 1.0, 1.0));
+        let rayt = Interval::new(0.0, f64::INFINITY);
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let t = bbox.hit(&ray, &rayt).unwrap();
+        assert_eq!(t.min(), 4.0);
+        assert_eq!(t.max(), 6.0);
+
+        // Passes above the box in z only: must be rejected by the third slab.
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(1.0, 1.0, 0.0));
+        assert!(bbox.hit(&ray, &rayt).is_none());
+
+        let ray = Ray::new(Point3::new(3.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(bbox.hit(&ray, &rayt).is_none());
+    }
+
+    #[test]
+    fn test_enclosing() {
+        let a = Aabb::from(&Point3::new(0.0, 0.0, 0.0), &Point3::new(1.0, 1.0, 1.0));
+        let b = Aabb::from(&Point3::new(2.0, -1.0, 0.5), &Point3::new(3.0, 0.0, 0.5));
+        let c = Aabb::new_enclosing(&Aabb::empty(), &Aabb::new_enclosing(&a, &b));
+
+        assert_eq!(c.axis(0).min(), 0.0);
+        assert_eq!(c.axis(0).max(), 3.0);
+        assert_eq!(c.axis(1).min(), -1.0);
+        assert_eq!(c.longest_axis(), 0);
+        assert_eq!(c.surface_area(), 2.0 * (6.0 + 2.0 + 3.0));
+        assert!(Aabb::empty().is_empty());
+        assert_eq!(Aabb::empty().surface_area(), 0.0);
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use crate::interval::Interval;
+use crate::objects::{HitRecord, Hittable, HittableList};
+use crate::ray::Ray;
+
+use super::aabb::Aabb;
+
+// Relative cost of traversing an inner node against intersecting one primitive.
+const TRAVERSAL_COST: f64 = 0.125;
+
+pub struct BvhNode {
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> BvhNode {
+        let mut objects = list.objects().to_vec();
+
+        BvhNode::from_objects(&mut objects)
+    }
+
+    pub fn from_objects(objects: &mut [Arc<dyn Hittable>]) -> BvhNode {
+        let bbox = objects.iter().fold(Aabb::empty(), |bbox, o| {
+            Aabb::new_enclosing(&bbox, o.bounding_box())
+        });
+
+        let (left, right): (Arc<dyn Hittable>, Arc<dyn Hittable>) = match objects.len() {
+            0 => {
+                let empty: Arc<dyn Hittable> = Arc::new(HittableList::new());
+                (empty.clone(), empty)
+            }
+            1 => (objects[0].clone(), objects[0].clone()),
+            2 => (objects[0].clone(), objects[1].clone()),
+            _ => {
+                let (axis, mid) = BvhNode::split(objects, &bbox);
+                sort_by_centroid(objects, axis);
+
+                let (l, r) = objects.split_at_mut(mid);
+                (BvhNode::child(l), BvhNode::child(r))
+            }
+        };
+
+        BvhNode { left, right, bbox }
+    }
+
+    fn child(objects: &mut [Arc<dyn Hittable>]) -> Arc<dyn Hittable> {
+        if objects.len() == 1 {
+            return objects[0].clone();
+        }
+
+        Arc::new(BvhNode::from_objects(objects))
+    }
+
+    fn split(objects: &mut [Arc<dyn Hittable>], bbox: &Aabb) -> (usize, usize) {
+        // Pick the axis and split position minimising the surface area heuristic. Falls back
+        // to a median split along the longest axis when the bounding box is degenerate.
+        let n = objects.len();
+        let parent_area = bbox.surface_area();
+        if parent_area <= 0.0 || !parent_area.is_finite() {
+            return (bbox.longest_axis(), n / 2);
+        }
+
+        let mut best = (bbox.longest_axis(), n / 2);
+        let mut best_cost = f64::INFINITY;
+        let mut right_areas = vec![0.0; n];
+
+        for axis in 0..3 {
+            sort_by_centroid(objects, axis);
+
+            let mut right = Aabb::empty();
+            for i in (1..n).rev() {
+                right = Aabb::new_enclosing(&right, objects[i].bounding_box());
+                right_areas[i] = right.surface_area();
+            }
+
+            let mut left = Aabb::empty();
+            for i in 1..n {
+                left = Aabb::new_enclosing(&left, objects[i - 1].bounding_box());
+                let cost = TRAVERSAL_COST
+                    + (left.surface_area() * i as f64 + right_areas[i] * (n - i) as f64)
+                        / parent_area;
+
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = (axis, i);
+                }
+            }
+        }
+
+        best
+    }
+}
+
+fn sort_by_centroid(objects: &mut [Arc<dyn Hittable>], axis: usize) {
+    objects.sort_by(|a, b| {
+        // Unbounded primitives such as planes have no centroid, total_cmp keeps the order total.
+        a.bounding_box()
+            .centroid(axis)
+            .total_cmp(&b.bounding_box().centroid(axis))
+    });
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        self.bbox.hit(ray, rayt)?;
+
+        let hit_left = self.left.hit(ray, rayt);
+        let max = match &hit_left {
+            Some(rec) => rec.t,
+            None => rayt.max(),
+        };
+        let hit_right = self.right.hit(ray, &Interval::new(rayt.min(), max));
+
+        hit_right.or(hit_left)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+    use crate::objects::point3::Point3;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::vector3::Vector3;
+    use crate::rng;
+
+    fn random_scene(count: usize) -> HittableList {
+        let mut world = HittableList::new();
+
+        for i in 0..count {
+            let center = Point3::new(
+                rng::random_range(-50.0, 50.0),
+                rng::random_range(-50.0, 50.0),
+                rng::random_range(-50.0, 50.0),
+            );
+            let r = rng::random_range(0.1, 3.0);
+            let sphere = if i % 4 == 0 {
+                let c2 = &center + Vector3::new(0.0, rng::random_range(0.0, 2.0), 0.0);
+                Sphere::with_motion(center, c2, r, Arc::new(DefaultMaterial))
+            } else {
+                Sphere::new(center, r, Arc::new(DefaultMaterial))
+            };
+            world.add(Arc::new(sphere));
+        }
+
+        world
+    }
+
+    fn random_ray() -> Ray {
+        let origin = Point3::new(
+            rng::random_range(-80.0, 80.0),
+            rng::random_range(-80.0, 80.0),
+            rng::random_range(-80.0, 80.0),
+        );
+        let target = Point3::new(
+            rng::random_range(-50.0, 50.0),
+            rng::random_range(-50.0, 50.0),
+            rng::random_range(-50.0, 50.0),
+        );
+
+        Ray::with_motion(
+            origin.clone(),
+            Vector3::from(&target - &origin),
+            rng::random(),
+        )
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        for (seed, count) in [(1, 1), (2, 2), (3, 3), (4, 17), (5, 250), (6, 2000)] {
+            rng::seed(seed);
+            let world = random_scene(count);
+            let bvh = BvhNode::new(world.clone());
+            let rayt = Interval::new(0.001, f64::INFINITY);
+
+            let mut hits = 0;
+            for _ in 0..2000 {
+                let ray = random_ray();
+                match (world.hit(&ray, &rayt), bvh.hit(&ray, &rayt)) {
+                    (None, None) => {}
+                    (Some(a), Some(b)) => {
+                        hits += 1;
+                        assert_eq!(a.t, b.t);
+                        assert_eq!(a.p, b.p);
+                        assert_eq!(a.normal, b.normal);
+                        assert_eq!(a.front_face, b.front_face);
+                    }
+                    (a, b) => panic!(
+                        "seed {}: brute force hit {}, bvh hit {}",
+                        seed,
+                        a.is_some(),
+                        b.is_some()
+                    ),
+                }
+            }
+
+            assert!(hits > 0);
+        }
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        rng::seed(42);
+        let world = random_scene(100);
+        let bvh = BvhNode::new(world.clone());
+
+        for axis in 0..3 {
+            assert_eq!(
+                bvh.bounding_box().axis(axis).min(),
+                world.bounding_box().axis(axis).min()
+            );
+            assert_eq!(
+                bvh.bounding_box().axis(axis).max(),
+                world.bounding_box().axis(axis).max()
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let bvh = BvhNode::new(HittableList::new());
+        let ray = Ray::new(Point3::default(), Vector3::new(0.0, 0.0, 1.0));
+
+        assert!(bvh
+            .hit(&ray, &Interval::new(0.001, f64::INFINITY))
+            .is_none());
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use crate::interval::{self, Interval};
+use crate::material::color::Color;
+use crate::material::isotropic::Isotropic;
+use crate::material::Material;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::rng;
+
+use super::aabb::Aabb;
+
+// Homogeneous volume filling a closed boundary. Rays travelling through it scatter after an
+// exponentially distributed distance, and pass through unaffected otherwise.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable>,
+    neg_inv_density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Arc<dyn Hittable>, density: f64, albedo: Color) -> ConstantMedium {
+        ConstantMedium::with_phase_function(boundary, density, Arc::new(Isotropic::new(albedo)))
+    }
+
+    pub fn with_phase_function(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> ConstantMedium {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        // Entry and exit points of the whole line, so rays starting inside the volume work too.
+        // The boundary must be convex for the segment between them to lie inside.
+        let rec1 = self.boundary.hit(ray, &interval::UNIVERSE)?;
+        let rec2 = self
+            .boundary
+            .hit(ray, &Interval::new(rec1.t + 0.0001, f64::INFINITY))?;
+
+        let t1 = rec1.t.max(rayt.min()).max(0.0);
+        let t2 = rec2.t.min(rayt.max());
+        if t1 >= t2 {
+            return None;
+        }
+
+        let ray_length = ray.direction().norm();
+        let distance_inside_boundary = (t2 - t1) * ray_length;
+        let hit_distance = self.neg_inv_density * f64::ln(rng::random());
+        if hit_distance > distance_inside_boundary {
+            return None;
+        }
+
+        let mut rec = HitRecord::new();
+        rec.t = t1 + hit_distance / ray_length;
+        rec.p = ray.at(rec.t);
+        // Arbitrary, a medium has no surface.
+        rec.normal = Vector3::new(1.0, 0.0, 0.0);
+        rec.front_face = true;
+        rec.mat = self.phase_function.clone();
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+    use crate::objects::point3::Point3;
+    use crate::objects::sphere::Sphere;
+
+    #[test]
+    fn test_free_flight() {
+        rng::seed(11);
+        let boundary = Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(DefaultMaterial),
+        ));
+        let medium = ConstantMedium::new(boundary, 0.5, Color::new(1.0, 1.0, 1.0));
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        // A unit length ray crossing the diameter: the scatter probability is 1 - exp(-2 * 0.5).
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let n = 20000;
+        let mut hits = 0;
+        for _ in 0..n {
+            if let Some(rec) = medium.hit(&ray, &rayt) {
+                assert!(rec.t > 4.0 && rec.t < 6.0);
+                hits += 1;
+            }
+        }
+        let expected = 1.0 - f64::exp(-1.0);
+        assert!((hits as f64 / n as f64 - expected).abs() < 0.02);
+
+        // Rays starting inside only cross the remaining part.
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 2.0));
+        assert!((0..100).all(|_| medium.hit(&ray, &rayt).is_none_or(|rec| rec.t < 0.5)));
+
+        let ray = Ray::new(Point3::new(0.0, 5.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!(medium.hit(&ray, &rayt).is_none());
+    }
+}
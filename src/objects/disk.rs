@@ -0,0 +1,138 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::objects::plane::tangents;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+
+use super::aabb::Aabb;
+
+#[derive(Clone)]
+pub struct Disk {
+    center: Point3,
+    normal: Vector3,
+    radius: f64,
+    tangents: (Vector3, Vector3),
+    material: Arc<dyn Material>,
+
+    bbox: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vector3, radius: f64, material: Arc<dyn Material>) -> Disk {
+        let normal = normal.normalise();
+
+        // A circle of radius r spans r * sin(angle between the normal and the axis) on each axis.
+        let extent = |n: f64| radius * f64::sqrt((1.0 - n * n).max(0.0));
+        let e = Vector3::new(extent(normal.x()), extent(normal.y()), extent(normal.z()));
+        let bbox = Aabb::from(&(&center - &e), &(&center + &e));
+
+        Disk {
+            tangents: tangents(&normal),
+            center,
+            normal,
+            radius,
+            material,
+            bbox,
+        }
+    }
+
+    fn random_point(&self) -> Point3 {
+        let offset = self.radius * Vector3::new_random_in_unit_disk();
+
+        &self.center + (offset.x() * &self.tangents.0) + (offset.y() * &self.tangents.1)
+    }
+
+    pub fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = self.normal.dot(&Vector3::from(&self.center - ray.origin())) / denom;
+        if !rayt.contains(t) {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let radial = Vector3::from(&p - &self.center);
+        let distance = radial.norm();
+        if distance > self.radius {
+            return None;
+        }
+
+        // Polar texture coordinates: the angle around the normal and the distance to the center.
+        let phi = f64::atan2(radial.dot(&self.tangents.1), radial.dot(&self.tangents.0));
+
+        let mut rec = HitRecord::new();
+        rec.t = t;
+        rec.p = p;
+        rec.u = (phi + PI) / (2.0 * PI);
+        rec.v = distance / self.radius;
+        rec.mat = self.material.clone();
+        rec.set_face_normal(ray, &self.normal);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        super::planar_pdf_value(self, origin, direction, self.area(), &self.normal)
+    }
+
+    fn random(&self, origin: &Point3) -> Vector3 {
+        Vector3::from(&self.random_point() - origin)
+    }
+
+    fn sample_surface(&self) -> Option<(HitRecord, f64)> {
+        let rec = super::surface_record(self, &self.random_point(), &self.normal)?;
+
+        Some((rec, 1.0 / self.area()))
+    }
+
+    fn surface_pdf(&self, ray: &Ray, t: f64) -> f64 {
+        super::surface_density(self, ray, t, self.area())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+
+    #[test]
+    fn test_hit() {
+        let disk = Disk::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            2.0,
+            Arc::new(DefaultMaterial),
+        );
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        assert_eq!(disk.bounding_box().axis(0).max(), 2.0);
+        assert!(disk.bounding_box().axis(2).size() < 0.001);
+
+        let ray = Ray::new(Point3::new(1.0, 1.0, 2.0), Vector3::new(0.0, 0.0, -1.0));
+        let rec = disk.hit(&ray, &rayt).unwrap();
+        assert_eq!(rec.t, 2.0);
+        assert!((rec.v - f64::sqrt(2.0) / 2.0).abs() < 1e-12);
+
+        // Inside the bounding box corner, outside the disk.
+        let ray = Ray::new(Point3::new(1.9, 1.9, 2.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(disk.hit(&ray, &rayt).is_none());
+    }
+}
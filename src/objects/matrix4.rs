@@ -0,0 +1,208 @@
+use std::ops;
+
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+
+// Row major 4x4 matrix of an affine transform, applied to column vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 { m }
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4::scaling(&Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn translation(offset: &Vector3) -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(factors: &Vector3) -> Matrix4 {
+        Matrix4::new([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation(axis: &Vector3, degrees: f64) -> Matrix4 {
+        // Rodrigues' formula, counterclockwise when looking down the axis.
+        let a = axis.normalise();
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let (sin, cos) = f64::to_radians(degrees).sin_cos();
+        let k = 1.0 - cos;
+
+        Matrix4::new([
+            [
+                cos + x * x * k,
+                x * y * k - z * sin,
+                x * z * k + y * sin,
+                0.0,
+            ],
+            [
+                y * x * k + z * sin,
+                cos + y * y * k,
+                y * z * k - x * sin,
+                0.0,
+            ],
+            [
+                z * x * k - y * sin,
+                z * y * k + x * sin,
+                cos + z * z * k,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        self.m[row][column]
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut t = [[0.0; 4]; 4];
+        for (i, row) in self.m.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                t[j][i] = value;
+            }
+        }
+
+        Matrix4::new(t)
+    }
+
+    pub fn inverse(&self) -> Option<Matrix4> {
+        // Gauss-Jordan elimination with partial pivoting.
+        let mut a = self.m;
+        let mut inv = Matrix4::identity().m;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap_or(column);
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inv.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inv[column][j] *= scale;
+            }
+
+            for row in 0..4 {
+                let factor = a[row][column];
+                if row == column || factor == 0.0 {
+                    continue;
+                }
+                for j in 0..4 {
+                    a[row][j] -= factor * a[column][j];
+                    inv[row][j] -= factor * inv[column][j];
+                }
+            }
+        }
+
+        Some(Matrix4::new(inv))
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        let (x, y, z) = (p.x(), p.y(), p.z());
+
+        Point3::new(
+            m[0][0] * x + m[0][1] * y + m[0][2] * z + m[0][3],
+            m[1][0] * x + m[1][1] * y + m[1][2] * z + m[1][3],
+            m[2][0] * x + m[2][1] * y + m[2][2] * z + m[2][3],
+        )
+    }
+
+    pub fn transform_vector(&self, v: &Vector3) -> Vector3 {
+        let m = &self.m;
+        let (x, y, z) = (v.x(), v.y(), v.z());
+
+        Vector3::new(
+            m[0][0] * x + m[0][1] * y + m[0][2] * z,
+            m[1][0] * x + m[1][1] * y + m[1][2] * z,
+            m[2][0] * x + m[2][1] * y + m[2][2] * z,
+        )
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Matrix4 {
+        Matrix4::identity()
+    }
+}
+
+impl ops::Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+
+        Matrix4::new(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: &Matrix4, b: &Matrix4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(
+                    (a.get(i, j) - b.get(i, j)).abs() < 1e-12,
+                    "{:?} != {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotation() {
+        let r = Matrix4::rotation(&Vector3::new(0.0, 0.0, 2.0), 90.0);
+        let v = r.transform_vector(&Vector3::new(1.0, 0.0, 0.0));
+        assert!((v.x()).abs() < 1e-12 && (v.y() - 1.0).abs() < 1e-12);
+
+        // Rotations are orthogonal.
+        let r = Matrix4::rotation(&Vector3::new(1.0, 2.0, 3.0), 33.0);
+        assert_near(&(r * r.transpose()), &Matrix4::identity());
+    }
+
+    #[test]
+    fn test_inverse() {
+        let m = Matrix4::translation(&Vector3::new(1.0, -2.0, 3.0))
+            * Matrix4::rotation(&Vector3::new(0.0, 1.0, 0.0), 30.0)
+            * Matrix4::scaling(&Vector3::new(2.0, 0.5, 4.0));
+        let inverse = m.inverse().unwrap();
+
+        assert_near(&(m * inverse), &Matrix4::identity());
+        assert_near(&(inverse * m), &Matrix4::identity());
+        let p = Point3::new(1.0, 2.0, 3.0);
+        let back = inverse.transform_point(&m.transform_point(&p));
+        assert!(Vector3::from(&back - &p).norm() < 1e-12);
+        assert!(Matrix4::scaling(&Vector3::new(1.0, 0.0, 1.0))
+            .inverse()
+            .is_none());
+    }
+}
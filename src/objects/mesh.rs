@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::objects::bvh::BvhNode;
+use crate::objects::point3::Point3;
+use crate::objects::triangle;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+
+use super::aabb::Aabb;
+
+// Vertex buffers shared by all the triangles of a mesh. Normals and texture coordinates are
+// either empty or hold one entry per position.
+struct MeshData {
+    positions: Vec<Point3>,
+    normals: Vec<Vector3>,
+    uvs: Vec<(f64, f64)>,
+    material: Arc<dyn Material>,
+}
+
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    indices: [usize; 3],
+    bbox: Aabb,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [&Point3; 3] {
+        let [a, b, c] = self.indices;
+        let p = &self.mesh.positions;
+
+        [&p[a], &p[b], &p[c]]
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        let p = self.vertices();
+        let (t, b) = triangle::intersect(ray, rayt, p)?;
+
+        let [i, j, k] = self.indices;
+        let mesh = &self.mesh;
+        let normals = (!mesh.normals.is_empty())
+            .then(|| [&mesh.normals[i], &mesh.normals[j], &mesh.normals[k]]);
+        let uvs = (!mesh.uvs.is_empty()).then(|| [mesh.uvs[i], mesh.uvs[j], mesh.uvs[k]]);
+
+        Some(triangle::record(ray, t, b, p, normals, uvs, &mesh.material))
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+pub struct TriangleMesh {
+    bvh: BvhNode,
+    triangles: usize,
+}
+
+impl TriangleMesh {
+    // Builds a mesh from indexed triangles and its own bounding volume hierarchy. Panics if an
+    // index is out of range, or if normals or uvs are not empty and do not hold one entry per
+    // position.
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vector3>,
+        uvs: Vec<(f64, f64)>,
+        indices: &[[usize; 3]],
+        material: Arc<dyn Material>,
+    ) -> TriangleMesh {
+        assert!(
+            normals.is_empty() || normals.len() == positions.len(),
+            "mesh has {} normals for {} positions",
+            normals.len(),
+            positions.len()
+        );
+        assert!(
+            uvs.is_empty() || uvs.len() == positions.len(),
+            "mesh has {} texture coordinates for {} positions",
+            uvs.len(),
+            positions.len()
+        );
+
+        let mesh = Arc::new(MeshData {
+            positions,
+            normals,
+            uvs,
+            material,
+        });
+
+        let mut triangles: Vec<Arc<dyn Hittable>> = indices
+            .iter()
+            .map(|&indices| {
+                let [a, b, c] = indices;
+                let p = &mesh.positions;
+                assert!(
+                    a < p.len() && b < p.len() && c < p.len(),
+                    "mesh triangle {:?} refers to a missing vertex",
+                    indices
+                );
+
+                let bbox = triangle::bounding_box([&p[a], &p[b], &p[c]]);
+                Arc::new(MeshTriangle {
+                    mesh: mesh.clone(),
+                    indices,
+                    bbox,
+                }) as Arc<dyn Hittable>
+            })
+            .collect();
+
+        TriangleMesh {
+            bvh: BvhNode::from_objects(&mut triangles),
+            triangles: triangles.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles == 0
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        self.bvh.hit(ray, rayt)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.bvh.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+    use crate::rng;
+
+    // Unit grid of n x n quads in the z = 0 plane, each split into two triangles.
+    fn grid(n: usize) -> TriangleMesh {
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        for j in 0..=n {
+            for i in 0..=n {
+                let (u, v) = (i as f64 / n as f64, j as f64 / n as f64);
+                positions.push(Point3::new(u, v, 0.0));
+                uvs.push((u, v));
+            }
+        }
+
+        let mut indices = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let k = j * (n + 1) + i;
+                indices.push([k, k + 1, k + n + 2]);
+                indices.push([k, k + n + 2, k + n + 1]);
+            }
+        }
+
+        TriangleMesh::new(
+            positions,
+            Vec::new(),
+            uvs,
+            &indices,
+            Arc::new(DefaultMaterial),
+        )
+    }
+
+    #[test]
+    fn test_grid() {
+        rng::seed(3);
+        let mesh = grid(40);
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        assert_eq!(mesh.len(), 3200);
+        assert_eq!(mesh.bounding_box().axis(0).min(), 0.0);
+        assert_eq!(mesh.bounding_box().axis(1).max(), 1.0);
+
+        for _ in 0..1000 {
+            let (x, y) = (rng::random(), rng::random());
+            let origin = Point3::new(rng::random(), rng::random(), 1.0);
+            let target = Point3::new(x, y, 0.0);
+            let ray = Ray::new(origin.clone(), Vector3::from(&target - &origin));
+
+            // Every ray aimed inside the grid hits it, and the interpolated texture
+            // coordinates match the position on the grid.
+            let rec = mesh.hit(&ray, &rayt).unwrap();
+            assert!((rec.t - 1.0).abs() < 1e-9);
+            assert!((rec.u - x).abs() < 1e-9);
+            assert!((rec.v - y).abs() < 1e-9);
+            assert_eq!(rec.normal, Vector3::new(0.0, 0.0, 1.0));
+        }
+
+        let ray = Ray::new(Point3::new(1.5, 0.5, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(mesh.hit(&ray, &rayt).is_none());
+    }
+}
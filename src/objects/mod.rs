@@ -1,9 +1,19 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub mod aabb;
+pub mod bvh;
+pub mod constant_medium;
+pub mod disk;
+pub mod matrix4;
+pub mod mesh;
+pub mod onb;
+pub mod plane;
 pub mod point3;
-pub mod vector3;
+pub mod quad;
 pub mod sphere;
+pub mod transform;
+pub mod triangle;
+pub mod vector3;
 
 use aabb::Aabb;
 
@@ -12,14 +22,17 @@ use crate::material::{DefaultMaterial, Material};
 use crate::objects::point3::Point3;
 use crate::objects::vector3::Vector3;
 use crate::ray::Ray;
+use crate::rng;
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vector3,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
-    pub mat: Rc<dyn Material>,
+    pub mat: Arc<dyn Material>,
 }
 
 impl HitRecord {
@@ -28,8 +41,10 @@ impl HitRecord {
             p: Point3::default(),
             normal: Vector3::default(),
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
             front_face: false,
-            mat: Rc::new(DefaultMaterial::default()),
+            mat: Arc::new(DefaultMaterial),
         }
     }
 
@@ -43,36 +58,120 @@ impl HitRecord {
     }
 }
 
-pub trait Hittable {
+impl Default for HitRecord {
+    fn default() -> HitRecord {
+        HitRecord::new()
+    }
+}
+
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord>;
 
     fn bounding_box(&self) -> &Aabb;
+
+    // Density, by solid angle, of the directions returned by random for rays from the origin.
+    // Objects which cannot be sampled have none and should not be used as lights.
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vector3) -> f64 {
+        0.0
+    }
+
+    fn random(&self, _origin: &Point3) -> Vector3 {
+        Vector3::new(1.0, 0.0, 0.0)
+    }
+
+    // Point drawn uniformly over the surface, seen from the outside, with its density by area.
+    // Light paths start from such points, for the objects which support it.
+    fn sample_surface(&self) -> Option<(HitRecord, f64)> {
+        None
+    }
+
+    // Density by area of the points returned by sample_surface at ray.at(t), or zero when
+    // that point does not lie on the object.
+    fn surface_pdf(&self, _ray: &Ray, _t: f64) -> f64 {
+        0.0
+    }
+}
+
+// Record of the surface point p of an object, as hit from the side its unit normal n faces.
+fn surface_record(object: &dyn Hittable, p: &Point3, n: &Vector3) -> Option<HitRecord> {
+    let ray = Ray::new(p + n, -n.clone());
+
+    object.hit(&ray, &Interval::new(0.5, 1.5))
 }
 
-pub struct HittableList<'t> {
-    objects: Vec<Rc<&'t dyn Hittable>>,
+// Uniform area density of an object at ray.at(t), if it lies there.
+fn surface_density(object: &dyn Hittable, ray: &Ray, t: f64, area: f64) -> f64 {
+    let epsilon = 1e-6 * f64::max(t.abs(), 1.0);
+    match object.hit(ray, &Interval::new(t - epsilon, t + epsilon)) {
+        Some(_) => 1.0 / area,
+        None => 0.0,
+    }
+}
+
+// Solid angle density of directions towards uniformly distributed points of a flat object.
+fn planar_pdf_value(
+    object: &dyn Hittable,
+    origin: &Point3,
+    direction: &Vector3,
+    area: f64,
+    normal: &Vector3,
+) -> f64 {
+    let ray = Ray::new(origin.clone(), direction.clone());
+    let Some(rec) = object.hit(&ray, &Interval::new(0.001, f64::INFINITY)) else {
+        return 0.0;
+    };
+
+    let length = direction.norm();
+    let distance_squared = rec.t * rec.t * length * length;
+    let cosine = f64::abs(direction.dot(normal) / length);
+
+    distance_squared / (cosine * area)
+}
+
+#[derive(Clone)]
+pub struct HittableList {
+    objects: Vec<Arc<dyn Hittable>>,
     bbox: Aabb,
 }
 
-impl<'t> HittableList<'t> {
-    pub fn new() -> HittableList<'t> {
+impl HittableList {
+    pub fn new() -> HittableList {
         HittableList {
             objects: Vec::new(),
-            bbox: Aabb::default(),
+            bbox: Aabb::empty(),
         }
     }
 
-    pub fn add(&mut self, o: &'t dyn Hittable) {
-        self.objects.push(Rc::<&dyn Hittable>::new(o));
+    pub fn add(&mut self, o: Arc<dyn Hittable>) {
         self.bbox = Aabb::new_enclosing(&self.bbox, o.bounding_box());
+        self.objects.push(o);
     }
 
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.bbox = Aabb::empty();
+    }
+
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.objects
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
     }
 }
 
-impl<'t> Hittable for HittableList<'t> {
+impl Default for HittableList {
+    fn default() -> HittableList {
+        HittableList::new()
+    }
+}
+
+impl Hittable for HittableList {
     fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
         let mut hit_anything: bool = false;
         let mut closest_so_far = rayt.max();
@@ -97,4 +196,40 @@ impl<'t> Hittable for HittableList<'t> {
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        // Objects are picked with equal probability.
+        let weight = 1.0 / self.objects.len() as f64;
+
+        self.objects
+            .iter()
+            .map(|o| weight * o.pdf_value(origin, direction))
+            .sum()
+    }
+
+    fn random(&self, origin: &Point3) -> Vector3 {
+        let n = rng::random_range_u32(0, self.objects.len() as u32) as usize;
+
+        self.objects[n].random(origin)
+    }
+
+    fn sample_surface(&self) -> Option<(HitRecord, f64)> {
+        if self.objects.is_empty() {
+            return None;
+        }
+
+        let n = rng::random_range_u32(0, self.objects.len() as u32) as usize;
+        let (rec, pdf) = self.objects[n].sample_surface()?;
+
+        Some((rec, pdf / self.objects.len() as f64))
+    }
+
+    fn surface_pdf(&self, ray: &Ray, t: f64) -> f64 {
+        let weight = 1.0 / self.objects.len() as f64;
+
+        self.objects
+            .iter()
+            .map(|o| weight * o.surface_pdf(ray, t))
+            .sum()
+    }
 }
@@ -0,0 +1,41 @@
+use crate::objects::vector3::Vector3;
+
+// Orthonormal basis whose w axis is the given direction.
+#[derive(Debug, Clone)]
+pub struct Onb {
+    u: Vector3,
+    v: Vector3,
+    w: Vector3,
+}
+
+impl Onb {
+    pub fn new(n: &Vector3) -> Onb {
+        let w = n.normalise();
+        let a = if w.x().abs() > 0.9 {
+            Vector3::new(0.0, 1.0, 0.0)
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(&a).normalise();
+        let u = w.cross(&v);
+
+        Onb { u, v, w }
+    }
+
+    pub fn u(&self) -> &Vector3 {
+        &self.u
+    }
+
+    pub fn v(&self) -> &Vector3 {
+        &self.v
+    }
+
+    pub fn w(&self) -> &Vector3 {
+        &self.w
+    }
+
+    pub fn transform(&self, local: &Vector3) -> Vector3 {
+        // Maps coordinates in the basis to world space.
+        local.x() * &self.u + local.y() * &self.v + local.z() * &self.w
+    }
+}
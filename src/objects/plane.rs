@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use crate::interval::{self, Interval};
+use crate::material::Material;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+
+use super::aabb::Aabb;
+
+// Two unit vectors completing the unit normal n into an orthonormal basis.
+pub(super) fn tangents(n: &Vector3) -> (Vector3, Vector3) {
+    let a = if n.x().abs() > 0.9 {
+        Vector3::new(0.0, 1.0, 0.0)
+    } else {
+        Vector3::new(1.0, 0.0, 0.0)
+    };
+    let s = n.cross(&a).normalise();
+    let t = n.cross(&s);
+
+    (s, t)
+}
+
+// Infinite plane through a point. Its bounding box is only finite along the normal when the
+// plane is perpendicular to an axis.
+#[derive(Clone)]
+pub struct Plane {
+    point: Point3,
+    normal: Vector3,
+    tangents: (Vector3, Vector3),
+    material: Arc<dyn Material>,
+
+    bbox: Aabb,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vector3, material: Arc<dyn Material>) -> Plane {
+        let normal = normal.normalise();
+        let extent = |axis: usize, p: f64| {
+            let others = [normal.x(), normal.y(), normal.z()]
+                .iter()
+                .enumerate()
+                .all(|(i, &c)| i == axis || c == 0.0);
+            if others {
+                Interval::new(p, p)
+            } else {
+                interval::UNIVERSE
+            }
+        };
+        let bbox = Aabb::new(
+            extent(0, point.x()),
+            extent(1, point.y()),
+            extent(2, point.z()),
+        );
+
+        Plane {
+            tangents: tangents(&normal),
+            point,
+            normal,
+            material,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = self.normal.dot(&Vector3::from(&self.point - ray.origin())) / denom;
+        if !rayt.contains(t) {
+            return None;
+        }
+
+        // Texture coordinates are distances along the tangents, so textures repeat in world units.
+        let p = ray.at(t);
+        let planar = Vector3::from(&p - &self.point);
+
+        let mut rec = HitRecord::new();
+        rec.t = t;
+        rec.u = planar.dot(&self.tangents.0);
+        rec.v = planar.dot(&self.tangents.1);
+        rec.p = p;
+        rec.mat = self.material.clone();
+        rec.set_face_normal(ray, &self.normal);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+
+    #[test]
+    fn test_hit() {
+        let ground = Plane::new(
+            Point3::new(0.0, -1.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Arc::new(DefaultMaterial),
+        );
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        assert!(ground.bounding_box().axis(1).size() < 0.001);
+        assert_eq!(ground.bounding_box().axis(0).max(), f64::INFINITY);
+
+        let ray = Ray::new(Point3::new(1e6, 1.0, -3e5), Vector3::new(0.0, -2.0, 0.0));
+        let rec = ground.hit(&ray, &rayt).unwrap();
+        assert_eq!(rec.t, 1.0);
+        assert!(rec.front_face);
+
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(ground.hit(&ray, &rayt).is_none());
+
+        let tilted = Plane::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+            Arc::new(DefaultMaterial),
+        );
+        assert_eq!(tilted.bounding_box().axis(1).min(), f64::NEG_INFINITY);
+    }
+}
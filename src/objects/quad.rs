@@ -0,0 +1,220 @@
+use std::sync::Arc;
+
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable, HittableList};
+use crate::ray::Ray;
+use crate::rng;
+
+use super::aabb::Aabb;
+
+// Parallelogram spanned by the u and v edges from the corner q.
+#[derive(Clone)]
+pub struct Quad {
+    q: Point3,
+    u: Vector3,
+    v: Vector3,
+    w: Vector3,
+    normal: Vector3,
+    d: f64,
+    material: Arc<dyn Material>,
+
+    bbox: Aabb,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vector3, v: Vector3, material: Arc<dyn Material>) -> Quad {
+        let n = u.cross(&v);
+        let normal = n.normalise();
+        let d = normal.dot(&Vector3::from(&q));
+        let w = &n / n.dot(&n);
+
+        // Both diagonals, as the box of a single one is flat for some orientations.
+        let bbox = Aabb::new_enclosing(
+            &Aabb::from(&q, &(&q + &u + &v)),
+            &Aabb::from(&(&q + &u), &(&q + &v)),
+        );
+
+        Quad {
+            q,
+            u,
+            v,
+            w,
+            normal,
+            d,
+            material,
+            bbox,
+        }
+    }
+
+    fn random_point(&self) -> Point3 {
+        &self.q + (rng::random() * &self.u) + (rng::random() * &self.v)
+    }
+
+    pub fn area(&self) -> f64 {
+        self.u.cross(&self.v).norm()
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(&Vector3::from(ray.origin()))) / denom;
+        if !rayt.contains(t) {
+            return None;
+        }
+
+        // Coordinates of the hit point in the (u, v) frame of the plane.
+        let p = ray.at(t);
+        let planar = Vector3::from(&p - &self.q);
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+
+        let unit = Interval::new(0.0, 1.0);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+
+        let mut rec = HitRecord::new();
+        rec.t = t;
+        rec.p = p;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.mat = self.material.clone();
+        rec.set_face_normal(ray, &self.normal);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        super::planar_pdf_value(self, origin, direction, self.area(), &self.normal)
+    }
+
+    fn random(&self, origin: &Point3) -> Vector3 {
+        Vector3::from(&self.random_point() - origin)
+    }
+
+    fn sample_surface(&self) -> Option<(HitRecord, f64)> {
+        let rec = super::surface_record(self, &self.random_point(), &self.normal)?;
+
+        Some((rec, 1.0 / self.area()))
+    }
+
+    fn surface_pdf(&self, ray: &Ray, t: f64) -> f64 {
+        super::surface_density(self, ray, t, self.area())
+    }
+}
+
+// Returns the six sides of the axis aligned box with opposite corners a and b, facing out.
+pub fn make_box(a: &Point3, b: &Point3, material: Arc<dyn Material>) -> HittableList {
+    let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+    let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+    let dx = Vector3::new(max.x() - min.x(), 0.0, 0.0);
+    let dy = Vector3::new(0.0, max.y() - min.y(), 0.0);
+    let dz = Vector3::new(0.0, 0.0, max.z() - min.z());
+
+    let sides = [
+        (Point3::new(min.x(), min.y(), max.z()), &dx, &dy), // front
+        (Point3::new(max.x(), min.y(), max.z()), &-&dz, &dy), // right
+        (Point3::new(max.x(), min.y(), min.z()), &-&dx, &dy), // back
+        (Point3::new(min.x(), min.y(), min.z()), &dz, &dy), // left
+        (Point3::new(min.x(), max.y(), max.z()), &dx, &-&dz), // top
+        (Point3::new(min.x(), min.y(), min.z()), &dx, &dz), // bottom
+    ];
+
+    let mut list = HittableList::new();
+    for (q, u, v) in sides {
+        list.add(Arc::new(Quad::new(
+            q,
+            u.clone(),
+            v.clone(),
+            material.clone(),
+        )));
+    }
+
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+
+    #[test]
+    fn test_hit() {
+        let quad = Quad::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 4.0),
+            Arc::new(DefaultMaterial),
+        );
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        // Flat along y, padded to a minimal thickness.
+        assert!(quad.bounding_box().axis(1).size() > 0.0);
+        assert_eq!(quad.area(), 8.0);
+
+        let ray = Ray::new(Point3::new(0.5, 3.0, 3.0), Vector3::new(0.0, -1.0, 0.0));
+        let rec = quad.hit(&ray, &rayt).unwrap();
+        assert_eq!(rec.t, 3.0);
+        assert_eq!((rec.u, rec.v), (0.25, 0.75));
+        assert_eq!(rec.normal, Vector3::new(0.0, 1.0, 0.0));
+        assert!(!rec.front_face);
+
+        let ray = Ray::new(Point3::new(2.5, 3.0, 3.0), Vector3::new(0.0, -1.0, 0.0));
+        assert!(quad.hit(&ray, &rayt).is_none());
+    }
+
+    #[test]
+    fn test_box_faces_out() {
+        let cube = make_box(
+            &Point3::new(1.0, 1.0, 1.0),
+            &Point3::new(-1.0, -1.0, -1.0),
+            Arc::new(DefaultMaterial),
+        );
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        assert_eq!(cube.len(), 6);
+        for (origin, direction) in [
+            (Point3::new(3.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0)),
+            (Point3::new(0.0, -3.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Point3::new(0.0, 0.0, 3.0), Vector3::new(0.0, 0.0, -1.0)),
+        ] {
+            let ray = Ray::new(origin, direction);
+            let rec = cube.hit(&ray, &rayt).unwrap();
+            assert_eq!(rec.t, 2.0);
+            assert!(rec.front_face);
+        }
+    }
+
+    #[test]
+    fn test_sampling() {
+        let quad = Quad::new(
+            Point3::new(-1.0, -1.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Arc::new(DefaultMaterial),
+        );
+        let origin = Point3::new(0.0, 0.0, 1.0);
+
+        // Straight down the pdf is distance squared over area.
+        let pdf = quad.pdf_value(&origin, &Vector3::new(0.0, 0.0, -1.0));
+        assert!((pdf - 0.25).abs() < 1e-9);
+        assert_eq!(quad.pdf_value(&origin, &Vector3::new(0.0, 0.0, 1.0)), 0.0);
+
+        for _ in 0..100 {
+            assert!(quad.pdf_value(&origin, &quad.random(&origin)) > 0.0);
+        }
+    }
+}
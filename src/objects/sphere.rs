@@ -1,11 +1,14 @@
-use std::rc::Rc;
+use std::f64::consts::PI;
+use std::sync::Arc;
 
 use crate::interval::Interval;
 use crate::material::Material;
+use crate::objects::onb::Onb;
 use crate::objects::point3::Point3;
 use crate::objects::vector3::Vector3;
 use crate::objects::{HitRecord, Hittable};
 use crate::ray::Ray;
+use crate::rng;
 
 use super::aabb::Aabb;
 
@@ -13,18 +16,18 @@ use super::aabb::Aabb;
 pub struct Sphere {
     o: Point3,
     r: f64,
-    material: Rc<dyn Material>,
+    material: Arc<dyn Material>,
     center: Ray,
 
     bbox: Aabb,
 }
 
 impl Sphere {
-    pub fn new(o: Point3, r: f64, material: Rc<dyn Material>) -> Sphere {
+    pub fn new(o: Point3, r: f64, material: Arc<dyn Material>) -> Sphere {
         Sphere::with_motion(o.clone(), o, r, material)
     }
 
-    pub fn with_motion(o: Point3, o2: Point3, r: f64, material: Rc<dyn Material>) -> Sphere {
+    pub fn with_motion(o: Point3, o2: Point3, r: f64, material: Arc<dyn Material>) -> Sphere {
         let center = Ray::new(o.clone(), Vector3::from(&o2 - &o));
         let rvec = Vector3::new(r, r, r);
         let box1 = Aabb::from(&(center.at(0.0) - &rvec), &(center.at(0.0) + &rvec));
@@ -52,6 +55,15 @@ impl Sphere {
     pub fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
+
+    // Texture coordinates of a point on the unit sphere: u is the angle around the y axis from
+    // x = -1, v the angle from y = -1 to y = +1, both mapped to [0, 1].
+    fn uv(p: &Vector3) -> (f64, f64) {
+        let theta = f64::acos(-p.y());
+        let phi = f64::atan2(-p.z(), p.x()) + PI;
+
+        (phi / (2.0 * PI), theta / PI)
+    }
 }
 
 impl Hittable for Sphere {
@@ -83,6 +95,7 @@ impl Hittable for Sphere {
         rec.mat = self.material.clone();
         let outward_normal = Vector3::from(&rec.p - &current_center) / self.r();
         rec.set_face_normal(ray, &outward_normal);
+        (rec.u, rec.v) = Sphere::uv(&outward_normal);
 
         Some(rec)
     }
@@ -90,4 +103,100 @@ impl Hittable for Sphere {
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        // Uniform over the cone of directions towards the sphere, at its starting position.
+        let ray = Ray::new(origin.clone(), direction.clone());
+        if self
+            .hit(&ray, &Interval::new(0.001, f64::INFINITY))
+            .is_none()
+        {
+            return 0.0;
+        }
+
+        // From inside the sphere, or on it, directions are drawn over the whole sphere.
+        let distance_squared = Vector3::from(&self.center.at(0.0) - origin).norm().powi(2);
+        if distance_squared <= self.r * self.r {
+            return 1.0 / (4.0 * PI);
+        }
+        let cos_theta_max = f64::sqrt(1.0 - self.r * self.r / distance_squared);
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: &Point3) -> Vector3 {
+        let direction = Vector3::from(&self.center.at(0.0) - origin);
+        let distance_squared = direction.dot(&direction);
+        if distance_squared <= self.r * self.r {
+            return Vector3::new_random_unit();
+        }
+        let uvw = Onb::new(&direction);
+
+        let (r1, r2) = (rng::random(), rng::random());
+        let z = 1.0 + r2 * (f64::sqrt(1.0 - self.r * self.r / distance_squared) - 1.0);
+        let phi = 2.0 * PI * r1;
+        let x = f64::cos(phi) * f64::sqrt(1.0 - z * z);
+        let y = f64::sin(phi) * f64::sqrt(1.0 - z * z);
+
+        uvw.transform(&Vector3::new(x, y, z))
+    }
+
+    fn sample_surface(&self) -> Option<(HitRecord, f64)> {
+        let normal = Vector3::new_random_unit();
+        let p = &self.center.at(0.0) + (self.r * &normal);
+        let rec = super::surface_record(self, &p, &normal)?;
+
+        Some((rec, 1.0 / (4.0 * PI * self.r * self.r)))
+    }
+
+    fn surface_pdf(&self, ray: &Ray, t: f64) -> f64 {
+        super::surface_density(self, ray, t, 4.0 * PI * self.r * self.r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+
+    #[test]
+    fn test_uv() {
+        let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 2.0, Arc::new(DefaultMaterial));
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        let ray = Ray::new(Point3::new(5.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        let rec = sphere.hit(&ray, &rayt).unwrap();
+        assert_eq!((rec.u, rec.v), (0.5, 0.5));
+
+        let ray = Ray::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        assert_eq!(sphere.hit(&ray, &rayt).unwrap().v, 1.0);
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        assert_eq!(sphere.hit(&ray, &rayt).unwrap().u, 0.25);
+    }
+
+    #[test]
+    fn test_sampling() {
+        let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 2.0, Arc::new(DefaultMaterial));
+        let origin = Point3::new(0.0, 0.0, 4.0);
+        // The sphere covers a cone of half angle 30 degrees from the origin.
+        let expected = 1.0 / (2.0 * PI * (1.0 - f64::sqrt(0.75)));
+
+        for _ in 0..100 {
+            let direction = sphere.random(&origin);
+            assert!((sphere.pdf_value(&origin, &direction) - expected).abs() < 1e-9);
+        }
+        assert_eq!(sphere.pdf_value(&origin, &Vector3::new(0.0, 0.0, 1.0)), 0.0);
+
+        // Inside the sphere, or on it, every direction is drawn alike.
+        for origin in [Point3::new(0.5, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0)] {
+            for _ in 0..100 {
+                let direction = sphere.random(&origin);
+                assert!((direction.norm() - 1.0).abs() < 1e-9);
+                let pdf = sphere.pdf_value(&origin, &direction);
+                assert!(pdf == 0.0 || (pdf - 1.0 / (4.0 * PI)).abs() < 1e-12);
+            }
+        }
+    }
 }
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use crate::interval::Interval;
+use crate::objects::matrix4::Matrix4;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+
+use super::aabb::Aabb;
+
+// Invertible affine transform, kept along with its inverse.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix4) -> Option<Transform> {
+        Some(Transform {
+            inverse: matrix.inverse()?,
+            matrix,
+        })
+    }
+
+    pub fn identity() -> Transform {
+        Transform::default()
+    }
+
+    pub fn translate(offset: &Vector3) -> Transform {
+        Transform {
+            matrix: Matrix4::translation(offset),
+            inverse: Matrix4::translation(&-offset),
+        }
+    }
+
+    pub fn rotate(axis: &Vector3, degrees: f64) -> Transform {
+        let matrix = Matrix4::rotation(axis, degrees);
+
+        Transform {
+            inverse: matrix.transpose(),
+            matrix,
+        }
+    }
+
+    // Panics if a factor is zero.
+    pub fn scale(factors: &Vector3) -> Transform {
+        assert!(
+            factors.x() != 0.0 && factors.y() != 0.0 && factors.z() != 0.0,
+            "cannot scale by {}",
+            factors
+        );
+
+        Transform {
+            matrix: Matrix4::scaling(factors),
+            inverse: Matrix4::scaling(&Vector3::new(
+                1.0 / factors.x(),
+                1.0 / factors.y(),
+                1.0 / factors.z(),
+            )),
+        }
+    }
+
+    // Composition applying this transform first, then the other one.
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            matrix: other.matrix * self.matrix,
+            inverse: self.inverse * other.inverse,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn point(&self, p: &Point3) -> Point3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: &Vector3) -> Vector3 {
+        self.matrix.transform_vector(v)
+    }
+
+    pub fn normal(&self, n: &Vector3) -> Vector3 {
+        // Normals stay perpendicular to the surface under the inverse transpose.
+        self.inverse.transpose().transform_vector(n).normalise()
+    }
+
+    pub fn ray(&self, ray: &Ray) -> Ray {
+        // The direction is not normalised, so distances along the ray are preserved.
+        Ray::with_motion(
+            self.point(ray.origin()),
+            self.vector(ray.direction()),
+            ray.time(),
+        )
+    }
+
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        // Each output axis spans the sums of the extreme contributions of every input axis, which
+        // leaves the infinite extents of unbounded boxes intact where other axes do not mix in.
+        let m = &self.matrix;
+        let axis = |i: usize| {
+            let (mut min, mut max) = (m.get(i, 3), m.get(i, 3));
+            for j in 0..3 {
+                let factor = m.get(i, j);
+                if factor == 0.0 {
+                    continue;
+                }
+
+                let (a, b) = (factor * bbox.axis(j).min(), factor * bbox.axis(j).max());
+                min += a.min(b);
+                max += a.max(b);
+            }
+
+            Interval::new(min, max)
+        };
+
+        Aabb::new(axis(0), axis(1), axis(2))
+    }
+}
+
+// Instance of an object placed in the world by a transform. The object is shared, so the same
+// geometry can be instanced any number of times.
+pub struct Transformed<H: Hittable + ?Sized> {
+    object: Arc<H>,
+    transform: Transform,
+    bbox: Aabb,
+}
+
+impl<H: Hittable + ?Sized> Transformed<H> {
+    pub fn new(object: Arc<H>, transform: Transform) -> Transformed<H> {
+        let bbox = transform.bounding_box(object.bounding_box());
+
+        Transformed {
+            object,
+            transform,
+            bbox,
+        }
+    }
+
+    pub fn object(&self) -> &Arc<H> {
+        &self.object
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for Transformed<H> {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        let local = self.transform.inverse().ray(ray);
+        let mut rec = self.object.hit(&local, rayt)?;
+
+        // The normal was oriented against the local ray and stays so in world space.
+        rec.p = self.transform.point(&rec.p);
+        rec.normal = self.transform.normal(&rec.normal);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+    use crate::objects::plane::Plane;
+    use crate::objects::quad::make_box;
+    use crate::objects::sphere::Sphere;
+
+    #[test]
+    fn test_instances() {
+        let unit: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(DefaultMaterial),
+        ));
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        // An ellipsoid stretched along x, then moved up.
+        let transform = Transform::scale(&Vector3::new(2.0, 1.0, 1.0))
+            .then(&Transform::translate(&Vector3::new(0.0, 3.0, 0.0)));
+        let ellipsoid = Transformed::new(unit.clone(), transform);
+        assert_eq!(ellipsoid.bounding_box().axis(0).max(), 2.0);
+        assert_eq!(ellipsoid.bounding_box().axis(1).min(), 2.0);
+
+        let ray = Ray::new(Point3::new(-5.0, 3.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let rec = ellipsoid.hit(&ray, &rayt).unwrap();
+        assert_eq!(rec.t, 3.0);
+        assert_eq!(rec.p, Point3::new(-2.0, 3.0, 0.0));
+        assert_eq!(rec.normal, Vector3::new(-1.0, 0.0, 0.0));
+
+        // The normal of the sheared surface is not the transformed one.
+        let ray = Ray::new(Point3::new(-5.0, 3.0, 0.9), Vector3::new(1.0, 0.0, 0.0));
+        let rec = ellipsoid.hit(&ray, &rayt).unwrap();
+        let tangent = Vector3::new(2.0 * 0.9, 0.0, f64::sqrt(1.0 - 0.81));
+        assert!(rec.normal.dot(&tangent).abs() < 1e-12);
+
+        // Many instances share the same sphere.
+        let copies: Vec<_> = (0..100)
+            .map(|i| {
+                let offset = Vector3::new(i as f64 * 3.0, 0.0, 0.0);
+                Transformed::new(unit.clone(), Transform::translate(&offset))
+            })
+            .collect();
+        assert_eq!(Arc::strong_count(&unit), 102);
+        assert_eq!(copies[99].bounding_box().axis(0).min(), 296.0);
+    }
+
+    #[test]
+    fn test_rotated_box_and_plane() {
+        let cube = make_box(
+            &Point3::new(0.0, 0.0, 0.0),
+            &Point3::new(1.0, 1.0, 1.0),
+            Arc::new(DefaultMaterial),
+        );
+        let rotated = Transformed::new(
+            Arc::new(cube),
+            Transform::rotate(&Vector3::new(0.0, 1.0, 0.0), 45.0),
+        );
+        let bbox = rotated.bounding_box();
+        assert!((bbox.axis(0).max() - f64::sqrt(2.0)).abs() < 1e-3);
+        assert!((bbox.axis(2).min() + f64::sqrt(2.0) / 2.0).abs() < 1e-3);
+
+        let plane = Transformed::new(
+            Arc::new(Plane::new(
+                Point3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+                Arc::new(DefaultMaterial),
+            )),
+            Transform::translate(&Vector3::new(0.0, -2.0, 0.0)),
+        );
+        assert_eq!(plane.bounding_box().axis(0).min(), f64::NEG_INFINITY);
+        assert!((plane.bounding_box().axis(1).max() + 2.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,313 @@
+use std::sync::Arc;
+
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::rng;
+
+use super::aabb::Aabb;
+
+#[derive(Clone)]
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vector3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    material: Arc<dyn Material>,
+
+    bbox: Aabb,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Point3; 3], material: Arc<dyn Material>) -> Triangle {
+        Triangle::with_attributes(vertices, None, None, material)
+    }
+
+    pub fn with_attributes(
+        vertices: [Point3; 3],
+        normals: Option<[Vector3; 3]>,
+        uvs: Option<[(f64, f64); 3]>,
+        material: Arc<dyn Material>,
+    ) -> Triangle {
+        let bbox = bounding_box([&vertices[0], &vertices[1], &vertices[2]]);
+
+        Triangle {
+            vertices,
+            normals,
+            uvs,
+            material,
+
+            bbox,
+        }
+    }
+
+    pub fn vertices(&self) -> &[Point3; 3] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> (Vector3, Vector3) {
+        let [p0, p1, p2] = &self.vertices;
+
+        (Vector3::from(p1 - p0), Vector3::from(p2 - p0))
+    }
+
+    fn random_point(&self) -> Point3 {
+        // Uniform barycentric coordinates, folding the far half of the parallelogram back.
+        let (e1, e2) = self.edges();
+        let (mut a, mut b) = (rng::random(), rng::random());
+        if a + b > 1.0 {
+            (a, b) = (1.0 - a, 1.0 - b);
+        }
+
+        &self.vertices[0] + (a * &e1) + (b * &e2)
+    }
+
+    pub fn area(&self) -> f64 {
+        let (e1, e2) = self.edges();
+
+        0.5 * e1.cross(&e2).norm()
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, rayt: &Interval) -> Option<HitRecord> {
+        let [p0, p1, p2] = &self.vertices;
+        let (t, b) = intersect(ray, rayt, [p0, p1, p2])?;
+
+        Some(record(
+            ray,
+            t,
+            b,
+            [p0, p1, p2],
+            self.normals.as_ref().map(|[n0, n1, n2]| [n0, n1, n2]),
+            self.uvs,
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        let (e1, e2) = self.edges();
+        let normal = e1.cross(&e2).normalise();
+
+        super::planar_pdf_value(self, origin, direction, self.area(), &normal)
+    }
+
+    fn random(&self, origin: &Point3) -> Vector3 {
+        Vector3::from(&self.random_point() - origin)
+    }
+
+    fn sample_surface(&self) -> Option<(HitRecord, f64)> {
+        let (e1, e2) = self.edges();
+        let normal = e1.cross(&e2).normalise();
+        let rec = super::surface_record(self, &self.random_point(), &normal)?;
+
+        Some((rec, 1.0 / self.area()))
+    }
+
+    fn surface_pdf(&self, ray: &Ray, t: f64) -> f64 {
+        super::surface_density(self, ray, t, self.area())
+    }
+}
+
+pub(super) fn bounding_box(p: [&Point3; 3]) -> Aabb {
+    let min = Point3::new(
+        p[0].x().min(p[1].x()).min(p[2].x()),
+        p[0].y().min(p[1].y()).min(p[2].y()),
+        p[0].z().min(p[1].z()).min(p[2].z()),
+    );
+    let max = Point3::new(
+        p[0].x().max(p[1].x()).max(p[2].x()),
+        p[0].y().max(p[1].y()).max(p[2].y()),
+        p[0].z().max(p[1].z()).max(p[2].z()),
+    );
+
+    Aabb::from(&min, &max)
+}
+
+fn point(p: &Point3) -> [f64; 3] {
+    [p.x(), p.y(), p.z()]
+}
+
+fn vector(v: &Vector3) -> [f64; 3] {
+    [v.x(), v.y(), v.z()]
+}
+
+// Watertight ray/triangle intersection (Woop, Benthin and Wald, 2013). The vertices are moved
+// into a space where the ray starts at the origin and runs along +z, so that edge tests on
+// shared edges are evaluated identically for both neighbouring triangles and rays cannot slip
+// between them. Returns the ray parameter and the barycentric coordinates of the hit point.
+pub(super) fn intersect(ray: &Ray, rayt: &Interval, p: [&Point3; 3]) -> Option<(f64, [f64; 3])> {
+    let o = point(ray.origin());
+    let d = vector(ray.direction());
+
+    // Permute the axes so that z is the dominant direction of the ray.
+    let kz = (0..3)
+        .max_by(|&a, &b| d[a].abs().total_cmp(&d[b].abs()))
+        .unwrap_or(2);
+    let kx = (kz + 1) % 3;
+    let ky = (kx + 1) % 3;
+    if d[kz] == 0.0 {
+        return None;
+    }
+
+    let sx = -d[kx] / d[kz];
+    let sy = -d[ky] / d[kz];
+    let sz = 1.0 / d[kz];
+
+    let mut v = [[0.0; 3]; 3];
+    for (i, vertex) in p.iter().enumerate() {
+        let q = point(vertex);
+        let (x, y, z) = (q[kx] - o[kx], q[ky] - o[ky], q[kz] - o[kz]);
+        v[i] = [x + sx * z, y + sy * z, z * sz];
+    }
+
+    // Twice the signed areas of the sub-triangles opposite to each vertex.
+    let e = [
+        v[1][0] * v[2][1] - v[1][1] * v[2][0],
+        v[2][0] * v[0][1] - v[2][1] * v[0][0],
+        v[0][0] * v[1][1] - v[0][1] * v[1][0],
+    ];
+
+    if (e[0] < 0.0 || e[1] < 0.0 || e[2] < 0.0) && (e[0] > 0.0 || e[1] > 0.0 || e[2] > 0.0) {
+        return None;
+    }
+
+    let det = e[0] + e[1] + e[2];
+    if det == 0.0 {
+        return None;
+    }
+
+    let t = (e[0] * v[0][2] + e[1] * v[1][2] + e[2] * v[2][2]) / det;
+    if !rayt.surrounds(t) {
+        return None;
+    }
+
+    Some((t, [e[0] / det, e[1] / det, e[2] / det]))
+}
+
+// Builds the hit record from the barycentric coordinates of the hit point, interpolating the
+// shading normal and texture coordinates when the vertices carry them.
+pub(super) fn record(
+    ray: &Ray,
+    t: f64,
+    b: [f64; 3],
+    p: [&Point3; 3],
+    normals: Option<[&Vector3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    material: &Arc<dyn Material>,
+) -> HitRecord {
+    let mut rec = HitRecord::new();
+    rec.t = t;
+    rec.p = ray.at(t);
+    rec.mat = material.clone();
+
+    let e1 = Vector3::from(p[1] - p[0]);
+    let e2 = Vector3::from(p[2] - p[0]);
+    let outward_normal = e1.cross(&e2).normalise();
+    rec.set_face_normal(ray, &outward_normal);
+
+    if let Some([n0, n1, n2]) = normals {
+        let shading = (b[0] * n0 + b[1] * n1 + b[2] * n2).normalise();
+        if shading.x().is_finite() {
+            // Keep the shading normal on the side of the surface the ray arrived from.
+            rec.normal = if shading.dot(&rec.normal) < 0.0 {
+                -shading
+            } else {
+                shading
+            };
+        }
+    }
+
+    (rec.u, rec.v) = match uvs {
+        Some(uv) => (
+            b[0] * uv[0].0 + b[1] * uv[1].0 + b[2] * uv[2].0,
+            b[0] * uv[0].1 + b[1] * uv[1].1 + b[2] * uv[2].1,
+        ),
+        None => (b[1], b[2]),
+    };
+
+    rec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DefaultMaterial;
+
+    fn triangle() -> Triangle {
+        Triangle::with_attributes(
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            Some([
+                Vector3::new(0.0, 0.0, 1.0),
+                Vector3::new(1.0, 0.0, 1.0).normalise(),
+                Vector3::new(0.0, 1.0, 1.0).normalise(),
+            ]),
+            Some([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]),
+            Arc::new(DefaultMaterial),
+        )
+    }
+
+    #[test]
+    fn test_hit() {
+        let rayt = Interval::new(0.001, f64::INFINITY);
+        let ray = Ray::new(Point3::new(0.25, 0.5, 2.0), Vector3::new(0.0, 0.0, -1.0));
+        let rec = triangle().hit(&ray, &rayt).unwrap();
+
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.p, Point3::new(0.25, 0.5, 0.0));
+        assert!(rec.front_face);
+        assert!((rec.u - 0.25).abs() < 1e-12);
+        assert!((rec.v - 0.5).abs() < 1e-12);
+        assert!(rec.normal.x() > 0.0 && rec.normal.y() > 0.0 && rec.normal.z() > 0.0);
+        assert!((rec.normal.norm() - 1.0).abs() < 1e-12);
+
+        // Seen from behind, the normals are flipped towards the ray.
+        let ray = Ray::new(Point3::new(0.25, 0.5, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        let rec = triangle().hit(&ray, &rayt).unwrap();
+        assert!(!rec.front_face);
+        assert!(rec.normal.z() < 0.0);
+
+        let ray = Ray::new(Point3::new(0.75, 0.5, 2.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(triangle().hit(&ray, &rayt).is_none());
+
+        let ray = Ray::new(Point3::new(0.25, 0.5, 2.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(triangle().hit(&ray, &rayt).is_none());
+    }
+
+    #[test]
+    fn test_shared_edge_is_watertight() {
+        // Two triangles sharing the diagonal of the unit square: rays aimed exactly at the
+        // diagonal must hit at least one of them.
+        let material: Arc<dyn Material> = Arc::new(DefaultMaterial);
+        let a = Point3::new(0.0, 0.0, 0.0);
+        let b = Point3::new(1.0, 0.0, 0.3);
+        let c = Point3::new(1.0, 1.0, 0.7);
+        let d = Point3::new(0.0, 1.0, 0.1);
+        let t1 = Triangle::new([a.clone(), b, c.clone()], material.clone());
+        let t2 = Triangle::new([a.clone(), c.clone(), d], material);
+        let rayt = Interval::new(0.001, f64::INFINITY);
+
+        let origin = Point3::new(0.31, 0.17, 5.0);
+        for i in 1..1000 {
+            let s = i as f64 / 1000.0;
+            let target = &a + s * Vector3::from(&c - &a);
+            let ray = Ray::new(origin.clone(), Vector3::from(&target - &origin));
+
+            assert!(
+                t1.hit(&ray, &rayt).is_some() || t2.hit(&ray, &rayt).is_some(),
+                "ray through the shared edge at {} missed",
+                s
+            );
+        }
+    }
+}
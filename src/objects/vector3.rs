@@ -42,6 +42,18 @@ impl Vector3 {
         }
     }
 
+    pub fn new_random_cosine() -> Vector3 {
+        // Direction in the hemisphere around +z, with a density proportional to cos(theta).
+        let (r1, r2) = (rng::random(), rng::random());
+        let phi = 2.0 * std::f64::consts::PI * r1;
+
+        Vector3::new(
+            f64::cos(phi) * f64::sqrt(r2),
+            f64::sin(phi) * f64::sqrt(r2),
+            f64::sqrt(1.0 - r2),
+        )
+    }
+
     pub fn near_zero(&self) -> bool {
         let s = 1e-8;
         f64::abs(self.x()) < s && f64::abs(self.y()) < s && f64::abs(self.z()) < s
@@ -0,0 +1,131 @@
+use std::f64::consts::PI;
+
+use crate::objects::onb::Onb;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::Hittable;
+use crate::rng;
+
+// Probability density over directions, by solid angle, along with a way to sample it.
+pub trait Pdf {
+    fn value(&self, direction: &Vector3) -> f64;
+
+    fn generate(&self) -> Vector3;
+}
+
+// Uniform over all directions.
+#[derive(Debug, Clone, Default)]
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _direction: &Vector3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self) -> Vector3 {
+        Vector3::new_random_unit()
+    }
+}
+
+// Proportional to the cosine with the normal, over the hemisphere it points to.
+#[derive(Debug, Clone)]
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(normal: &Vector3) -> CosinePdf {
+        CosinePdf {
+            uvw: Onb::new(normal),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vector3) -> f64 {
+        let cosine = direction.normalise().dot(self.uvw.w());
+
+        f64::max(cosine / PI, 0.0)
+    }
+
+    fn generate(&self) -> Vector3 {
+        self.uvw.transform(&Vector3::new_random_cosine())
+    }
+}
+
+// Directions from a point towards an object, as sampled by the object itself.
+pub struct HittablePdf<'a> {
+    objects: &'a dyn Hittable,
+    origin: Point3,
+}
+
+impl<'a> HittablePdf<'a> {
+    pub fn new(objects: &'a dyn Hittable, origin: Point3) -> HittablePdf<'a> {
+        HittablePdf { objects, origin }
+    }
+}
+
+impl Pdf for HittablePdf<'_> {
+    fn value(&self, direction: &Vector3) -> f64 {
+        self.objects.pdf_value(&self.origin, direction)
+    }
+
+    fn generate(&self) -> Vector3 {
+        self.objects.random(&self.origin)
+    }
+}
+
+// Even mixture of two densities. Sampling it and weighting by the mixture density amounts to
+// multiple importance sampling of both with the balance heuristic.
+pub struct MixturePdf<'a> {
+    p: [&'a dyn Pdf; 2],
+}
+
+impl<'a> MixturePdf<'a> {
+    pub fn new(p0: &'a dyn Pdf, p1: &'a dyn Pdf) -> MixturePdf<'a> {
+        MixturePdf { p: [p0, p1] }
+    }
+}
+
+impl Pdf for MixturePdf<'_> {
+    fn value(&self, direction: &Vector3) -> f64 {
+        0.5 * self.p[0].value(direction) + 0.5 * self.p[1].value(direction)
+    }
+
+    fn generate(&self) -> Vector3 {
+        if rng::random() < 0.5 {
+            self.p[0].generate()
+        } else {
+            self.p[1].generate()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monte Carlo estimate of the integral of the density over the sphere, which must be 1.
+    fn total(pdf: &dyn Pdf) -> f64 {
+        let n = 100_000;
+        let sum: f64 = (0..n)
+            .map(|_| pdf.value(&Vector3::new_random_unit()) / SpherePdf.value(&Vector3::default()))
+            .sum();
+
+        sum / n as f64
+    }
+
+    #[test]
+    fn test_densities_integrate_to_one() {
+        rng::seed(17);
+        let cosine = CosinePdf::new(&Vector3::new(1.0, 2.0, -0.5));
+
+        assert!((total(&SpherePdf) - 1.0).abs() < 1e-12);
+        assert!((total(&cosine) - 1.0).abs() < 0.02);
+        assert!((total(&MixturePdf::new(&cosine, &SpherePdf)) - 1.0).abs() < 0.02);
+
+        for _ in 0..1000 {
+            assert!(cosine.value(&cosine.generate()) > 0.0);
+        }
+    }
+}
@@ -14,6 +14,18 @@ impl PpmColor {
         PpmColor { r, g, b }
     }
 
+    pub fn r(&self) -> u8 {
+        self.r
+    }
+
+    pub fn g(&self) -> u8 {
+        self.g
+    }
+
+    pub fn b(&self) -> u8 {
+        self.b
+    }
+
     pub fn set(&mut self, color: &PpmColor) {
         self.r = color.r;
         self.g = color.g;
@@ -29,20 +41,30 @@ fn linear_to_gamma(linear_component: f64) -> f64 {
     0.0
 }
 
+fn gamma_to_linear(gamma_component: f64) -> f64 {
+    gamma_component * gamma_component
+}
+
+impl From<&PpmColor> for Color {
+    fn from(c: &PpmColor) -> Color {
+        Color::new(
+            gamma_to_linear(f64::from(c.r) / 255.0),
+            gamma_to_linear(f64::from(c.g) / 255.0),
+            gamma_to_linear(f64::from(c.b) / 255.0),
+        )
+    }
+}
+
 impl From<Color> for PpmColor {
     fn from(c: Color) -> PpmColor {
-        let intensity = Interval::new(0.000, 255.0);
-
-        PpmColor {
-            r: intensity.clamp(linear_to_gamma(c.r()) * 255_f64) as u32 as u8,
-            g: intensity.clamp(linear_to_gamma(c.g()) * 255_f64) as u32 as u8,
-            b: intensity.clamp(linear_to_gamma(c.b()) * 255_f64) as u32 as u8,
-        }
+        PpmColor::from(&c)
     }
 }
 
 impl From<&Color> for PpmColor {
     fn from(c: &Color) -> PpmColor {
+        // Radiance is unbounded, only the quantised output is clamped.
+        let c = c.saturate();
         let intensity = Interval::new(0.000, 255.0);
 
         PpmColor {
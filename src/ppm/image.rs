@@ -1,6 +1,7 @@
 use std::fmt;
+use std::io::{self, Read, Write};
 
-use crate::{material::color::Color, ppm::color::PpmColor};
+use crate::{film::Film, material::color::Color, ppm::color::PpmColor};
 
 #[derive(Debug)]
 pub struct Ppm {
@@ -22,9 +23,144 @@ impl Ppm {
         }
     }
 
+    pub fn new_binary(w: u32, h: u32) -> Ppm {
+        Ppm {
+            magic: 6,
+            ..Ppm::new(w, h, 256)
+        }
+    }
+
     pub fn set(&mut self, x: u32, y: u32, color: &Color) {
         self.body[(y * self.w + x) as usize].set(&PpmColor::from(color))
     }
+
+    pub fn set_film(&mut self, film: &Film) {
+        for y in 0..u32::min(self.h, film.height()) {
+            for x in 0..u32::min(self.w, film.width()) {
+                self.set(x, y, film.get(x, y));
+            }
+        }
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if self.magic != 6 {
+            return write!(out, "{}", self);
+        }
+
+        write!(
+            out,
+            "P{}\n{} {}\n{}\n",
+            self.magic, self.w, self.h, self.depth
+        )?;
+
+        let body: Vec<u8> = self
+            .body
+            .iter()
+            .flat_map(|color| [color.r(), color.g(), color.b()])
+            .collect();
+        out.write_all(&body)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// Reads the next header field, skipping whitespace and comments.
+fn field(data: &[u8], position: &mut usize) -> io::Result<u32> {
+    loop {
+        match data.get(*position) {
+            Some(b'#') => {
+                while data.get(*position).is_some_and(|&c| c != b'\n') {
+                    *position += 1;
+                }
+            }
+            Some(c) if c.is_ascii_whitespace() => *position += 1,
+            _ => break,
+        }
+    }
+
+    let start = *position;
+    while data.get(*position).is_some_and(|c| c.is_ascii_digit()) {
+        *position += 1;
+    }
+
+    std::str::from_utf8(&data[start..*position])
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid("invalid PPM header"))
+}
+
+impl Ppm {
+    // Reads a plain (P3) or binary (P6) image. Samples of images with a maximum value other
+    // than 255 are rescaled to 8 bits.
+    pub fn read<R: Read>(input: &mut R) -> io::Result<Ppm> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+
+        let magic = match data.get(..2) {
+            Some(b"P3") => 3,
+            Some(b"P6") => 6,
+            _ => return Err(invalid("not a P3 or P6 PPM file")),
+        };
+        let mut position = 2;
+        let w = field(&data, &mut position)?;
+        let h = field(&data, &mut position)?;
+        let max = field(&data, &mut position)?;
+        if max == 0 || max > 65535 {
+            return Err(invalid("invalid PPM maximum value"));
+        }
+
+        let count = w as usize * h as usize * 3;
+        let samples: Vec<u32> = if magic == 3 {
+            (0..count)
+                .map(|_| field(&data, &mut position))
+                .collect::<io::Result<_>>()?
+        } else {
+            // A single whitespace character separates the header from the samples.
+            let body = &data[usize::min(position + 1, data.len())..];
+            let size = if max < 256 { 1 } else { 2 };
+            if body.len() < count * size {
+                return Err(invalid("truncated PPM image"));
+            }
+
+            body.chunks_exact(size)
+                .take(count)
+                .map(|c| c.iter().fold(0, |v, &b| v << 8 | u32::from(b)))
+                .collect()
+        };
+
+        let mut img = Ppm {
+            magic,
+            ..Ppm::new(w, h, 256)
+        };
+        for (n, rgb) in samples.chunks_exact(3).enumerate() {
+            let [r, g, b] = [rgb[0], rgb[1], rgb[2]].map(|v| (u32::min(v, max) * 255 / max) as u8);
+            img.body[n].set(&PpmColor::new(r, g, b));
+        }
+
+        Ok(img)
+    }
+}
+
+impl From<&Ppm> for Film {
+    fn from(img: &Ppm) -> Film {
+        let mut film = Film::new(img.w, img.h);
+        for (n, color) in img.body.iter().enumerate() {
+            film.set(n as u32 % img.w, n as u32 / img.w, Color::from(color));
+        }
+
+        film
+    }
+}
+
+impl From<&Film> for Ppm {
+    fn from(film: &Film) -> Ppm {
+        let mut img = Ppm::new(film.width(), film.height(), 256);
+        img.set_film(film);
+
+        img
+    }
 }
 
 impl fmt::Display for Ppm {
@@ -1,2 +1,2 @@
-mod color;
+pub mod color;
 pub mod image;
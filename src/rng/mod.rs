@@ -1,5 +1,13 @@
 extern crate fastrand;
 
+pub fn seed(seed: u64) {
+    fastrand::seed(seed)
+}
+
+pub fn random_u64() -> u64 {
+    fastrand::u64(..)
+}
+
 pub fn random() -> f64 {
     fastrand::f64()
 }
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use crate::background::Background;
+use crate::camera::Camera;
+use crate::fog::Fog;
+use crate::material::{
+    color::Color, dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+    metal::Metal, DefaultMaterial, Material,
+};
+use crate::objects::constant_medium::ConstantMedium;
+use crate::objects::point3::Point3;
+use crate::objects::quad::{make_box, Quad};
+use crate::objects::transform::{Transform, Transformed};
+use crate::objects::vector3::Vector3;
+use crate::objects::{sphere, Hittable, HittableList};
+use crate::rng;
+
+use super::Scene;
+
+pub const NAMES: [&str; 3] = ["cover", "cornell", "cornell_smoke"];
+
+pub fn by_name(name: &str) -> Option<Scene> {
+    match name {
+        "cover" => Some(cover()),
+        "cornell" => Some(cornell()),
+        "cornell_smoke" => Some(cornell_smoke()),
+        _ => None,
+    }
+}
+
+// Random spheres scene from the cover of "Ray Tracing in One Weekend".
+pub fn cover() -> Scene {
+    let mut camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::default(),
+        Vector3::new(0.0, 1.0, 0.0),
+        16.0 / 9.0,
+        1200,
+        20.0,
+        10.0,
+        0.6,
+    );
+    // Thin ground fog, fading the spheres in the distance.
+    let mut fog = Fog::new(0.02, Color::new(1.0, 1.0, 1.0));
+    fog.set_falloff(0.0, 1.0);
+    camera
+        .set_antialiasing(500)
+        .set_maximum_depth(50)
+        .set_fog(Some(fog));
+
+    let mut world = HittableList::new();
+
+    let ground_material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(Arc::new(sphere::Sphere::new(
+        Point3::new(0.0, -1000.0, -1.0),
+        1000.0,
+        ground_material,
+    )));
+
+    let p = Point3::new(4.0, 0.2, 0.0);
+    for a in 0..22_usize {
+        for b in 0..22_usize {
+            let choose_mat = rng::random();
+            let center = Point3::new(
+                f64::from(a as i32 - 11) + 0.9 * rng::random(),
+                0.2,
+                f64::from(b as i32 - 11) + 0.9 * rng::random(),
+            );
+            let motion: Option<Point3>;
+
+            if Vector3::from(&center - &p).norm() > 0.9 {
+                let sphere_material: Arc<dyn Material> = match choose_mat {
+                    0.0..0.8 => {
+                        // diffuse
+                        let albedo = Color::new(
+                            rng::random() * rng::random(),
+                            rng::random() * rng::random(),
+                            rng::random() * rng::random(),
+                        );
+                        motion =
+                            Some(&center + Vector3::new(0.0, rng::random_range(0.0, 0.5), 0.0));
+                        Arc::new(Lambertian::new(albedo))
+                    }
+                    0.8..0.95 => {
+                        // metal
+                        let albedo = Color::new(
+                            rng::random_range(0.5, 1.0),
+                            rng::random_range(0.5, 1.0),
+                            rng::random_range(0.5, 1.0),
+                        );
+                        let fuzz = rng::random_range(0.0, 0.5);
+                        motion = None;
+                        Arc::new(Metal::new(albedo, fuzz))
+                    }
+                    _ => {
+                        // glass
+                        motion = None;
+                        Arc::new(Dielectric::new(1.50))
+                    }
+                };
+
+                let binding = match motion {
+                    Some(c2) => sphere::Sphere::with_motion(center, c2, 0.2, sphere_material),
+                    None => sphere::Sphere::new(center, 0.2, sphere_material),
+                };
+                world.add(Arc::new(binding));
+            }
+        }
+    }
+
+    let material1 = Arc::new(Dielectric::new(1.5));
+    world.add(Arc::new(sphere::Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        material1,
+    )));
+
+    let material2 = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    world.add(Arc::new(sphere::Sphere::new(
+        Point3::new(-4.0, 1.0, 0.0),
+        1.0,
+        material2,
+    )));
+
+    let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+    world.add(Arc::new(sphere::Sphere::new(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        material3,
+    )));
+
+    Scene {
+        camera,
+        world,
+        lights: HittableList::new(),
+    }
+}
+
+// Empty Cornell box, lit by the given ceiling light.
+fn cornell_room(light: Quad) -> Scene {
+    let mut camera = Camera::new(
+        Point3::new(278.0, 278.0, -800.0),
+        Point3::new(278.0, 278.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        1.0,
+        600,
+        40.0,
+        10.0,
+        0.0,
+    );
+    camera
+        .set_antialiasing(200)
+        .set_maximum_depth(50)
+        .set_background(Background::black());
+
+    let red: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+
+    let mut world = HittableList::new();
+    let walls = [
+        (
+            Point3::new(555.0, 0.0, 0.0),
+            Vector3::new(0.0, 555.0, 0.0),
+            Vector3::new(0.0, 0.0, 555.0),
+            &green,
+        ),
+        (
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 555.0, 0.0),
+            Vector3::new(0.0, 0.0, 555.0),
+            &red,
+        ),
+        (
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(555.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 555.0),
+            &white,
+        ),
+        (
+            Point3::new(555.0, 555.0, 555.0),
+            Vector3::new(-555.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, -555.0),
+            &white,
+        ),
+        (
+            Point3::new(0.0, 0.0, 555.0),
+            Vector3::new(555.0, 0.0, 0.0),
+            Vector3::new(0.0, 555.0, 0.0),
+            &white,
+        ),
+    ];
+    for (q, u, v, material) in walls {
+        world.add(Arc::new(Quad::new(q, u, v, material.clone())));
+    }
+
+    let light: Arc<dyn Hittable> = Arc::new(light);
+    let mut lights = HittableList::new();
+    lights.add(light.clone());
+    world.add(light);
+
+    Scene {
+        camera,
+        world,
+        lights,
+    }
+}
+
+// Box of the given size with a corner at the origin, turned around its vertical axis and moved.
+fn turned_box(
+    size: Point3,
+    angle: f64,
+    offset: Vector3,
+    material: Arc<dyn Material>,
+) -> Arc<dyn Hittable> {
+    let transform =
+        Transform::rotate(&Vector3::new(0.0, 1.0, 0.0), angle).then(&Transform::translate(&offset));
+
+    Arc::new(Transformed::new(
+        Arc::new(make_box(&Point3::new(0.0, 0.0, 0.0), &size, material)),
+        transform,
+    ))
+}
+
+// Cornell box lit by a single area light in the ceiling.
+pub fn cornell() -> Scene {
+    let mut scene = cornell_room(Quad::new(
+        Point3::new(343.0, 554.0, 332.0),
+        Vector3::new(-130.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -105.0),
+        Arc::new(DiffuseLight::new(Color::new(15.0, 15.0, 15.0))),
+    ));
+
+    scene.world.add(turned_box(
+        Point3::new(165.0, 330.0, 165.0),
+        15.0,
+        Vector3::new(265.0, 0.0, 295.0),
+        Arc::new(Metal::new(Color::new(0.8, 0.85, 0.88), 0.05)),
+    ));
+    scene.world.add(turned_box(
+        Point3::new(165.0, 165.0, 165.0),
+        -18.0,
+        Vector3::new(130.0, 0.0, 65.0),
+        Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73))),
+    ));
+    scene.world.add(Arc::new(sphere::Sphere::new(
+        Point3::new(183.0, 225.0, 169.0),
+        60.0,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+
+    scene
+}
+
+// Cornell box holding two blocks of smoke, one dark and one light, under a larger dim light.
+pub fn cornell_smoke() -> Scene {
+    let mut scene = cornell_room(Quad::new(
+        Point3::new(113.0, 554.0, 127.0),
+        Vector3::new(330.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 305.0),
+        Arc::new(DiffuseLight::new(Color::new(7.0, 7.0, 7.0))),
+    ));
+
+    let boundary: Arc<dyn Material> = Arc::new(DefaultMaterial);
+    let smoke = [
+        (
+            Point3::new(165.0, 330.0, 165.0),
+            15.0,
+            Vector3::new(265.0, 0.0, 295.0),
+            Color::new(0.0, 0.0, 0.0),
+        ),
+        (
+            Point3::new(165.0, 165.0, 165.0),
+            -18.0,
+            Vector3::new(130.0, 0.0, 65.0),
+            Color::new(1.0, 1.0, 1.0),
+        ),
+    ];
+    for (size, angle, offset, albedo) in smoke {
+        scene.world.add(Arc::new(ConstantMedium::new(
+            turned_box(size, angle, offset, boundary.clone()),
+            0.01,
+            albedo,
+        )));
+    }
+
+    scene
+}
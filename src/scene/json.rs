@@ -0,0 +1,354 @@
+// Small JSON (RFC 8259) reader keeping track of where every value starts, so that the scene
+// loader can point at the offending line and column.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value {
+    pub kind: Kind,
+    pub position: Position,
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self.kind {
+            Kind::Null => "null",
+            Kind::Bool(_) => "a boolean",
+            Kind::Number(_) => "a number",
+            Kind::String(_) => "a string",
+            Kind::Array(_) => "an array",
+            Kind::Object(_) => "an object",
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match &self.kind {
+            Kind::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.kind {
+            Kind::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub position: Position,
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    position: Position,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Parser<'a> {
+        Parser {
+            chars: text.chars().peekable(),
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, ParseError> {
+        Err(ParseError {
+            message,
+            position: self.position,
+        })
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\n' | '\r' => {
+                    self.next();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => self.error(format!("expected '{}', found '{}'", expected, c)),
+            None => self.error(format!("expected '{}', found end of file", expected)),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, kind: Kind) -> Result<Kind, ParseError> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return self.error(format!("invalid literal, expected '{}'", word));
+            }
+            self.next();
+        }
+
+        Ok(kind)
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+        let position = self.position;
+
+        let kind = match self.peek() {
+            Some('{') => self.object()?,
+            Some('[') => self.array()?,
+            Some('"') => Kind::String(self.string()?),
+            Some('t') => self.keyword("true", Kind::Bool(true))?,
+            Some('f') => self.keyword("false", Kind::Bool(false))?,
+            Some('n') => self.keyword("null", Kind::Null)?,
+            Some(c) if c == '-' || c.is_ascii_digit() => Kind::Number(self.number()?),
+            Some(c) => return self.error(format!("unexpected character '{}'", c)),
+            None => return self.error("unexpected end of file".to_string()),
+        };
+
+        Ok(Value { kind, position })
+    }
+
+    fn object(&mut self) -> Result<Kind, ParseError> {
+        let mut members: Vec<(String, Value)> = Vec::new();
+        self.expect('{')?;
+        self.skip_whitespace();
+
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(Kind::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return self.error("expected a member name".to_string());
+            }
+
+            let key_position = self.position;
+            let key = self.string()?;
+            if members.iter().any(|(k, _)| *k == key) {
+                return Err(ParseError {
+                    message: format!("duplicate member \"{}\"", key),
+                    position: key_position,
+                });
+            }
+
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            members.push((key, value));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Kind::Object(members)),
+                _ => return self.error("expected ',' or '}' after an object member".to_string()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Kind, ParseError> {
+        let mut elements = Vec::new();
+        self.expect('[')?;
+        self.skip_whitespace();
+
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(Kind::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Kind::Array(elements)),
+                _ => return self.error("expected ',' or ']' after an array element".to_string()),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParseError> {
+        let mut code = 0;
+
+        for _ in 0..4 {
+            match self.next().and_then(|c| c.to_digit(16)) {
+                Some(d) => code = code * 16 + d,
+                None => return self.error("invalid unicode escape".to_string()),
+            }
+        }
+
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let mut s = String::new();
+        self.expect('"')?;
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = self.hex4()?;
+                            if (0xd800..0xdc00).contains(&code) {
+                                // Surrogate pair.
+                                self.expect('\\')?;
+                                self.expect('u')?;
+                                let low = self.hex4()?;
+                                code = 0x10000
+                                    + ((code - 0xd800) << 10)
+                                    + (low.wrapping_sub(0xdc00) & 0x3ff);
+                            }
+                            match char::from_u32(code) {
+                                Some(c) => c,
+                                None => return self.error("invalid unicode escape".to_string()),
+                            }
+                        }
+                        _ => return self.error("invalid escape sequence".to_string()),
+                    };
+                    s.push(c);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return self.error("control character in string".to_string())
+                }
+                Some(c) => s.push(c),
+                None => return self.error("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        let start = self.position;
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                text.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        match text.parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(n),
+            _ => Err(ParseError {
+                message: format!("invalid number '{}'", text),
+                position: start,
+            }),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser::new(text);
+    let value = parser.value()?;
+
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return parser.error("trailing characters after the document".to_string());
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let v =
+            parse("{\"a\": [1, -2.5e1, true, null],\n \"b\": {\"c\": \"x\\u00e9\\n\"}}").unwrap();
+
+        let a = v.get("a").unwrap();
+        assert_eq!(a.position, Position { line: 1, column: 7 });
+        match &a.kind {
+            Kind::Array(elements) => {
+                assert_eq!(elements[0].kind, Kind::Number(1.0));
+                assert_eq!(elements[1].kind, Kind::Number(-25.0));
+                assert_eq!(elements[2].kind, Kind::Bool(true));
+                assert_eq!(elements[3].kind, Kind::Null);
+            }
+            _ => panic!("expected an array"),
+        }
+
+        let c = v.get("b").unwrap().get("c").unwrap();
+        assert_eq!(c.kind, Kind::String("xé\n".to_string()));
+        assert_eq!(
+            c.position,
+            Position {
+                line: 2,
+                column: 13
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let e = parse("{\n  \"a\": 1,\n  \"b\" 2\n}").unwrap_err();
+        assert_eq!(e.position, Position { line: 3, column: 7 });
+
+        let e = parse("[1, 2").unwrap_err();
+        assert_eq!(e.position.line, 1);
+
+        assert!(parse("{\"a\": 1, \"a\": 2}").is_err());
+        assert!(parse("[1] x").is_err());
+        assert!(parse("01.2.3").is_err());
+    }
+}
@@ -0,0 +1,1280 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::background::Background;
+use crate::camera::{Camera, Fisheye, Layout, Projection, Stereo};
+use crate::environment::EnvironmentLight;
+use crate::fog::Fog;
+use crate::integrator::ambient_occlusion::AmbientOcclusion;
+use crate::integrator::photon_mapping::PhotonMapping;
+use crate::integrator::{self, Integrator};
+use crate::light::{DirectionalLight, Light, PointLight, SpotLight};
+use crate::material::{
+    color::Color, dielectric::Dielectric, diffuse_light::DiffuseLight, isotropic::Isotropic,
+    lambertian::Lambertian, metal::Metal, Material,
+};
+use crate::objects::bvh::BvhNode;
+use crate::objects::constant_medium::ConstantMedium;
+use crate::objects::disk::Disk;
+use crate::objects::matrix4::Matrix4;
+use crate::objects::mesh::TriangleMesh;
+use crate::objects::plane::Plane;
+use crate::objects::point3::Point3;
+use crate::objects::quad::{make_box, Quad};
+use crate::objects::sphere::Sphere;
+use crate::objects::transform::{Transform, Transformed};
+use crate::objects::triangle::Triangle;
+use crate::objects::vector3::Vector3;
+use crate::objects::{Hittable, HittableList};
+use crate::sky::Sky;
+use crate::texture::checker::Checker;
+use crate::texture::image::ImageTexture;
+use crate::texture::noise::{NoiseKind, NoiseTexture};
+use crate::texture::{SolidColor, Texture};
+
+use super::json::{Kind, Value};
+use super::obj;
+use super::{Scene, SceneError};
+
+fn error<T>(value: &Value, field: &str, message: String) -> Result<T, SceneError> {
+    Err(SceneError::new(
+        Some(value.position),
+        format!("{}: {}", field, message),
+    ))
+}
+
+fn mismatch<T>(value: &Value, field: &str, expected: &str) -> Result<T, SceneError> {
+    error(
+        value,
+        field,
+        format!("expected {}, found {}", expected, value.type_name()),
+    )
+}
+
+// Checks that the value is an object only made of known members.
+fn object<'v>(
+    value: &'v Value,
+    field: &str,
+    allowed: &[&str],
+) -> Result<&'v [(String, Value)], SceneError> {
+    match &value.kind {
+        Kind::Object(members) => {
+            for (key, member) in members {
+                if !allowed.contains(&key.as_str()) {
+                    return error(member, field, format!("unknown field \"{}\"", key));
+                }
+            }
+
+            Ok(members)
+        }
+        _ => mismatch(value, field, "an object"),
+    }
+}
+
+fn required<'v>(value: &'v Value, key: &str, field: &str) -> Result<&'v Value, SceneError> {
+    match value.get(key) {
+        Some(v) => Ok(v),
+        None => error(value, field, format!("missing field \"{}\"", key)),
+    }
+}
+
+fn number(value: &Value, field: &str) -> Result<f64, SceneError> {
+    match value.kind {
+        Kind::Number(n) => Ok(n),
+        _ => mismatch(value, field, "a number"),
+    }
+}
+
+fn integer(value: &Value, field: &str) -> Result<u64, SceneError> {
+    let n = number(value, field)?;
+    if n < 0.0 || n.fract() != 0.0 || n > u32::MAX as f64 {
+        return error(
+            value,
+            field,
+            format!("expected a non-negative integer, found {}", n),
+        );
+    }
+
+    Ok(n as u64)
+}
+
+// Integer counting things which cannot be done zero times, such as samples.
+fn count(value: &Value, field: &str) -> Result<u64, SceneError> {
+    match integer(value, field)? {
+        0 => error(
+            value,
+            field,
+            "expected a positive integer, found 0".to_string(),
+        ),
+        n => Ok(n),
+    }
+}
+
+fn string<'v>(value: &'v Value, field: &str) -> Result<&'v str, SceneError> {
+    match &value.kind {
+        Kind::String(s) => Ok(s),
+        _ => mismatch(value, field, "a string"),
+    }
+}
+
+fn array<'v>(value: &'v Value, field: &str) -> Result<&'v [Value], SceneError> {
+    match &value.kind {
+        Kind::Array(elements) => Ok(elements),
+        _ => mismatch(value, field, "an array"),
+    }
+}
+
+fn numbers<const N: usize>(value: &Value, field: &str) -> Result<[f64; N], SceneError> {
+    let elements = array(value, field)?;
+    if elements.len() != N {
+        return error(
+            value,
+            field,
+            format!("expected {} numbers, found {}", N, elements.len()),
+        );
+    }
+
+    let mut t = [0.0; N];
+    for (n, element) in elements.iter().enumerate() {
+        t[n] = number(element, &format!("{}[{}]", field, n))?;
+    }
+
+    Ok(t)
+}
+
+fn triple(value: &Value, field: &str) -> Result<[f64; 3], SceneError> {
+    numbers(value, field)
+}
+
+fn uv(value: &Value, field: &str) -> Result<(f64, f64), SceneError> {
+    let [u, v] = numbers(value, field)?;
+    Ok((u, v))
+}
+
+fn list<T, F>(value: &Value, field: &str, f: F) -> Result<Vec<T>, SceneError>
+where
+    F: Fn(&Value, &str) -> Result<T, SceneError>,
+{
+    array(value, field)?
+        .iter()
+        .enumerate()
+        .map(|(n, element)| f(element, &format!("{}[{}]", field, n)))
+        .collect()
+}
+
+fn vertices<T, F>(value: &Value, field: &str, f: F) -> Result<[T; 3], SceneError>
+where
+    F: Fn(&Value, &str) -> Result<T, SceneError>,
+{
+    let elements = list(value, field, f)?;
+    let count = elements.len();
+
+    elements.try_into().or_else(|_| {
+        error(
+            value,
+            field,
+            format!("expected 3 vertices, found {}", count),
+        )
+    })
+}
+
+fn point(value: &Value, field: &str) -> Result<Point3, SceneError> {
+    let [x, y, z] = triple(value, field)?;
+    Ok(Point3::new(x, y, z))
+}
+
+fn vector(value: &Value, field: &str) -> Result<Vector3, SceneError> {
+    let [x, y, z] = triple(value, field)?;
+    Ok(Vector3::new(x, y, z))
+}
+
+fn normal(value: &Value, field: &str) -> Result<Vector3, SceneError> {
+    let v = vector(value, field)?;
+    if v.near_zero() {
+        return error(value, field, "must not be a zero vector".to_string());
+    }
+
+    Ok(v)
+}
+
+// Sequence of transforms, applied in order.
+fn transform(value: &Value, name: &str) -> Result<Transform, SceneError> {
+    let steps = list(value, name, |step, name| {
+        let kind = kind(step, name)?;
+
+        match kind {
+            "translate" => {
+                object(step, name, &["type", "offset"])?;
+                Ok(Transform::translate(&field(step, "offset", name, vector)?))
+            }
+            "rotate" => {
+                object(step, name, &["type", "axis", "angle"])?;
+                Ok(Transform::rotate(
+                    &field(step, "axis", name, normal)?,
+                    field(step, "angle", name, number)?,
+                ))
+            }
+            "scale" => {
+                object(step, name, &["type", "factor"])?;
+                let factor = required(step, "factor", name)?;
+                let factors = match &factor.kind {
+                    Kind::Number(f) => Vector3::new(*f, *f, *f),
+                    _ => vector(factor, &member(name, "factor"))?,
+                };
+                if factors.x() == 0.0 || factors.y() == 0.0 || factors.z() == 0.0 {
+                    return error(
+                        factor,
+                        &member(name, "factor"),
+                        "must not be zero".to_string(),
+                    );
+                }
+
+                Ok(Transform::scale(&factors))
+            }
+            "matrix" => {
+                // The three rows of the affine part, the last row is implicit.
+                object(step, name, &["type", "rows"])?;
+                let rows = required(step, "rows", name)?;
+                let values = list(rows, &member(name, "rows"), numbers::<4>)?;
+                let [r0, r1, r2]: [[f64; 4]; 3] = match values.try_into() {
+                    Ok(values) => values,
+                    Err(values) => {
+                        return error(
+                            rows,
+                            &member(name, "rows"),
+                            format!("expected 3 rows, found {}", values.len()),
+                        )
+                    }
+                };
+
+                match Transform::new(Matrix4::new([r0, r1, r2, [0.0, 0.0, 0.0, 1.0]])) {
+                    Some(transform) => Ok(transform),
+                    None => error(rows, &member(name, "rows"), "is not invertible".to_string()),
+                }
+            }
+            _ => error(
+                required(step, "type", name)?,
+                &member(name, "type"),
+                format!("unknown transform type \"{}\"", kind),
+            ),
+        }
+    })?;
+
+    Ok(steps.iter().fold(Transform::identity(), |transform, step| {
+        transform.then(step)
+    }))
+}
+
+fn color(value: &Value, field: &str) -> Result<Color, SceneError> {
+    let [r, g, b] = triple(value, field)?;
+    Ok(Color::new(r, g, b))
+}
+
+fn kind<'v>(value: &'v Value, field: &str) -> Result<&'v str, SceneError> {
+    string(required(value, "type", field)?, &member(field, "type"))
+}
+
+fn member(field: &str, key: &str) -> String {
+    format!("{}.{}", field, key)
+}
+
+fn optional<T, F>(value: &Value, key: &str, field: &str, default: T, f: F) -> Result<T, SceneError>
+where
+    F: Fn(&Value, &str) -> Result<T, SceneError>,
+{
+    match value.get(key) {
+        Some(v) => f(v, &member(field, key)),
+        None => Ok(default),
+    }
+}
+
+fn field<T, F>(value: &Value, key: &str, field: &str, f: F) -> Result<T, SceneError>
+where
+    F: Fn(&Value, &str) -> Result<T, SceneError>,
+{
+    f(required(value, key, field)?, &member(field, key))
+}
+
+pub(super) struct Loader {
+    directory: PathBuf,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    emissive: HashSet<String>,
+}
+
+impl Loader {
+    pub(super) fn new(directory: &Path) -> Loader {
+        Loader {
+            directory: directory.to_path_buf(),
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            emissive: HashSet::new(),
+        }
+    }
+
+    pub(super) fn scene(&mut self, document: &Value) -> Result<Scene, SceneError> {
+        object(
+            document,
+            "scene",
+            &[
+                "camera",
+                "background",
+                "fog",
+                "integrator",
+                "lights",
+                "textures",
+                "materials",
+                "objects",
+            ],
+        )?;
+
+        let mut camera = match document.get("camera") {
+            Some(v) => self.camera(v, "camera")?,
+            None => return error(document, "scene", "missing field \"camera\"".to_string()),
+        };
+        if let Some(background) = document.get("background") {
+            camera.set_background(self.background(background, "background")?);
+        }
+        if let Some(fog) = document.get("fog") {
+            camera.set_fog(Some(self.fog(fog, "fog")?));
+        }
+        if let Some(integrator) = document.get("integrator") {
+            camera.set_integrator(self.integrator(integrator, "integrator")?);
+        }
+        if let Some(lights) = document.get("lights") {
+            let lights = array(lights, "lights")?
+                .iter()
+                .enumerate()
+                .map(|(n, definition)| self.light(definition, &format!("lights[{}]", n)))
+                .collect::<Result<Vec<_>, _>>()?;
+            camera.set_delta_lights(lights);
+        }
+
+        if let Some(textures) = document.get("textures") {
+            let definitions = match &textures.kind {
+                Kind::Object(members) => members,
+                _ => return mismatch(textures, "textures", "an object"),
+            };
+
+            // Textures may refer to the ones defined before them.
+            for (name, definition) in definitions {
+                let texture = self.texture(definition, &member("textures", name))?;
+                self.textures.insert(name.clone(), texture);
+            }
+        }
+
+        if let Some(materials) = document.get("materials") {
+            let definitions = match &materials.kind {
+                Kind::Object(members) => members,
+                _ => return mismatch(materials, "materials", "an object"),
+            };
+
+            for (name, definition) in definitions {
+                let material = self.material(definition, &member("materials", name))?;
+                self.materials.insert(name.clone(), material);
+                if definition.get("type").and_then(Value::as_str) == Some("diffuse_light") {
+                    self.emissive.insert(name.clone());
+                }
+            }
+        }
+
+        let mut world = HittableList::new();
+        let mut lights = HittableList::new();
+        match camera.background() {
+            Background::Environment(light) => lights.add(light.clone()),
+            Background::Sky(sky) => lights.add(sky.clone()),
+            _ => (),
+        }
+        if let Some(objects) = document.get("objects") {
+            for (n, definition) in array(objects, "objects")?.iter().enumerate() {
+                let object = self.object(definition, &format!("objects[{}]", n))?;
+                if self.is_light(definition) {
+                    lights.add(object.clone());
+                }
+                world.add(object);
+            }
+        }
+
+        Ok(Scene {
+            camera,
+            world,
+            lights,
+        })
+    }
+
+    // Emissive objects are sampled as lights when they know how to sample themselves, which
+    // excludes meshes, moving spheres and transformed objects.
+    fn is_light(&self, definition: &Value) -> bool {
+        let kind = definition.get("type").and_then(Value::as_str);
+        let material = definition.get("material").and_then(Value::as_str);
+
+        matches!(kind, Some("sphere" | "quad" | "disk" | "triangle"))
+            && material.is_some_and(|m| self.emissive.contains(m))
+            && definition.get("center2").is_none()
+            && definition.get("transform").is_none()
+    }
+
+    fn camera(&self, value: &Value, name: &str) -> Result<Camera, SceneError> {
+        object(
+            value,
+            name,
+            &[
+                "lookfrom",
+                "lookat",
+                "vup",
+                "aspect_ratio",
+                "image_width",
+                "vfov",
+                "focus_dist",
+                "defocus_angle",
+                "samples_per_pixel",
+                "max_depth",
+                "min_depth",
+                "projection",
+                "stereo",
+            ],
+        )?;
+
+        let aspect_ratio = optional(value, "aspect_ratio", name, 16.0 / 9.0, number)?;
+        if aspect_ratio <= 0.0 {
+            return error(
+                required(value, "aspect_ratio", name)?,
+                &member(name, "aspect_ratio"),
+                "must be positive".to_string(),
+            );
+        }
+
+        // The view needs a direction, and an up vector leaning away from it to orient the
+        // image around it.
+        let lookfrom = field(value, "lookfrom", name, point)?;
+        let lookat = field(value, "lookat", name, point)?;
+        let vup = optional(value, "vup", name, Vector3::new(0.0, 1.0, 0.0), vector)?;
+        let direction = Vector3::from(&lookat - &lookfrom);
+        if direction.norm() == 0.0 {
+            return error(
+                required(value, "lookat", name)?,
+                &member(name, "lookat"),
+                "must differ from lookfrom".to_string(),
+            );
+        }
+        if direction.cross(&vup).norm() <= 1e-9 * direction.norm() * vup.norm() {
+            return error(
+                value.get("vup").unwrap_or(value),
+                &member(name, "vup"),
+                "must not be parallel to the view direction".to_string(),
+            );
+        }
+
+        let vfov = optional(value, "vfov", name, 90.0, number)?;
+        if vfov <= 0.0 || vfov >= 180.0 {
+            return error(
+                required(value, "vfov", name)?,
+                &member(name, "vfov"),
+                "must be between 0 and 180, exclusive".to_string(),
+            );
+        }
+        let focus_dist = optional(value, "focus_dist", name, 10.0, number)?;
+        if focus_dist <= 0.0 {
+            return error(
+                required(value, "focus_dist", name)?,
+                &member(name, "focus_dist"),
+                "must be positive".to_string(),
+            );
+        }
+
+        let mut camera = Camera::new(
+            lookfrom,
+            lookat,
+            vup,
+            aspect_ratio,
+            optional(value, "image_width", name, 400, count)? as u32,
+            vfov,
+            focus_dist,
+            optional(value, "defocus_angle", name, 0.0, number)?,
+        );
+        camera
+            .set_antialiasing(optional(value, "samples_per_pixel", name, 10, count)? as usize)
+            .set_maximum_depth(optional(value, "max_depth", name, 10, integer)? as usize)
+            .set_minimum_depth(optional(value, "min_depth", name, 3, integer)? as usize);
+        if let Some(projection) = value.get("projection") {
+            camera.set_projection(self.projection(projection, &member(name, "projection"))?);
+        }
+        if let Some(stereo) = value.get("stereo") {
+            let stereo = self.stereo(stereo, &member(name, "stereo"), &camera)?;
+            camera.set_stereo(Some(stereo));
+        }
+
+        Ok(camera)
+    }
+
+    // Views converge on the focus plane unless told otherwise, but for omnidirectional stereo
+    // panoramas whose views are parallel.
+    fn stereo(&self, value: &Value, name: &str, camera: &Camera) -> Result<Stereo, SceneError> {
+        object(value, name, &["layout", "ipd", "convergence"])?;
+
+        let layout = match value.get("layout") {
+            None => Layout::SideBySide,
+            Some(layout) => match string(layout, &member(name, "layout"))? {
+                "side_by_side" => Layout::SideBySide,
+                "top_bottom" => Layout::TopBottom,
+                other => {
+                    return error(
+                        layout,
+                        &member(name, "layout"),
+                        format!("unknown stereo layout \"{}\"", other),
+                    )
+                }
+            },
+        };
+        let ipd = optional(value, "ipd", name, 0.064, number)?;
+        if ipd < 0.0 {
+            return error(
+                required(value, "ipd", name)?,
+                &member(name, "ipd"),
+                "must not be negative".to_string(),
+            );
+        }
+        let convergence = match value.get("convergence") {
+            None if camera.projection() == Projection::Equirectangular => f64::INFINITY,
+            None => camera.focus_dist(),
+            Some(Value {
+                kind: Kind::String(s),
+                ..
+            }) if s == "parallel" => f64::INFINITY,
+            Some(convergence) => {
+                let field = member(name, "convergence");
+                if let Kind::String(other) = &convergence.kind {
+                    return error(
+                        convergence,
+                        &field,
+                        format!("expected a number or \"parallel\", found \"{}\"", other),
+                    );
+                }
+                let distance = number(convergence, &field)?;
+                if distance <= 0.0 {
+                    return error(convergence, &field, "must be positive".to_string());
+                }
+                distance
+            }
+        };
+
+        Ok(Stereo {
+            layout,
+            ipd,
+            convergence,
+        })
+    }
+
+    fn projection(&self, value: &Value, name: &str) -> Result<Projection, SceneError> {
+        let kind = kind(value, name)?;
+
+        match kind {
+            "perspective" => {
+                object(value, name, &["type"])?;
+                Ok(Projection::Perspective)
+            }
+            "orthographic" => {
+                object(value, name, &["type"])?;
+                Ok(Projection::Orthographic)
+            }
+            "fisheye" => {
+                object(value, name, &["type", "fov", "mapping"])?;
+                let fov = optional(value, "fov", name, 180.0, number)?;
+                if fov <= 0.0 || fov > 360.0 {
+                    return error(
+                        required(value, "fov", name)?,
+                        &member(name, "fov"),
+                        "must be in (0, 360]".to_string(),
+                    );
+                }
+                let mapping = match value.get("mapping") {
+                    None => Fisheye::Equisolid,
+                    Some(mapping) => match string(mapping, &member(name, "mapping"))? {
+                        "equidistant" => Fisheye::Equidistant,
+                        "equisolid" => Fisheye::Equisolid,
+                        other => {
+                            return error(
+                                mapping,
+                                &member(name, "mapping"),
+                                format!("unknown fisheye mapping \"{}\"", other),
+                            )
+                        }
+                    },
+                };
+
+                Ok(Projection::Fisheye { fov, mapping })
+            }
+            "equirectangular" => {
+                object(value, name, &["type"])?;
+                Ok(Projection::Equirectangular)
+            }
+            _ => error(
+                required(value, "type", name)?,
+                &member(name, "type"),
+                format!("unknown projection type \"{}\"", kind),
+            ),
+        }
+    }
+
+    fn background(&self, value: &Value, name: &str) -> Result<Background, SceneError> {
+        let kind = kind(value, name)?;
+
+        match kind {
+            "black" => {
+                object(value, name, &["type"])?;
+                Ok(Background::black())
+            }
+            "solid" => {
+                object(value, name, &["type", "color"])?;
+                Ok(Background::Solid(field(value, "color", name, color)?))
+            }
+            "gradient" => {
+                object(value, name, &["type", "horizon", "zenith"])?;
+                Ok(Background::Gradient {
+                    horizon: field(value, "horizon", name, color)?,
+                    zenith: field(value, "zenith", name, color)?,
+                })
+            }
+            "environment" => {
+                object(value, name, &["type", "file", "intensity", "rotation"])?;
+                let file = required(value, "file", name)?;
+                let path = self.directory.join(string(file, &member(name, "file"))?);
+
+                let mut light = match EnvironmentLight::load(&path) {
+                    Ok(light) => light,
+                    Err(e) => {
+                        return error(
+                            file,
+                            &member(name, "file"),
+                            format!("cannot read image {}: {}", path.display(), e),
+                        )
+                    }
+                };
+                light
+                    .set_intensity(optional(value, "intensity", name, 1.0, number)?)
+                    .set_rotation(optional(value, "rotation", name, 0.0, number)?);
+
+                Ok(Background::Environment(Arc::new(light)))
+            }
+            "sky" => {
+                object(
+                    value,
+                    name,
+                    &["type", "elevation", "azimuth", "turbidity", "intensity"],
+                )?;
+                let elevation = optional(value, "elevation", name, 45.0, number)?;
+                if !(0.0..=90.0).contains(&elevation) {
+                    return error(
+                        required(value, "elevation", name)?,
+                        &member(name, "elevation"),
+                        "must be between 0 and 90 degrees".to_string(),
+                    );
+                }
+                let turbidity = optional(value, "turbidity", name, 3.0, number)?;
+                if !(2.0..=10.0).contains(&turbidity) {
+                    return error(
+                        required(value, "turbidity", name)?,
+                        &member(name, "turbidity"),
+                        "must be between 2 and 10".to_string(),
+                    );
+                }
+
+                let mut sky = Sky::new(
+                    elevation,
+                    optional(value, "azimuth", name, 0.0, number)?,
+                    turbidity,
+                );
+                sky.set_intensity(optional(value, "intensity", name, 1.0, number)?);
+
+                Ok(Background::Sky(Arc::new(sky)))
+            }
+            _ => error(
+                required(value, "type", name)?,
+                &member(name, "type"),
+                format!("unknown background type \"{}\"", kind),
+            ),
+        }
+    }
+
+    fn fog(&self, value: &Value, name: &str) -> Result<Fog, SceneError> {
+        object(value, name, &["density", "albedo", "height", "falloff"])?;
+
+        let density = field(value, "density", name, number)?;
+        if density <= 0.0 {
+            return error(
+                required(value, "density", name)?,
+                &member(name, "density"),
+                "must be positive".to_string(),
+            );
+        }
+        if let Some(v) = value.get("falloff") {
+            if number(v, &member(name, "falloff"))? < 0.0 {
+                return error(
+                    v,
+                    &member(name, "falloff"),
+                    "must not be negative".to_string(),
+                );
+            }
+        }
+
+        let mut fog = Fog::new(
+            density,
+            optional(value, "albedo", name, Color::new(1.0, 1.0, 1.0), color)?,
+        );
+        fog.set_falloff(
+            optional(value, "height", name, 0.0, number)?,
+            optional(value, "falloff", name, 0.0, number)?,
+        );
+
+        Ok(fog)
+    }
+
+    fn light(&self, value: &Value, name: &str) -> Result<Arc<dyn Light>, SceneError> {
+        let kind = kind(value, name)?;
+        // Intensity of the point lights, irradiance of the directional ones.
+        let emit = |value: &Value| -> Result<Color, SceneError> {
+            let color = optional(value, "color", name, Color::new(1.0, 1.0, 1.0), color)?;
+            Ok(optional(value, "intensity", name, 1.0, number)? * color)
+        };
+
+        match kind {
+            "point" => {
+                object(value, name, &["type", "position", "color", "intensity"])?;
+                Ok(Arc::new(PointLight::new(
+                    field(value, "position", name, point)?,
+                    emit(value)?,
+                )))
+            }
+            "spot" => {
+                object(
+                    value,
+                    name,
+                    &[
+                        "type",
+                        "position",
+                        "direction",
+                        "color",
+                        "intensity",
+                        "angle",
+                        "falloff",
+                    ],
+                )?;
+                let angle = optional(value, "angle", name, 30.0, number)?;
+                if angle <= 0.0 || angle > 180.0 {
+                    return error(
+                        required(value, "angle", name)?,
+                        &member(name, "angle"),
+                        "must be in (0, 180]".to_string(),
+                    );
+                }
+                let falloff = optional(value, "falloff", name, f64::min(5.0, angle), number)?;
+                if falloff < 0.0 || falloff > angle {
+                    return error(
+                        required(value, "falloff", name)?,
+                        &member(name, "falloff"),
+                        "must be between 0 and the angle".to_string(),
+                    );
+                }
+
+                Ok(Arc::new(SpotLight::new(
+                    field(value, "position", name, point)?,
+                    field(value, "direction", name, normal)?,
+                    emit(value)?,
+                    angle,
+                    falloff,
+                )))
+            }
+            "directional" => {
+                object(value, name, &["type", "direction", "color", "intensity"])?;
+                Ok(Arc::new(DirectionalLight::new(
+                    field(value, "direction", name, normal)?,
+                    emit(value)?,
+                )))
+            }
+            _ => error(
+                required(value, "type", name)?,
+                &member(name, "type"),
+                format!("unknown light type \"{}\"", kind),
+            ),
+        }
+    }
+
+    // Gather radius of the photon mapping integrators, scaled to the scene when missing.
+    fn gather_radius(&self, value: &Value, name: &str) -> Result<Option<f64>, SceneError> {
+        let Some(radius_value) = value.get("radius") else {
+            return Ok(None);
+        };
+        let radius = number(radius_value, &member(name, "radius"))?;
+        if radius <= 0.0 {
+            return error(
+                radius_value,
+                &member(name, "radius"),
+                "must be positive".to_string(),
+            );
+        }
+
+        Ok(Some(radius))
+    }
+
+    // Integrators go by the names of the command line, those with settings taking them here.
+    fn integrator(&self, value: &Value, name: &str) -> Result<Arc<dyn Integrator>, SceneError> {
+        let kind = kind(value, name)?;
+
+        match kind {
+            "photon" => {
+                object(value, name, &["type", "photons", "radius"])?;
+
+                Ok(Arc::new(PhotonMapping::new(
+                    optional(value, "photons", name, 100_000, count)? as usize,
+                    self.gather_radius(value, name)?,
+                )))
+            }
+            "sppm" => {
+                object(
+                    value,
+                    name,
+                    &["type", "photons", "radius", "passes", "alpha"],
+                )?;
+                let alpha = optional(value, "alpha", name, 2.0 / 3.0, number)?;
+                if alpha <= 0.0 || alpha > 1.0 {
+                    return error(
+                        required(value, "alpha", name)?,
+                        &member(name, "alpha"),
+                        "must be in (0, 1]".to_string(),
+                    );
+                }
+
+                Ok(Arc::new(PhotonMapping::progressive(
+                    optional(value, "photons", name, 100_000, count)? as usize,
+                    self.gather_radius(value, name)?,
+                    optional(value, "passes", name, 64, count)? as usize,
+                    alpha,
+                )))
+            }
+            "ao" => {
+                object(value, name, &["type", "samples", "distance"])?;
+                let distance = optional(value, "distance", name, f64::INFINITY, number)?;
+                if distance <= 0.0 {
+                    return error(
+                        required(value, "distance", name)?,
+                        &member(name, "distance"),
+                        "must be positive".to_string(),
+                    );
+                }
+
+                Ok(Arc::new(AmbientOcclusion::new(
+                    optional(value, "samples", name, 16, count)? as usize,
+                    distance,
+                )))
+            }
+            _ => match integrator::by_name(kind) {
+                Some(integrator) => {
+                    object(value, name, &["type"])?;
+                    Ok(integrator)
+                }
+                None => error(
+                    required(value, "type", name)?,
+                    &member(name, "type"),
+                    format!(
+                        "unknown integrator type \"{}\", expected one of: {}",
+                        kind,
+                        integrator::NAMES.join(", ")
+                    ),
+                ),
+            },
+        }
+    }
+
+    fn material(&self, value: &Value, name: &str) -> Result<Arc<dyn Material>, SceneError> {
+        let kind = kind(value, name)?;
+
+        match kind {
+            "lambertian" => {
+                object(value, name, &["type", "albedo"])?;
+                Ok(Arc::new(Lambertian::from_texture(field(
+                    value,
+                    "albedo",
+                    name,
+                    |v, n| self.texture(v, n),
+                )?)))
+            }
+            "metal" => {
+                object(value, name, &["type", "albedo", "fuzz"])?;
+                Ok(Arc::new(Metal::from_texture(
+                    field(value, "albedo", name, |v, n| self.texture(v, n))?,
+                    optional(value, "fuzz", name, 0.0, number)?,
+                )))
+            }
+            "dielectric" => {
+                object(value, name, &["type", "refraction_index"])?;
+                Ok(Arc::new(Dielectric::new(field(
+                    value,
+                    "refraction_index",
+                    name,
+                    number,
+                )?)))
+            }
+            "diffuse_light" => {
+                object(value, name, &["type", "emit", "intensity"])?;
+                let intensity = optional(value, "intensity", name, 1.0, number)?;
+
+                Ok(Arc::new(DiffuseLight::from_texture(
+                    field(value, "emit", name, |v, n| self.texture(v, n))?,
+                    intensity,
+                )))
+            }
+            _ => error(
+                required(value, "type", name)?,
+                &member(name, "type"),
+                format!("unknown material type \"{}\"", kind),
+            ),
+        }
+    }
+
+    // A texture is either a color, the name of a texture of the scene or an inline definition.
+    fn texture(&self, value: &Value, name: &str) -> Result<Arc<dyn Texture>, SceneError> {
+        match &value.kind {
+            Kind::Array(_) => return Ok(Arc::new(SolidColor::new(color(value, name)?))),
+            Kind::String(reference) => {
+                return match self.textures.get(reference) {
+                    Some(texture) => Ok(texture.clone()),
+                    None => error(value, name, format!("unknown texture \"{}\"", reference)),
+                }
+            }
+            Kind::Object(_) => (),
+            _ => return mismatch(value, name, "a color, a texture name or a texture"),
+        }
+
+        let kind = kind(value, name)?;
+        match kind {
+            "solid" => {
+                object(value, name, &["type", "color"])?;
+                Ok(Arc::new(SolidColor::new(field(
+                    value, "color", name, color,
+                )?)))
+            }
+            "checker" => {
+                object(value, name, &["type", "scale", "even", "odd"])?;
+                let scale = optional(value, "scale", name, 1.0, number)?;
+                if scale <= 0.0 {
+                    return error(
+                        required(value, "scale", name)?,
+                        &member(name, "scale"),
+                        "must be positive".to_string(),
+                    );
+                }
+
+                Ok(Arc::new(Checker::new(
+                    scale,
+                    field(value, "even", name, |v, n| self.texture(v, n))?,
+                    field(value, "odd", name, |v, n| self.texture(v, n))?,
+                )))
+            }
+            "image" => {
+                object(value, name, &["type", "file"])?;
+                let file = required(value, "file", name)?;
+                let path = self.directory.join(string(file, &member(name, "file"))?);
+
+                match ImageTexture::load(&path) {
+                    Ok(texture) => Ok(Arc::new(texture)),
+                    Err(e) => error(
+                        file,
+                        &member(name, "file"),
+                        format!("cannot read image {}: {}", path.display(), e),
+                    ),
+                }
+            }
+            "noise" => {
+                object(value, name, &["type", "scale", "pattern", "color"])?;
+                let pattern = match value.get("pattern") {
+                    Some(v) => string(v, &member(name, "pattern"))?,
+                    None => "smooth",
+                };
+                let pattern = match pattern {
+                    "smooth" => NoiseKind::Smooth,
+                    "turbulence" => NoiseKind::Turbulence,
+                    "marble" => NoiseKind::Marble,
+                    _ => {
+                        return error(
+                            required(value, "pattern", name)?,
+                            &member(name, "pattern"),
+                            format!("unknown noise pattern \"{}\"", pattern),
+                        )
+                    }
+                };
+
+                Ok(Arc::new(NoiseTexture::new(
+                    optional(value, "scale", name, 1.0, number)?,
+                    pattern,
+                    optional(value, "color", name, Color::new(1.0, 1.0, 1.0), color)?,
+                )))
+            }
+            _ => error(
+                required(value, "type", name)?,
+                &member(name, "type"),
+                format!("unknown texture type \"{}\"", kind),
+            ),
+        }
+    }
+
+    fn material_ref(&self, value: &Value, name: &str) -> Result<Arc<dyn Material>, SceneError> {
+        let reference = string(
+            required(value, "material", name)?,
+            &member(name, "material"),
+        )?;
+
+        match self.materials.get(reference) {
+            Some(material) => Ok(material.clone()),
+            None => error(
+                required(value, "material", name)?,
+                &member(name, "material"),
+                format!("unknown material \"{}\"", reference),
+            ),
+        }
+    }
+
+    // Objects of any type may be placed by an optional transform.
+    fn object(&self, value: &Value, name: &str) -> Result<Arc<dyn Hittable>, SceneError> {
+        let shape = self.shape(value, name)?;
+
+        match value.get("transform") {
+            Some(v) => Ok(Arc::new(Transformed::new(
+                shape,
+                transform(v, &member(name, "transform"))?,
+            ))),
+            None => Ok(shape),
+        }
+    }
+
+    fn shape(&self, value: &Value, name: &str) -> Result<Arc<dyn Hittable>, SceneError> {
+        let kind = kind(value, name)?;
+
+        match kind {
+            "sphere" => {
+                object(
+                    value,
+                    name,
+                    &[
+                        "type",
+                        "center",
+                        "center2",
+                        "radius",
+                        "material",
+                        "transform",
+                    ],
+                )?;
+
+                let center = field(value, "center", name, point)?;
+                let radius = field(value, "radius", name, number)?;
+                let material = self.material_ref(value, name)?;
+
+                Ok(match value.get("center2") {
+                    Some(v) => Arc::new(Sphere::with_motion(
+                        center,
+                        point(v, &member(name, "center2"))?,
+                        radius,
+                        material,
+                    )),
+                    None => Arc::new(Sphere::new(center, radius, material)),
+                })
+            }
+            "quad" => {
+                object(
+                    value,
+                    name,
+                    &["type", "q", "u", "v", "material", "transform"],
+                )?;
+
+                Ok(Arc::new(Quad::new(
+                    field(value, "q", name, point)?,
+                    field(value, "u", name, vector)?,
+                    field(value, "v", name, vector)?,
+                    self.material_ref(value, name)?,
+                )))
+            }
+            "box" => {
+                object(
+                    value,
+                    name,
+                    &["type", "min", "max", "material", "transform"],
+                )?;
+
+                Ok(Arc::new(make_box(
+                    &field(value, "min", name, point)?,
+                    &field(value, "max", name, point)?,
+                    self.material_ref(value, name)?,
+                )))
+            }
+            "disk" => {
+                object(
+                    value,
+                    name,
+                    &[
+                        "type",
+                        "center",
+                        "normal",
+                        "radius",
+                        "material",
+                        "transform",
+                    ],
+                )?;
+
+                Ok(Arc::new(Disk::new(
+                    field(value, "center", name, point)?,
+                    field(value, "normal", name, normal)?,
+                    field(value, "radius", name, number)?,
+                    self.material_ref(value, name)?,
+                )))
+            }
+            "plane" => {
+                object(
+                    value,
+                    name,
+                    &["type", "point", "normal", "material", "transform"],
+                )?;
+
+                Ok(Arc::new(Plane::new(
+                    field(value, "point", name, point)?,
+                    field(value, "normal", name, normal)?,
+                    self.material_ref(value, name)?,
+                )))
+            }
+            "medium" => {
+                object(
+                    value,
+                    name,
+                    &["type", "boundary", "density", "albedo", "transform"],
+                )?;
+
+                let density = field(value, "density", name, number)?;
+                if density <= 0.0 {
+                    return error(
+                        required(value, "density", name)?,
+                        &member(name, "density"),
+                        "must be positive".to_string(),
+                    );
+                }
+
+                Ok(Arc::new(ConstantMedium::with_phase_function(
+                    field(value, "boundary", name, |v, n| self.object(v, n))?,
+                    density,
+                    Arc::new(Isotropic::from_texture(field(
+                        value,
+                        "albedo",
+                        name,
+                        |v, n| self.texture(v, n),
+                    )?)),
+                )))
+            }
+            "triangle" => {
+                object(
+                    value,
+                    name,
+                    &[
+                        "type",
+                        "vertices",
+                        "normals",
+                        "uvs",
+                        "material",
+                        "transform",
+                    ],
+                )?;
+
+                Ok(Arc::new(Triangle::with_attributes(
+                    field(value, "vertices", name, |v, f| vertices(v, f, point))?,
+                    optional(value, "normals", name, None, |v, f| {
+                        vertices(v, f, vector).map(Some)
+                    })?,
+                    optional(value, "uvs", name, None, |v, f| {
+                        vertices(v, f, uv).map(Some)
+                    })?,
+                    self.material_ref(value, name)?,
+                )))
+            }
+            "mesh" => {
+                object(
+                    value,
+                    name,
+                    &[
+                        "type",
+                        "positions",
+                        "normals",
+                        "uvs",
+                        "indices",
+                        "material",
+                        "transform",
+                    ],
+                )?;
+
+                let positions = field(value, "positions", name, |v, f| list(v, f, point))?;
+                let normals = optional(value, "normals", name, Vec::new(), |v, f| {
+                    list(v, f, vector)
+                })?;
+                let uvs = optional(value, "uvs", name, Vec::new(), |v, f| list(v, f, uv))?;
+
+                for (key, count) in [("normals", normals.len()), ("uvs", uvs.len())] {
+                    if count != 0 && count != positions.len() {
+                        return error(
+                            required(value, key, name)?,
+                            &member(name, key),
+                            format!(
+                                "expected one entry per position ({}), found {}",
+                                positions.len(),
+                                count
+                            ),
+                        );
+                    }
+                }
+
+                let indices = field(value, "indices", name, |v, f| {
+                    list(v, f, |v, f| {
+                        let triangle = vertices(v, f, integer)?;
+                        match triangle.iter().find(|&&i| i as usize >= positions.len()) {
+                            Some(i) => error(
+                                v,
+                                f,
+                                format!(
+                                    "index {} out of range, the mesh has {} positions",
+                                    i,
+                                    positions.len()
+                                ),
+                            ),
+                            None => Ok(triangle.map(|i| i as usize)),
+                        }
+                    })
+                })?;
+
+                Ok(Arc::new(TriangleMesh::new(
+                    positions,
+                    normals,
+                    uvs,
+                    &indices,
+                    self.material_ref(value, name)?,
+                )))
+            }
+            "obj" => {
+                object(value, name, &["type", "file", "material", "transform"])?;
+
+                let file = field(value, "file", name, |v, f| string(v, f).map(String::from))?;
+                let material = match value.get("material") {
+                    Some(_) => Some(self.material_ref(value, name)?),
+                    None => None,
+                };
+
+                let mut meshes: Vec<Arc<dyn Hittable>> =
+                    obj::load(self.directory.join(file), material)?
+                        .into_iter()
+                        .map(|m| Arc::new(m.mesh) as Arc<dyn Hittable>)
+                        .collect();
+
+                Ok(Arc::new(BvhNode::from_objects(&mut meshes)))
+            }
+            _ => error(
+                required(value, "type", name)?,
+                &member(name, "type"),
+                format!("unknown object type \"{}\"", kind),
+            ),
+        }
+    }
+}
@@ -0,0 +1,818 @@
+pub mod builtin;
+pub mod json;
+mod loader;
+pub mod obj;
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::camera::Camera;
+use crate::film::Film;
+use crate::integrator::PathStats;
+use crate::objects::bvh::BvhNode;
+use crate::objects::HittableList;
+
+use json::Position;
+
+pub struct Scene {
+    pub camera: Camera,
+    pub world: HittableList,
+    pub lights: HittableList, // Emitters of the world sampled directly, if any
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneError {
+    pub file: Option<PathBuf>,
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl SceneError {
+    pub fn new(position: Option<Position>, message: String) -> SceneError {
+        SceneError {
+            file: None,
+            position,
+            message,
+        }
+    }
+
+    fn in_file(mut self, file: &Path) -> SceneError {
+        if self.file.is_none() {
+            self.file = Some(file.to_path_buf());
+        }
+
+        self
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+        if let Some(position) = &self.position {
+            write!(f, "{}:", position)?;
+        }
+        if self.file.is_some() || self.position.is_some() {
+            write!(f, " ")?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl Scene {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| {
+            SceneError::new(None, format!("cannot read scene: {}", e)).in_file(path)
+        })?;
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        Scene::parse_in(&text, directory).map_err(|e| e.in_file(path))
+    }
+
+    pub fn parse(text: &str) -> Result<Scene, SceneError> {
+        Scene::parse_in(text, Path::new(""))
+    }
+
+    // Parses a scene whose relative file references are resolved from the given directory.
+    fn parse_in(text: &str, directory: &Path) -> Result<Scene, SceneError> {
+        let document =
+            json::parse(text).map_err(|e| SceneError::new(Some(e.position), e.message))?;
+
+        loader::Loader::new(directory).scene(&document)
+    }
+
+    pub fn bvh(&self) -> BvhNode {
+        BvhNode::new(self.world.clone())
+    }
+
+    pub fn render(&self) -> Film {
+        self.camera.render_with_lights(&self.bvh(), &self.lights)
+    }
+
+    pub fn render_with_stats(&self) -> (Film, PathStats) {
+        self.camera.render_with_stats(&self.bvh(), &self.lights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::background::Background;
+    use crate::camera::{Fisheye, Layout, Projection, Stereo};
+    use crate::fog::Fog;
+    use crate::integrator::{self, photon_mapping::PhotonMapping};
+    use crate::interval::Interval;
+    use crate::light::{DirectionalLight, PointLight, SpotLight};
+    use crate::material::color::Color;
+    use crate::objects::point3::Point3;
+    use crate::objects::vector3::Vector3;
+    use crate::objects::Hittable;
+    use crate::ray::Ray;
+    use crate::sky::Sky;
+
+    const SCENE: &str = r#"{
+        "camera": {
+            "lookfrom": [13, 2, 3],
+            "lookat": [0, 0, 0],
+            "aspect_ratio": 1.5,
+            "image_width": 300,
+            "vfov": 20,
+            "defocus_angle": 0.6,
+            "samples_per_pixel": 32,
+            "max_depth": 8
+        },
+        "materials": {
+            "ground": { "type": "lambertian", "albedo": [0.5, 0.5, 0.5] },
+            "glass": { "type": "dielectric", "refraction_index": 1.5 },
+            "steel": { "type": "metal", "albedo": [0.7, 0.6, 0.5], "fuzz": 0.1 }
+        },
+        "objects": [
+            { "type": "sphere", "center": [0, -1000, 0], "radius": 1000, "material": "ground" },
+            { "type": "sphere", "center": [0, 1, 0], "radius": 1, "material": "glass" },
+            {
+                "type": "sphere",
+                "center": [4, 1, 0],
+                "center2": [4, 1.5, 0],
+                "radius": 1,
+                "material": "steel"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse() {
+        let scene = Scene::parse(SCENE).unwrap();
+
+        assert_eq!(scene.world.len(), 3);
+        assert_eq!(scene.camera.aspect_ratio(), 1.5);
+        assert_eq!(scene.camera.vfov(), 20.0);
+        assert_eq!(scene.camera.position(), &Point3::new(13.0, 2.0, 3.0));
+
+        let ray = Ray::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let rec = scene
+            .bvh()
+            .hit(&ray, &Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        assert_eq!(rec.t, 3.0);
+    }
+
+    #[test]
+    fn test_errors() {
+        let e = Scene::parse(&SCENE.replace("\"glass\" }", "\"marble\" }"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.position,
+            Some(Position {
+                line: 19,
+                column: 79
+            })
+        );
+        assert_eq!(
+            e.message,
+            "objects[1].material: unknown material \"marble\""
+        );
+
+        let e = Scene::parse(&SCENE.replace("\"radius\": 1,\n", "\"radius\": \"big\",\n"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.message,
+            "objects[2].radius: expected a number, found a string"
+        );
+        assert_eq!(e.position.unwrap().line, 24);
+
+        let e = Scene::parse(&SCENE.replace("\"fuzz\"", "\"fuz\""))
+            .err()
+            .unwrap();
+        assert_eq!(e.message, "materials.steel: unknown field \"fuz\"");
+
+        let e = Scene::parse(&SCENE.replace("[13, 2, 3]", "[13, 2]"))
+            .err()
+            .unwrap();
+        assert_eq!(e.message, "camera.lookfrom: expected 3 numbers, found 2");
+
+        let e =
+            Scene::parse(&SCENE.replace("\"samples_per_pixel\": 32", "\"samples_per_pixel\": 0"))
+                .err()
+                .unwrap();
+        assert_eq!(
+            e.message,
+            "camera.samples_per_pixel: expected a positive integer, found 0"
+        );
+
+        let e = Scene::parse(&SCENE.replace("\"max_depth\": 8", "\"max_depth\": 2.5"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.message,
+            "camera.max_depth: expected a non-negative integer, found 2.5"
+        );
+
+        // Cameras that would render nothing but NaN.
+        for (from, to, message, line) in [
+            (
+                "[13, 2, 3]",
+                "[0, 0, 0]",
+                "camera.lookat: must differ from lookfrom",
+                4,
+            ),
+            (
+                "\"lookat\": [0, 0, 0],",
+                "\"lookat\": [0, 0, 0], \"vup\": [13, 2, 3],",
+                "camera.vup: must not be parallel to the view direction",
+                4,
+            ),
+            (
+                "\"vfov\": 20",
+                "\"vfov\": 180",
+                "camera.vfov: must be between 0 and 180, exclusive",
+                7,
+            ),
+            (
+                "\"defocus_angle\": 0.6",
+                "\"focus_dist\": 0, \"defocus_angle\": 0.6",
+                "camera.focus_dist: must be positive",
+                8,
+            ),
+        ] {
+            let e = Scene::parse(&SCENE.replace(from, to)).err().unwrap();
+            assert_eq!(e.message, message);
+            assert_eq!(e.position.unwrap().line, line);
+        }
+
+        let e = Scene::parse("{ \"objects\": [ }").err().unwrap();
+        assert_eq!(
+            e.position,
+            Some(Position {
+                line: 1,
+                column: 16
+            })
+        );
+    }
+
+    #[test]
+    fn test_mesh() {
+        let mesh = r#"{
+            "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+            "materials": { "white": { "type": "lambertian", "albedo": [1, 1, 1] } },
+            "objects": [{
+                "type": "mesh",
+                "positions": [[-1, -1, 0], [1, -1, 0], [1, 1, 0], [-1, 1, 0]],
+                "uvs": [[0, 0], [1, 0], [1, 1], [0, 1]],
+                "indices": [[0, 1, 2], [0, 2, 3]],
+                "material": "white"
+            }]
+        }"#;
+
+        let scene = Scene::parse(mesh).unwrap();
+        let ray = Ray::new(Point3::new(0.5, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let rec = scene
+            .bvh()
+            .hit(&ray, &Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        assert_eq!(rec.t, 5.0);
+        assert!((rec.u - 0.75).abs() < 1e-12);
+
+        let e = Scene::parse(&mesh.replace("[0, 2, 3]", "[0, 2, 4]"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.message,
+            "objects[0].indices[1]: index 4 out of range, the mesh has 4 positions"
+        );
+
+        let e = Scene::parse(&mesh.replace("[1, 1], [0, 1]", "[1, 1]"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.message,
+            "objects[0].uvs: expected one entry per position (4), found 3"
+        );
+    }
+
+    #[test]
+    fn test_background() {
+        let scene = Scene::parse(
+            r#"{
+                "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                "background": { "type": "solid", "color": [0.1, 0.2, 0.3] },
+                "materials": {
+                    "lamp": { "type": "diffuse_light", "emit": [1, 0.5, 0.25], "intensity": 4 }
+                },
+                "objects": [{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "lamp" }]
+            }"#,
+        )
+        .unwrap();
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        assert_eq!(
+            scene.camera.background(),
+            &Background::Solid(Color::new(0.1, 0.2, 0.3))
+        );
+        let rec = scene
+            .bvh()
+            .hit(&ray, &Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        assert_eq!(rec.mat.emitted(&ray, &rec), Color::new(4.0, 2.0, 1.0));
+        assert_eq!(scene.lights.len(), 1);
+
+        let e = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "background": { "type": "stars" } }"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            e.message,
+            "background.type: unknown background type \"stars\""
+        );
+    }
+
+    #[test]
+    fn test_fog() {
+        let scene = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "fog": { "density": 0.1, "falloff": 0.5 } }"#,
+        )
+        .unwrap();
+        let mut fog = Fog::new(0.1, Color::new(1.0, 1.0, 1.0));
+        fog.set_falloff(0.0, 0.5);
+        assert_eq!(scene.camera.fog(), Some(&fog));
+
+        for density in ["-1", "0"] {
+            let e = Scene::parse(&format!(
+                r#"{{ "camera": {{ "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] }},
+                     "fog": {{ "density": {} }} }}"#,
+                density
+            ))
+            .err()
+            .unwrap();
+            assert_eq!(e.message, "fog.density: must be positive");
+        }
+    }
+
+    #[test]
+    fn test_integrators() {
+        let scene = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "integrator": { "type": "ao", "samples": 4, "distance": 2 } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            format!("{:?}", scene.camera.integrator()),
+            "AmbientOcclusion { samples: 4, distance: 2.0 }"
+        );
+
+        let scene = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "integrator": { "type": "bdpt" } }"#,
+        )
+        .unwrap();
+        assert_eq!(format!("{:?}", scene.camera.integrator()), "Bidirectional");
+
+        let scene = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "integrator": { "type": "sppm", "photons": 1000, "radius": 0.5 } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            format!("{:?}", scene.camera.integrator()),
+            format!(
+                "{:?}",
+                PhotonMapping::progressive(1000, Some(0.5), 64, 2.0 / 3.0)
+            )
+        );
+
+        let e = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "integrator": { "type": "photon", "radius": 0 } }"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(e.message, "integrator.radius: must be positive");
+
+        let e = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "integrator": { "type": "uv" } }"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            e.message,
+            "integrator.type: unknown integrator type \"uv\", expected one of: path, bdpt, \
+             photon, sppm, whitted, ao, normal, albedo, depth"
+        );
+
+        // Every integrator of the command line goes by the same name in scenes.
+        for name in integrator::NAMES {
+            let scene = Scene::parse(&format!(
+                r#"{{ "camera": {{ "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] }},
+                     "integrator": {{ "type": "{}" }} }}"#,
+                name
+            ))
+            .unwrap();
+            assert_eq!(
+                format!("{:?}", scene.camera.integrator()),
+                format!("{:?}", integrator::by_name(name).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn test_sky() {
+        let scene = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "background": { "type": "sky", "elevation": 20, "azimuth": -45,
+                                 "turbidity": 4, "intensity": 0.5 } }"#,
+        )
+        .unwrap();
+        let mut sky = Sky::new(20.0, -45.0, 4.0);
+        sky.set_intensity(0.5);
+        assert_eq!(scene.camera.background(), &Background::Sky(Arc::new(sky)));
+        assert_eq!(scene.lights.len(), 1);
+
+        let e = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "background": { "type": "sky", "turbidity": 1 } }"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(e.message, "background.turbidity: must be between 2 and 10");
+    }
+
+    #[test]
+    fn test_delta_lights() {
+        let scene = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "lights": [
+                     { "type": "point", "position": [0, 2, 0], "intensity": 5 },
+                     { "type": "spot", "position": [0, 4, 0], "direction": [0, -1, 0],
+                       "color": [1, 0.5, 0.25], "angle": 20, "falloff": 2 },
+                     { "type": "directional", "direction": [1, -1, 0], "intensity": 2 }
+                 ] }"#,
+        )
+        .unwrap();
+
+        let lights = scene.camera.delta_lights();
+        assert_eq!(lights.len(), 3);
+        assert_eq!(
+            format!("{:?}", lights[0]),
+            format!(
+                "{:?}",
+                PointLight::new(Point3::new(0.0, 2.0, 0.0), Color::new(5.0, 5.0, 5.0))
+            )
+        );
+        assert_eq!(
+            format!("{:?}", lights[1]),
+            format!(
+                "{:?}",
+                SpotLight::new(
+                    Point3::new(0.0, 4.0, 0.0),
+                    Vector3::new(0.0, -1.0, 0.0),
+                    Color::new(1.0, 0.5, 0.25),
+                    20.0,
+                    2.0
+                )
+            )
+        );
+        assert_eq!(
+            format!("{:?}", lights[2]),
+            format!(
+                "{:?}",
+                DirectionalLight::new(Vector3::new(1.0, -1.0, 0.0), Color::new(2.0, 2.0, 2.0))
+            )
+        );
+        assert!(scene.lights.is_empty());
+
+        let e = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "lights": [{ "type": "spot", "position": [0, 4, 0], "direction": [0, -1, 0],
+                              "angle": 10, "falloff": 15 }] }"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            e.message,
+            "lights[0].falloff: must be between 0 and the angle"
+        );
+
+        let e = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "lights": [{ "type": "area" }] }"#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(e.message, "lights[0].type: unknown light type \"area\"");
+    }
+
+    #[test]
+    fn test_projections() {
+        let camera = |projection: &str| {
+            Scene::parse(&format!(
+                r#"{{ "camera": {{ "lookfrom": [0, 0, 5], "lookat": [0, 0, 0],
+                                 "projection": {} }} }}"#,
+                projection
+            ))
+            .map(|scene| scene.camera.projection())
+        };
+
+        assert_eq!(
+            camera(r#"{ "type": "orthographic" }"#),
+            Ok(Projection::Orthographic)
+        );
+        assert_eq!(
+            camera(r#"{ "type": "fisheye" }"#),
+            Ok(Projection::Fisheye {
+                fov: 180.0,
+                mapping: Fisheye::Equisolid
+            })
+        );
+        assert_eq!(
+            camera(r#"{ "type": "fisheye", "fov": 200, "mapping": "equidistant" }"#),
+            Ok(Projection::Fisheye {
+                fov: 200.0,
+                mapping: Fisheye::Equidistant
+            })
+        );
+        assert_eq!(
+            camera(r#"{ "type": "equirectangular" }"#),
+            Ok(Projection::Equirectangular)
+        );
+
+        let message = |projection: &str| camera(projection).err().unwrap().message;
+        assert_eq!(
+            message(r#"{ "type": "fisheye", "fov": 400 }"#),
+            "camera.projection.fov: must be in (0, 360]"
+        );
+        assert_eq!(
+            message(r#"{ "type": "fisheye", "mapping": "stereographic" }"#),
+            "camera.projection.mapping: unknown fisheye mapping \"stereographic\""
+        );
+        assert_eq!(
+            message(r#"{ "type": "cylindrical" }"#),
+            "camera.projection.type: unknown projection type \"cylindrical\""
+        );
+    }
+
+    #[test]
+    fn test_stereo() {
+        let stereo = |stereo: &str| {
+            Scene::parse(&format!(
+                r#"{{ "camera": {{ "lookfrom": [0, 0, 5], "lookat": [0, 0, 0], "focus_dist": 3,
+                                 "stereo": {} }} }}"#,
+                stereo
+            ))
+            .map(|scene| scene.camera.stereo())
+        };
+
+        assert_eq!(
+            stereo("{}"),
+            Ok(Some(Stereo {
+                layout: Layout::SideBySide,
+                ipd: 0.064,
+                convergence: 3.0
+            }))
+        );
+        assert_eq!(
+            stereo(r#"{ "layout": "top_bottom", "ipd": 0.1, "convergence": 20 }"#),
+            Ok(Some(Stereo {
+                layout: Layout::TopBottom,
+                ipd: 0.1,
+                convergence: 20.0
+            }))
+        );
+
+        assert_eq!(
+            stereo(r#"{ "convergence": "parallel" }"#).map(|s| s.unwrap().convergence),
+            Ok(f64::INFINITY)
+        );
+
+        // Omnidirectional stereo panoramas have parallel views unless told otherwise.
+        let panorama = |convergence: &str| {
+            Scene::parse(&format!(
+                r#"{{ "camera": {{ "lookfrom": [0, 0, 5], "lookat": [0, 0, 0],
+                                 "projection": {{ "type": "equirectangular" }},
+                                 "stereo": {{ "layout": "top_bottom"{} }} }} }}"#,
+                convergence
+            ))
+            .unwrap()
+            .camera
+            .stereo()
+            .unwrap()
+            .convergence
+        };
+        assert_eq!(panorama(""), f64::INFINITY);
+        assert_eq!(panorama(r#", "convergence": 2"#), 2.0);
+
+        let message = |value: &str| stereo(value).err().unwrap().message;
+        assert_eq!(
+            message(r#"{ "convergence": "far" }"#),
+            "camera.stereo.convergence: expected a number or \"parallel\", found \"far\""
+        );
+        assert_eq!(
+            message(r#"{ "layout": "anaglyph" }"#),
+            "camera.stereo.layout: unknown stereo layout \"anaglyph\""
+        );
+        assert_eq!(
+            message(r#"{ "convergence": 0 }"#),
+            "camera.stereo.convergence: must be positive"
+        );
+    }
+
+    #[test]
+    fn test_environment() {
+        let directory = std::env::temp_dir().join("rustracer-environment-test");
+        fs::create_dir_all(&directory).unwrap();
+        let mut sky = Film::new(4, 2);
+        sky.set(1, 0, Color::new(8.0, 4.0, 2.0));
+        sky.save(directory.join("sky.hdr")).unwrap();
+        let path = directory.join("scene.json");
+        fs::write(
+            &path,
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "background": { "type": "environment", "file": "sky.hdr",
+                                 "intensity": 2, "rotation": 90 } }"#,
+        )
+        .unwrap();
+
+        let scene = Scene::load(&path).unwrap();
+        let Background::Environment(light) = scene.camera.background() else {
+            panic!("expected an environment background");
+        };
+        assert_eq!((light.image().width(), light.image().height()), (4, 2));
+        assert_eq!((light.intensity(), light.rotation()), (2.0, 90.0));
+        assert_eq!(scene.lights.len(), 1);
+
+        let e = Scene::parse(
+            r#"{ "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+                 "background": { "type": "environment", "file": "none.hdr" } }"#,
+        )
+        .err()
+        .unwrap();
+        assert!(e
+            .message
+            .starts_with("background.file: cannot read image none.hdr"));
+    }
+
+    #[test]
+    fn test_planar_objects() {
+        let planar = r#"{
+            "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+            "materials": { "white": { "type": "lambertian", "albedo": [1, 1, 1] } },
+            "objects": [
+                { "type": "plane", "point": [0, 0, -10], "normal": [0, 0, 1], "material": "white" },
+                { "type": "box", "min": [-1, -1, -1], "max": [1, 1, 1], "material": "white" },
+                { "type": "quad", "q": [2, 0, 0], "u": [1, 0, 0], "v": [0, 1, 0], "material": "white" },
+                { "type": "disk", "center": [-3, 0, 0], "normal": [0, 0, 1], "radius": 1, "material": "white" }
+            ]
+        }"#;
+
+        let bvh = Scene::parse(planar).unwrap().bvh();
+        let rayt = Interval::new(0.001, f64::INFINITY);
+        for (x, t) in [(0.0, 4.0), (2.5, 5.0), (-3.5, 5.0), (8.0, 15.0)] {
+            let ray = Ray::new(Point3::new(x, 0.5, 5.0), Vector3::new(0.0, 0.0, -1.0));
+            assert_eq!(bvh.hit(&ray, &rayt).unwrap().t, t);
+        }
+
+        let e = Scene::parse(&planar.replace(
+            "\"normal\": [0, 0, 1], \"material\"",
+            "\"normal\": [0, 0, 0], \"material\"",
+        ))
+        .err()
+        .unwrap();
+        assert_eq!(e.message, "objects[0].normal: must not be a zero vector");
+    }
+
+    #[test]
+    fn test_transforms() {
+        let transformed = r#"{
+            "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+            "materials": { "white": { "type": "lambertian", "albedo": [1, 1, 1] } },
+            "objects": [{
+                "type": "box",
+                "min": [-1, -1, -1],
+                "max": [1, 1, 1],
+                "material": "white",
+                "transform": [
+                    { "type": "scale", "factor": [1, 1, 2] },
+                    { "type": "rotate", "axis": [0, 1, 0], "angle": 90 },
+                    { "type": "translate", "offset": [0, 10, 0] }
+                ]
+            }]
+        }"#;
+
+        // Stretched along z, then turned to lie along x.
+        let bvh = Scene::parse(transformed).unwrap().bvh();
+        let ray = Ray::new(Point3::new(-5.0, 10.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let rec = bvh.hit(&ray, &Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!((rec.normal.x() + 1.0).abs() < 1e-9);
+
+        let e = Scene::parse(&transformed.replace("[1, 1, 2]", "0"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.message,
+            "objects[0].transform[0].factor: must not be zero"
+        );
+
+        let e = Scene::parse(&transformed.replace(
+            "{ \"type\": \"scale\", \"factor\": [1, 1, 2] }",
+            "{ \"type\": \"matrix\", \"rows\": [[1, 0, 0, 0], [0, 1, 0, 0], [1, 0, 0, 0]] }",
+        ))
+        .err()
+        .unwrap();
+        assert_eq!(e.message, "objects[0].transform[0].rows: is not invertible");
+    }
+
+    #[test]
+    fn test_medium() {
+        let fog = r#"{
+            "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+            "objects": [{
+                "type": "medium",
+                "boundary": { "type": "box", "min": [-1, -1, -1], "max": [1, 1, 1], "material": "none" },
+                "density": 1e9,
+                "albedo": [0.5, 0.5, 0.5]
+            }]
+        }"#;
+
+        // The boundary material is never used, but must still exist.
+        let e = Scene::parse(fog).err().unwrap();
+        assert_eq!(
+            e.message,
+            "objects[0].boundary.material: unknown material \"none\""
+        );
+
+        let fog = fog.replace(
+            "\"objects\"",
+            "\"materials\": { \"none\": { \"type\": \"dielectric\", \"refraction_index\": 1 } }, \"objects\"",
+        );
+        let bvh = Scene::parse(&fog).unwrap().bvh();
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let rec = bvh.hit(&ray, &Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-6);
+        let srec = rec.mat.scatter(&ray, &rec).unwrap();
+        assert_eq!(srec.attenuation, Color::new(0.5, 0.5, 0.5));
+
+        let e = Scene::parse(&fog.replace("1e9", "0")).err().unwrap();
+        assert_eq!(e.message, "objects[0].density: must be positive");
+    }
+
+    #[test]
+    fn test_textures() {
+        let textured = r#"{
+            "camera": { "lookfrom": [0, 0, 5], "lookat": [0, 0, 0] },
+            "textures": {
+                "red": [1, 0, 0],
+                "tiles": { "type": "checker", "scale": 0.5, "even": "red", "odd": [0, 0, 1] }
+            },
+            "materials": {
+                "floor": { "type": "lambertian", "albedo": "tiles" },
+                "marble": { "type": "metal", "albedo": { "type": "noise", "pattern": "marble" } }
+            },
+            "objects": [{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "floor" }]
+        }"#;
+
+        let scene = Scene::parse(textured).unwrap();
+        let ray = Ray::new(Point3::new(0.2, 0.2, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let rec = scene
+            .bvh()
+            .hit(&ray, &Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        let srec = rec.mat.scatter(&ray, &rec).unwrap();
+        assert_eq!(srec.attenuation, Color::new(0.0, 0.0, 1.0));
+
+        let e = Scene::parse(&textured.replace("\"even\": \"red\"", "\"even\": \"blue\""))
+            .err()
+            .unwrap();
+        assert_eq!(e.message, "textures.tiles.even: unknown texture \"blue\"");
+
+        let e = Scene::parse(&textured.replace(
+            "\"noise\", \"pattern\": \"marble\"",
+            "\"image\", \"file\": \"none.png\"",
+        ))
+        .err()
+        .unwrap();
+        assert!(e
+            .message
+            .starts_with("materials.marble.albedo.file: cannot read image none.png"));
+    }
+
+    #[test]
+    fn test_error_display() {
+        let e = SceneError::new(Some(Position { line: 3, column: 4 }), "oops".to_string())
+            .in_file(Path::new("scenes/cover.json"));
+
+        assert_eq!(e.to_string(), "scenes/cover.json:3:4: oops");
+    }
+}
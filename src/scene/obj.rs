@@ -0,0 +1,751 @@
+// Wavefront OBJ and MTL reader. Faces are triangulated and grouped into one triangle mesh per
+// group (or object) and material, each with its own vertex buffers.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::material::{
+    color::Color, dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian,
+    metal::Metal, Material,
+};
+use crate::objects::mesh::TriangleMesh;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::texture::image::ImageTexture;
+use crate::texture::Texture;
+
+use super::json::Position;
+use super::SceneError;
+
+pub struct ObjMesh {
+    pub name: String,
+    pub material: String,
+    pub mesh: TriangleMesh,
+}
+
+type Token<'a> = (&'a str, Position);
+
+fn error<T>(position: Position, message: String) -> Result<T, SceneError> {
+    Err(SceneError::new(Some(position), message))
+}
+
+fn read(path: &Path) -> Result<String, SceneError> {
+    fs::read_to_string(path)
+        .map_err(|e| SceneError::new(None, format!("cannot read file: {}", e)).in_file(path))
+}
+
+// Splits the text into statements made of whitespace separated tokens, dropping comments and
+// joining lines ending with a backslash.
+fn statements(text: &str) -> Vec<Vec<Token<'_>>> {
+    let mut statements = Vec::new();
+    let mut current = Vec::new();
+
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let trimmed = line.trim_end();
+        let (line, continued) = match trimmed.strip_suffix('\\') {
+            Some(rest) => (rest, true),
+            None => (trimmed, false),
+        };
+
+        let mut offset = 0;
+        for token in line.split_whitespace() {
+            let start = offset + line[offset..].find(token).unwrap_or(0);
+            offset = start + token.len();
+
+            let column = line[..start].chars().count() + 1;
+            current.push((
+                token,
+                Position {
+                    line: n + 1,
+                    column,
+                },
+            ));
+        }
+
+        if !continued && !current.is_empty() {
+            statements.push(std::mem::take(&mut current));
+        }
+    }
+
+    if !current.is_empty() {
+        statements.push(current);
+    }
+
+    statements
+}
+
+fn number(token: &Token) -> Result<f64, SceneError> {
+    match token.0.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(n),
+        _ => error(token.1, format!("expected a number, found \"{}\"", token.0)),
+    }
+}
+
+fn arguments<'t, 'a>(
+    statement: &'t [Token<'a>],
+    min: usize,
+    max: usize,
+) -> Result<&'t [Token<'a>], SceneError> {
+    let (keyword, position) = statement[0];
+    let arguments = &statement[1..];
+
+    if arguments.len() < min || arguments.len() > max {
+        let expected = if min == max {
+            format!("{}", min)
+        } else if max == usize::MAX {
+            format!("at least {}", min)
+        } else {
+            format!("{} to {}", min, max)
+        };
+        return error(
+            position,
+            format!(
+                "\"{}\" expects {} values, found {}",
+                keyword,
+                expected,
+                arguments.len()
+            ),
+        );
+    }
+
+    Ok(arguments)
+}
+
+fn color(statement: &[Token]) -> Result<Color, SceneError> {
+    let arguments = arguments(statement, 1, 3)?;
+    let values = arguments
+        .iter()
+        .map(number)
+        .collect::<Result<Vec<f64>, SceneError>>()?;
+
+    match values[..] {
+        [v] => Ok(Color::new(v, v, v)),
+        [r, g, b] => Ok(Color::new(r, g, b)),
+        _ => error(
+            statement[0].1,
+            format!("\"{}\" expects 1 or 3 values", statement[0].0),
+        ),
+    }
+}
+
+// Material description as read from a MTL file.
+struct MtlMaterial {
+    kd: Color,
+    map_kd: Option<Arc<dyn Texture>>,
+    ks: Color,
+    ke: Color,
+    ns: f64,
+    ni: f64,
+    d: f64,
+    illum: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> MtlMaterial {
+        MtlMaterial {
+            kd: Color::new(0.8, 0.8, 0.8),
+            map_kd: None,
+            ks: Color::new(0.0, 0.0, 0.0),
+            ke: Color::new(0.0, 0.0, 0.0),
+            ns: 0.0,
+            ni: 1.0,
+            d: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    // Emissive materials become diffuse lights. Transparent materials (dissolve below 1 or a
+    // refraction illumination model) become dielectrics, materials whose specular color dominates
+    // the diffuse one become metals with a fuzz derived from the Phong exponent, and everything
+    // else is Lambertian, textured by the diffuse map if there is one.
+    fn material(&self) -> Arc<dyn Material> {
+        if !self.ke.is_black() {
+            Arc::new(DiffuseLight::new(self.ke.clone()))
+        } else if self.d < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            let ni = if self.ni > 1.0 { self.ni } else { 1.5 };
+            Arc::new(Dielectric::new(ni))
+        } else if self.ks.max_component() > self.kd.max_component() {
+            let fuzz = f64::sqrt(2.0 / (self.ns.max(0.0) + 2.0));
+            Arc::new(Metal::new(self.ks.clone(), fuzz))
+        } else if let Some(map_kd) = &self.map_kd {
+            Arc::new(Lambertian::from_texture(map_kd.clone()))
+        } else {
+            Arc::new(Lambertian::new(self.kd.clone()))
+        }
+    }
+}
+
+// Texture maps are resolved from the given directory.
+fn parse_mtl(
+    text: &str,
+    directory: &Path,
+) -> Result<HashMap<String, Arc<dyn Material>>, SceneError> {
+    let mut definitions: Vec<(String, MtlMaterial)> = Vec::new();
+
+    for statement in statements(text) {
+        let (keyword, position) = statement[0];
+
+        if keyword == "newmtl" {
+            let name = arguments(&statement, 1, 1)?[0].0;
+            definitions.push((name.to_string(), MtlMaterial::default()));
+            continue;
+        }
+
+        let current = match definitions.last_mut() {
+            Some((_, m)) => m,
+            None => return error(position, format!("\"{}\" before newmtl", keyword)),
+        };
+
+        match keyword {
+            "Kd" => current.kd = color(&statement)?,
+            "Ks" => current.ks = color(&statement)?,
+            "Ke" => current.ke = color(&statement)?,
+            "Ns" => current.ns = number(&arguments(&statement, 1, 1)?[0])?,
+            "Ni" => current.ni = number(&arguments(&statement, 1, 1)?[0])?,
+            "d" => current.d = number(&arguments(&statement, 1, 1)?[0])?,
+            "Tr" => current.d = 1.0 - number(&arguments(&statement, 1, 1)?[0])?,
+            "illum" => {
+                let token = &arguments(&statement, 1, 1)?[0];
+                current.illum = match token.0.parse::<u32>() {
+                    Ok(illum) => illum,
+                    Err(_) => {
+                        return error(
+                            token.1,
+                            format!("expected an illumination model, found \"{}\"", token.0),
+                        )
+                    }
+                };
+            }
+            "map_Kd" => {
+                // Options come before the file name, which is the last token.
+                let (file, position) = *arguments(&statement, 1, usize::MAX)?.last().unwrap();
+                let path = directory.join(file);
+                match ImageTexture::load(&path) {
+                    Ok(texture) => current.map_kd = Some(Arc::new(texture)),
+                    Err(e) => {
+                        return error(position, format!("cannot read image \"{}\": {}", file, e))
+                    }
+                }
+            }
+            // Other texture maps and statements have no equivalent yet.
+            _ => {}
+        }
+    }
+
+    Ok(definitions
+        .into_iter()
+        .map(|(name, m)| (name, m.material()))
+        .collect())
+}
+
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Arc<dyn Material>>, SceneError> {
+    let path = path.as_ref();
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    parse_mtl(&read(path)?, directory).map_err(|e| e.in_file(path))
+}
+
+// Indices of the position, texture coordinates and normal of a face vertex.
+type VertexRef = (usize, Option<usize>, Option<usize>);
+
+struct Chunk {
+    name: String,
+    material: String,
+    triangles: Vec<[VertexRef; 3]>,
+}
+
+struct ObjParser<'p> {
+    directory: &'p Path,
+    material: Option<Arc<dyn Material>>,
+
+    positions: Vec<Point3>,
+    uvs: Vec<(f64, f64)>,
+    normals: Vec<Vector3>,
+    materials: HashMap<String, Arc<dyn Material>>,
+
+    name: String,
+    usemtl: String,
+    chunks: Vec<Chunk>,
+    chunk_index: HashMap<(String, String), usize>,
+}
+
+impl<'p> ObjParser<'p> {
+    fn new(directory: &'p Path, material: Option<Arc<dyn Material>>) -> ObjParser<'p> {
+        ObjParser {
+            directory,
+            material,
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            materials: HashMap::new(),
+            name: String::new(),
+            usemtl: String::new(),
+            chunks: Vec::new(),
+            chunk_index: HashMap::new(),
+        }
+    }
+
+    fn statement(&mut self, statement: &[Token]) -> Result<(), SceneError> {
+        match statement[0].0 {
+            "v" => {
+                let a = arguments(statement, 3, 4)?;
+                self.positions
+                    .push(Point3::new(number(&a[0])?, number(&a[1])?, number(&a[2])?));
+            }
+            "vt" => {
+                let a = arguments(statement, 1, 3)?;
+                let v = if a.len() > 1 { number(&a[1])? } else { 0.0 };
+                self.uvs.push((number(&a[0])?, v));
+            }
+            "vn" => {
+                let a = arguments(statement, 3, 3)?;
+                self.normals
+                    .push(Vector3::new(number(&a[0])?, number(&a[1])?, number(&a[2])?));
+            }
+            "f" => self.face(statement)?,
+            "o" | "g" => {
+                let names: Vec<&str> = statement[1..].iter().map(|t| t.0).collect();
+                self.name = names.join(" ");
+            }
+            // Libraries are not needed when a material replaces theirs.
+            "mtllib" if self.material.is_none() => {
+                for token in arguments(statement, 1, usize::MAX)? {
+                    let path = self.directory.join(token.0);
+                    let library = load_mtl(&path).map_err(|e| match e.position {
+                        Some(_) => e,
+                        None => SceneError::new(
+                            Some(token.1),
+                            format!("cannot load material library \"{}\": {}", token.0, e),
+                        ),
+                    })?;
+                    self.materials.extend(library);
+                }
+            }
+            "usemtl" => {
+                let name = arguments(statement, 1, 1)?[0].0;
+                if self.material.is_none() && !self.materials.contains_key(name) {
+                    return error(statement[1].1, format!("unknown material \"{}\"", name));
+                }
+                self.usemtl = name.to_string();
+            }
+            // Smoothing groups, lines, points, free-form geometry and render attributes are
+            // ignored.
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn index(token: &Token, text: &str, count: usize, what: &str) -> Result<usize, SceneError> {
+        let index = match text.parse::<i64>() {
+            Ok(i) => i,
+            Err(_) => return error(token.1, format!("invalid face vertex \"{}\"", token.0)),
+        };
+
+        // Negative indices count backwards from the last element read so far.
+        let resolved = if index < 0 {
+            count as i64 + index
+        } else {
+            index - 1
+        };
+        if index == 0 || resolved < 0 || resolved >= count as i64 {
+            return error(
+                token.1,
+                format!(
+                    "{} index {} out of range, {} defined so far",
+                    what, index, count
+                ),
+            );
+        }
+
+        Ok(resolved as usize)
+    }
+
+    fn face(&mut self, statement: &[Token]) -> Result<(), SceneError> {
+        let tokens = arguments(statement, 3, usize::MAX)?;
+
+        let mut vertices: Vec<VertexRef> = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            let mut parts = token.0.split('/');
+            let p = ObjParser::index(
+                token,
+                parts.next().unwrap_or(""),
+                self.positions.len(),
+                "position",
+            )?;
+            let t = match parts.next() {
+                Some("") | None => None,
+                Some(t) => Some(ObjParser::index(
+                    token,
+                    t,
+                    self.uvs.len(),
+                    "texture coordinate",
+                )?),
+            };
+            let n = match parts.next() {
+                Some("") | None => None,
+                Some(n) => Some(ObjParser::index(token, n, self.normals.len(), "normal")?),
+            };
+            if parts.next().is_some() {
+                return error(token.1, format!("invalid face vertex \"{}\"", token.0));
+            }
+
+            vertices.push((p, t, n));
+        }
+
+        let polygon: Vec<&Point3> = vertices.iter().map(|v| &self.positions[v.0]).collect();
+        let triangles = triangulate(&polygon);
+
+        let key = (self.name.clone(), self.usemtl.clone());
+        let index = match self.chunk_index.get(&key) {
+            Some(&index) => index,
+            None => {
+                self.chunks.push(Chunk {
+                    name: key.0.clone(),
+                    material: key.1.clone(),
+                    triangles: Vec::new(),
+                });
+                self.chunk_index.insert(key, self.chunks.len() - 1);
+                self.chunks.len() - 1
+            }
+        };
+
+        self.chunks[index].triangles.extend(
+            triangles
+                .into_iter()
+                .map(|[a, b, c]| [vertices[a], vertices[b], vertices[c]]),
+        );
+
+        Ok(())
+    }
+
+    fn meshes(self) -> Vec<ObjMesh> {
+        let default: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+
+        self.chunks
+            .into_iter()
+            .map(|chunk| {
+                // Texture coordinates and normals are only kept when every vertex has them.
+                let all = chunk.triangles.iter().flatten();
+                let has_uvs = all.clone().all(|v| v.1.is_some());
+                let has_normals = all.clone().all(|v| v.2.is_some());
+
+                let mut remap: HashMap<VertexRef, usize> = HashMap::new();
+                let mut positions = Vec::new();
+                let mut uvs = Vec::new();
+                let mut normals = Vec::new();
+                let mut indices = Vec::with_capacity(chunk.triangles.len());
+
+                for triangle in &chunk.triangles {
+                    indices.push(triangle.map(|(p, t, n)| {
+                        let key = (p, t.filter(|_| has_uvs), n.filter(|_| has_normals));
+                        *remap.entry(key).or_insert_with(|| {
+                            positions.push(self.positions[p].clone());
+                            if let Some(t) = key.1 {
+                                uvs.push(self.uvs[t]);
+                            }
+                            if let Some(n) = key.2 {
+                                normals.push(self.normals[n].clone());
+                            }
+                            positions.len() - 1
+                        })
+                    }));
+                }
+
+                let material = match (&self.material, self.materials.get(&chunk.material)) {
+                    (Some(m), _) | (None, Some(m)) => m.clone(),
+                    (None, None) => default.clone(),
+                };
+
+                ObjMesh {
+                    name: chunk.name,
+                    material: chunk.material,
+                    mesh: TriangleMesh::new(positions, normals, uvs, &indices, material),
+                }
+            })
+            .collect()
+    }
+}
+
+// Splits a planar polygon into triangles by ear clipping, so that concave faces are handled.
+// Falls back to a fan for degenerate polygons.
+fn triangulate(polygon: &[&Point3]) -> Vec<[usize; 3]> {
+    let n = polygon.len();
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Newell's method gives a robust normal for the polygon, used to project it onto the plane
+    // of its two least significant axes.
+    let mut normal = [0.0; 3];
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        normal[0] += (a.y() - b.y()) * (a.z() + b.z());
+        normal[1] += (a.z() - b.z()) * (a.x() + b.x());
+        normal[2] += (a.x() - b.x()) * (a.y() + b.y());
+    }
+    let axis = (0..3)
+        .max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs()))
+        .unwrap_or(2);
+    let points: Vec<(f64, f64)> = polygon
+        .iter()
+        .map(|p| match axis {
+            0 => (p.y(), p.z()),
+            1 => (p.z(), p.x()),
+            _ => (p.x(), p.y()),
+        })
+        .collect();
+    let orientation = normal[axis].signum();
+
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        ((a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)) * orientation
+    };
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 && orientation != 0.0 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + m - 1) % m],
+                remaining[i],
+                remaining[(i + 1) % m],
+            );
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            if cross(pa, pb, pc) <= 0.0 {
+                return false;
+            }
+
+            // No other vertex may lie inside the candidate ear.
+            !remaining.iter().any(|&j| {
+                j != a
+                    && j != b
+                    && j != c
+                    && cross(pa, pb, points[j]) >= 0.0
+                    && cross(pb, pc, points[j]) >= 0.0
+                    && cross(pc, pa, points[j]) >= 0.0
+            })
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + m - 1) % m],
+                    remaining[i],
+                    remaining[(i + 1) % m],
+                ]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+
+    triangles
+}
+
+fn parse(
+    text: &str,
+    directory: &Path,
+    material: Option<Arc<dyn Material>>,
+) -> Result<Vec<ObjMesh>, SceneError> {
+    let mut parser = ObjParser::new(directory, material);
+
+    for statement in statements(text) {
+        parser.statement(&statement)?;
+    }
+
+    Ok(parser.meshes())
+}
+
+// Loads the meshes of an OBJ file, one per group and material. Material libraries are looked
+// up next to the file; when `material` is given it is used for every face instead.
+pub fn load<P: AsRef<Path>>(
+    path: P,
+    material: Option<Arc<dyn Material>>,
+) -> Result<Vec<ObjMesh>, SceneError> {
+    let path = path.as_ref();
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    parse(&read(path)?, directory, material).map_err(|e| e.in_file(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval::Interval;
+    use crate::objects::{HitRecord, Hittable};
+    use crate::ray::Ray;
+
+    const CUBE: &str = "\
+# Unit cube made of quads
+mtllib cube.mtl
+o cube
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 -1
+g bottom top
+usemtl red
+f 1/1/1 4/4/1 3/3/1 2/2/1
+f -4/1 -3/2 -2/3 \\
+  -1/4
+g sides
+usemtl steel
+f 1 2 6 5
+f 2 3 7 6
+f 3 4 8 7
+f 4 1 5 8
+";
+
+    const MTL: &str = "\
+newmtl red
+Kd 0.8 0.1 0.1
+newmtl steel
+Kd 0.1 0.1 0.1
+Ks 0.9
+Ns 200
+newmtl glass
+d 0.2
+Ni 1.45
+";
+
+    #[test]
+    fn test_parse() {
+        let directory = std::env::temp_dir().join("rustracer-obj-test");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("cube.mtl"), MTL).unwrap();
+
+        let meshes = parse(CUBE, &directory, None).unwrap();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].name, "bottom top");
+        assert_eq!(meshes[0].material, "red");
+        assert_eq!(meshes[0].mesh.len(), 4);
+        assert_eq!(meshes[1].name, "sides");
+        assert_eq!(meshes[1].mesh.len(), 8);
+
+        // The top face, with the texture coordinates of its corners interpolated.
+        let ray = Ray::new(Point3::new(0.25, 0.5, 3.0), Vector3::new(0.0, 0.0, -1.0));
+        let rec = meshes[0]
+            .mesh
+            .hit(&ray, &Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        assert_eq!(rec.t, 2.0);
+        assert!((rec.u - 0.25).abs() < 1e-12 && (rec.v - 0.5).abs() < 1e-12);
+
+        let missing = parse(CUBE, Path::new("/nonexistent"), None).err().unwrap();
+        assert_eq!(missing.position, Some(Position { line: 2, column: 8 }));
+
+        let overridden: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(1.0, 1.0, 1.0)));
+        assert_eq!(
+            parse(CUBE, Path::new("/nonexistent"), Some(overridden))
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(1.0, 1.0, 1.0)));
+        let cases = [
+            (
+                "v 0 0 0\nv 1 0 0\nf 1 2 3\n",
+                3,
+                7,
+                "position index 3 out of range, 2 defined so far",
+            ),
+            ("v 0 0\n", 1, 1, "\"v\" expects 3 to 4 values, found 2"),
+            ("v 0 0 x\n", 1, 7, "expected a number, found \"x\""),
+            (
+                "v 0 0 0\n\nf 1 1/x 1\n",
+                3,
+                5,
+                "invalid face vertex \"1/x\"",
+            ),
+            (
+                "v 0 0 0\nf 1 1\n",
+                2,
+                1,
+                "\"f\" expects at least 3 values, found 2",
+            ),
+        ];
+
+        for (text, line, column, message) in cases {
+            let e = parse(text, Path::new(""), Some(material.clone()))
+                .err()
+                .unwrap();
+            assert_eq!(e.position, Some(Position { line, column }), "{}", text);
+            assert_eq!(e.message, message);
+        }
+
+        let e = parse("usemtl wood\n", Path::new(""), None).err().unwrap();
+        assert_eq!(e.message, "unknown material \"wood\"");
+
+        let e = parse_mtl("Kd 1 1 1\n", Path::new("")).err().unwrap();
+        assert_eq!(e.message, "\"Kd\" before newmtl");
+    }
+
+    #[test]
+    fn test_map_kd() {
+        let directory = std::env::temp_dir().join("rustracer-mtl-test");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("wood.ppm"), "P3 2 1 255 255 0 0 0 0 255\n").unwrap();
+
+        let materials = parse_mtl("newmtl wood\nmap_Kd -s 1 1 1 wood.ppm\n", &directory).unwrap();
+        let mut rec = HitRecord::new();
+        rec.u = 0.75;
+        let ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let srec = materials["wood"].scatter(&ray, &rec).unwrap();
+        assert_eq!(srec.attenuation, Color::new(0.0, 0.0, 1.0));
+
+        let e = parse_mtl("newmtl wood\nmap_Kd oak.png\n", &directory)
+            .err()
+            .unwrap();
+        assert_eq!(e.position, Some(Position { line: 2, column: 8 }));
+        assert!(e.message.starts_with("cannot read image \"oak.png\""));
+    }
+
+    #[test]
+    fn test_triangulate_concave() {
+        // L-shaped hexagon: a fan from the first vertex would cover the notch.
+        let points = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(2.0, 1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(1.0, 2.0, 0.0),
+            Point3::new(0.0, 2.0, 0.0),
+        ];
+        let polygon: Vec<&Point3> = points.iter().collect();
+        let triangles = triangulate(&polygon);
+
+        assert_eq!(triangles.len(), 4);
+        let area: f64 = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let (pa, pb, pc) = (&points[a], &points[b], &points[c]);
+                ((pb.x() - pa.x()) * (pc.y() - pa.y()) - (pb.y() - pa.y()) * (pc.x() - pa.x()))
+                    / 2.0
+            })
+            .sum();
+        assert_eq!(area, 3.0);
+    }
+}
@@ -0,0 +1,322 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::interval::Interval;
+use crate::material::color::Color;
+use crate::objects::aabb::Aabb;
+use crate::objects::onb::Onb;
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::objects::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::rng;
+
+// Angular radius of the sun, in radians.
+const SUN_RADIUS: f64 = 0.00465;
+
+// Luminance of the sun outside the atmosphere, in kcd/m².
+const SUN_LUMINANCE: f64 = 1.6e6;
+
+// Radiance of a luminance of 1 kcd/m², so that white surfaces in the midday sun come out
+// around one.
+const SCALE: f64 = 0.05;
+
+// Clear daylight sky after the analytic model of Preetham, Shirley and Smits, with the disk of
+// the sun. The luminance and the chromaticity of the sky are each given by a Perez distribution,
+// brightening towards the sun and the horizon, whose coefficients follow from the turbidity of
+// the air; the sun is dimmed and reddened by the air it shines through.
+//
+// As a background it gives the radiance of the escaping rays. As a light it draws directions
+// towards the disk of the sun, far too small to be found by escaping rays alone; the rest of the
+// sky is smooth enough to be left to them. There is no ground: directions below the horizon see
+// the sky at the horizon.
+pub struct Sky {
+    elevation: f64,       // Height of the sun above the horizon, in degrees
+    azimuth: f64,         // Turn of the sun from -z towards +x, in degrees
+    turbidity: f64,       // Haze, from 2 for a clear sky to 10 for a hazy one
+    intensity: f64,       // Scale of the radiance
+    sun: Vector3,         // Unit vector towards the sun
+    perez: [[f64; 5]; 3], // Distribution coefficients of the luminance, x and y
+    zenith: [f64; 3],     // Zenith values over the distribution at the zenith
+    sun_color: Color,     // Radiance of the sun disk, before scaling
+    bbox: Aabb,
+}
+
+// Relative value of the distribution at the angles from the zenith and from the sun.
+fn perez(c: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    (1.0 + c[0] * f64::exp(c[1] / cos_theta))
+        * (1.0 + c[2] * f64::exp(c[3] * gamma) + c[4] * gamma.cos() * gamma.cos())
+}
+
+// Polynomial in the angle of the sun from the zenith, for each power of the turbidity.
+fn chromaticity(t: f64, theta: f64, c: [[f64; 4]; 3]) -> f64 {
+    let polynomial = |c: [f64; 4]| ((c[0] * theta + c[1]) * theta + c[2]) * theta + c[3];
+
+    t * t * polynomial(c[0]) + t * polynomial(c[1]) + polynomial(c[2])
+}
+
+impl Sky {
+    // Turbidity is kept within the range of the model, 2 to 10, and the sun above the horizon.
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Sky {
+        let elevation = elevation.clamp(0.0, 90.0);
+        let t = turbidity.clamp(2.0, 10.0);
+        let (e, a) = (elevation.to_radians(), azimuth.to_radians());
+        let sun = Vector3::new(e.cos() * a.sin(), e.sin(), -e.cos() * a.cos());
+        let theta = PI / 2.0 - e;
+
+        let coefficients = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+        let zenith = [
+            (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192,
+            chromaticity(
+                t,
+                theta,
+                [
+                    [0.00166, -0.00375, 0.00209, 0.0],
+                    [-0.02903, 0.06377, -0.03202, 0.00394],
+                    [0.11693, -0.21196, 0.06052, 0.25886],
+                ],
+            ),
+            chromaticity(
+                t,
+                theta,
+                [
+                    [0.00275, -0.00610, 0.00317, 0.0],
+                    [-0.04214, 0.08970, -0.04153, 0.00516],
+                    [0.15346, -0.26756, 0.06670, 0.26688],
+                ],
+            ),
+        ];
+
+        Sky {
+            elevation,
+            azimuth,
+            turbidity: t,
+            intensity: 1.0,
+            sun,
+            zenith: [0, 1, 2].map(|i| zenith[i] / perez(&coefficients[i], 1.0, theta)),
+            perez: coefficients,
+            sun_color: sun_color(theta, t),
+            bbox: Aabb::empty(),
+        }
+    }
+
+    pub fn set_intensity(&mut self, intensity: f64) -> &mut Self {
+        self.intensity = intensity;
+
+        self
+    }
+
+    pub fn elevation(&self) -> f64 {
+        self.elevation
+    }
+
+    pub fn azimuth(&self) -> f64 {
+        self.azimuth
+    }
+
+    pub fn turbidity(&self) -> f64 {
+        self.turbidity
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    pub fn sun(&self) -> &Vector3 {
+        &self.sun
+    }
+
+    // Radiance arriving from the direction.
+    pub fn radiance(&self, direction: &Vector3) -> Color {
+        let length = direction.norm();
+        if length <= 0.0 {
+            return Color::default();
+        }
+
+        let d = direction / length;
+        let mut radiance = self.sky(&d);
+        if d.dot(&self.sun) >= SUN_RADIUS.cos() {
+            radiance += &self.sun_color;
+        }
+
+        SCALE * self.intensity * radiance
+    }
+
+    // Radiance of the sky alone in the unit direction, in kcd/m².
+    fn sky(&self, d: &Vector3) -> Color {
+        // The distributions fall to their horizon values as the cosine goes to zero.
+        let cos_theta = f64::max(d.y(), 0.0);
+        let gamma = f64::acos(Interval::new(-1.0, 1.0).clamp(d.dot(&self.sun)));
+        let [luminance, x, y] =
+            [0, 1, 2].map(|i| self.zenith[i] * perez(&self.perez[i], cos_theta, gamma));
+        if y <= 0.0 {
+            return Color::default();
+        }
+
+        // From xyY to linear sRGB, out of gamut colors losing their negative components.
+        let (cx, cy, cz) = (x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let color = Color::new(
+            3.2404542 * cx - 1.5371385 * cy - 0.4985314 * cz,
+            -0.9692660 * cx + 1.8760108 * cy + 0.0415560 * cz,
+            0.0556434 * cx - 0.2040259 * cy + 1.0572252 * cz,
+        );
+
+        color.clamp(&Interval::new(0.0, f64::INFINITY))
+    }
+
+    // Density by solid angle of the directions drawn by sample.
+    pub fn pdf(&self, direction: &Vector3) -> f64 {
+        let length = direction.norm();
+        if length <= 0.0 || direction.dot(&self.sun) / length < SUN_RADIUS.cos() {
+            return 0.0;
+        }
+
+        1.0 / (2.0 * PI * (1.0 - SUN_RADIUS.cos()))
+    }
+
+    // Unit direction drawn uniformly over the disk of the sun.
+    pub fn sample(&self) -> Vector3 {
+        let (r1, r2) = (rng::random(), rng::random());
+        let z = 1.0 + r2 * (SUN_RADIUS.cos() - 1.0);
+        let phi = 2.0 * PI * r1;
+        let sin = f64::sqrt(1.0 - z * z);
+
+        Onb::new(&self.sun).transform(&Vector3::new(phi.cos() * sin, phi.sin() * sin, z))
+    }
+}
+
+// Radiance of the sun seen through the air, in kcd/m², after the transmittance of the
+// atmosphere for its molecules and its haze at a wavelength for each channel.
+fn sun_color(theta: f64, turbidity: f64) -> Color {
+    // Length of the way through the air, relative to the way straight up.
+    let mass = 1.0 / (theta.cos() + 0.15 * f64::powf(93.885 - theta.to_degrees(), -1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let transmittance = |wavelength: f64| {
+        let rayleigh = 0.008735 * wavelength.powf(-4.08);
+        let aerosols = beta * wavelength.powf(-1.3);
+
+        f64::exp(-(rayleigh + aerosols) * mass)
+    };
+
+    // Wavelengths in micrometres.
+    SUN_LUMINANCE
+        * Color::new(
+            transmittance(0.65),
+            transmittance(0.55),
+            transmittance(0.45),
+        )
+}
+
+impl Hittable for Sky {
+    fn hit(&self, _ray: &Ray, _rayt: &Interval) -> Option<HitRecord> {
+        None
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, _origin: &Point3, direction: &Vector3) -> f64 {
+        self.pdf(direction)
+    }
+
+    fn random(&self, _origin: &Point3) -> Vector3 {
+        self.sample()
+    }
+}
+
+impl fmt::Debug for Sky {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sky")
+            .field("elevation", &self.elevation)
+            .field("azimuth", &self.azimuth)
+            .field("turbidity", &self.turbidity)
+            .field("intensity", &self.intensity)
+            .finish()
+    }
+}
+
+impl PartialEq for Sky {
+    fn eq(&self, other: &Sky) -> bool {
+        self.elevation == other.elevation
+            && self.azimuth == other.azimuth
+            && self.turbidity == other.turbidity
+            && self.intensity == other.intensity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_radiance() {
+        let sky = Sky::new(30.0, 90.0, 3.0);
+        assert!((sky.sun().x() - 30_f64.to_radians().cos()).abs() < 1e-12);
+        assert!((sky.sun().y() - 0.5).abs() < 1e-12);
+
+        // Blue overhead, at the zenith luminance.
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let zenith = sky.radiance(&up);
+        assert!(zenith.b() > zenith.g() && zenith.g() > zenith.r());
+        let chi = (4.0 / 9.0 - 3.0 / 120.0) * PI / 3.0;
+        let luminance = (4.0453 * 3.0 - 4.9710) * chi.tan() - 0.2155 * 3.0 + 2.4192;
+        assert!((zenith.luminance() - SCALE * luminance).abs() < 1e-3);
+
+        // Brighter around the sun and towards the horizon, and the sun far brighter still.
+        let near = sky.radiance(&Vector3::new(1.0, 0.6, 0.1));
+        let away = sky.radiance(&Vector3::new(-1.0, 0.6, -0.1));
+        assert!(near.luminance() > away.luminance());
+        let horizon = sky.radiance(&Vector3::new(-1.0, 0.0, 0.0));
+        assert!(horizon.luminance() > zenith.luminance());
+        assert!(!sky.radiance(&Vector3::new(-1.0, -0.5, 0.0)).is_black());
+        assert!(sky.radiance(sky.sun()).luminance() > 1000.0 * near.luminance());
+
+        // The sun reddens as it sets, and dims in haze.
+        let sun = |sky: &Sky| sky.radiance(sky.sun());
+        let (high, low) = (
+            sun(&Sky::new(60.0, 0.0, 3.0)),
+            sun(&Sky::new(2.0, 0.0, 3.0)),
+        );
+        assert!(low.b() / low.r() < high.b() / high.r());
+        assert!(sun(&Sky::new(60.0, 0.0, 8.0)).luminance() < high.luminance());
+    }
+
+    #[test]
+    fn test_sampling() {
+        let sky = Sky::new(45.0, 30.0, 2.5);
+        rng::seed(5);
+
+        let solid_angle = 2.0 * PI * (1.0 - SUN_RADIUS.cos());
+        for _ in 0..100 {
+            let d = sky.sample();
+            assert!((d.norm() - 1.0).abs() < 1e-9);
+            assert!((sky.pdf(&d) * solid_angle - 1.0).abs() < 1e-9);
+        }
+        assert_eq!(sky.pdf(&Vector3::new(0.0, 1.0, 0.0)), 0.0);
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use crate::material::color::Color;
+use crate::objects::point3::Point3;
+
+use super::{SolidColor, Texture};
+
+// Solid checker pattern alternating two textures in unit cubes of the given size.
+#[derive(Clone)]
+pub struct Checker {
+    inv_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl Checker {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Checker {
+        Checker {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Checker {
+        Checker::new(
+            scale,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let x = (self.inv_scale * p.x()).floor() as i64;
+        let y = (self.inv_scale * p.y()).floor() as i64;
+        let z = (self.inv_scale * p.z()).floor() as i64;
+
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let checker = Checker::from_colors(0.5, white.clone(), black.clone());
+
+        assert_eq!(checker.value(0.0, 0.0, &Point3::new(0.1, 0.1, 0.1)), white);
+        assert_eq!(checker.value(0.0, 0.0, &Point3::new(0.6, 0.1, 0.1)), black);
+        assert_eq!(checker.value(0.0, 0.0, &Point3::new(-0.1, 0.1, 0.1)), black);
+        assert_eq!(
+            checker.value(0.0, 0.0, &Point3::new(-0.1, -0.1, 0.1)),
+            white
+        );
+    }
+}
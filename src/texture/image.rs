@@ -0,0 +1,62 @@
+use std::io;
+use std::path::Path;
+
+use crate::film::Film;
+use crate::interval::Interval;
+use crate::material::color::Color;
+use crate::objects::point3::Point3;
+
+use super::Texture;
+
+// Image mapped on the unit square of texture coordinates, v going up from the bottom row.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    image: Film,
+}
+
+impl ImageTexture {
+    pub fn new(image: Film) -> ImageTexture {
+        ImageTexture { image }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<ImageTexture> {
+        Ok(ImageTexture::new(Film::load(path)?))
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        let (w, h) = (self.image.width(), self.image.height());
+        if w == 0 || h == 0 {
+            // Debugging aid, missing images show up in cyan.
+            return Color::new(0.0, 1.0, 1.0);
+        }
+
+        let unit = Interval::new(0.0, 1.0);
+        let u = unit.clamp(u);
+        let v = 1.0 - unit.clamp(v);
+
+        let x = ((u * w as f64) as u32).min(w - 1);
+        let y = ((v * h as f64) as u32).min(h - 1);
+
+        self.image.get(x, y).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value() {
+        let mut film = Film::new(2, 2);
+        film.set(0, 0, Color::new(1.0, 0.0, 0.0));
+        film.set(1, 1, Color::new(0.0, 0.0, 1.0));
+        let texture = ImageTexture::new(film);
+        let p = Point3::new(0.0, 0.0, 0.0);
+
+        assert_eq!(texture.value(0.25, 0.75, &p), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(texture.value(1.0, 0.0, &p), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(texture.value(2.0, -1.0, &p), Color::new(0.0, 0.0, 1.0));
+    }
+}
@@ -0,0 +1,29 @@
+pub mod checker;
+pub mod image;
+pub mod noise;
+pub mod perlin;
+
+use crate::material::color::Color;
+use crate::objects::point3::Point3;
+
+// Color of a surface as a function of its texture coordinates and of the hit point.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> SolidColor {
+        SolidColor { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        self.albedo.clone()
+    }
+}
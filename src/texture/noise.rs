@@ -0,0 +1,44 @@
+use crate::material::color::Color;
+use crate::objects::point3::Point3;
+
+use super::perlin::Perlin;
+use super::Texture;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    Smooth,     // Plain Perlin noise
+    Turbulence, // Several octaves of absolute noise
+    Marble,     // Sine stripes along z, phase shifted by turbulence
+}
+
+#[derive(Debug, Clone)]
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+    kind: NoiseKind,
+    color: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64, kind: NoiseKind, color: Color) -> NoiseTexture {
+        NoiseTexture {
+            noise: Perlin::new(),
+            scale,
+            kind,
+            color,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        let s = Point3::new(self.scale * p.x(), self.scale * p.y(), self.scale * p.z());
+        let intensity = match self.kind {
+            NoiseKind::Smooth => 0.5 * (1.0 + self.noise.noise(&s)),
+            NoiseKind::Turbulence => self.noise.turbulence(&s, 7),
+            NoiseKind::Marble => 0.5 * (1.0 + (s.z() + 10.0 * self.noise.turbulence(p, 7)).sin()),
+        };
+
+        intensity * &self.color
+    }
+}
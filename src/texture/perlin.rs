@@ -0,0 +1,124 @@
+use crate::objects::point3::Point3;
+use crate::objects::vector3::Vector3;
+use crate::rng;
+
+const POINT_COUNT: usize = 256;
+
+// Gradient noise over random unit vectors, hashed from the integer lattice with three
+// permutation tables.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    vectors: Vec<Vector3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new() -> Perlin {
+        let vectors = (0..POINT_COUNT)
+            .map(|_| {
+                Vector3::new(
+                    rng::random_range(-1.0, 1.0),
+                    rng::random_range(-1.0, 1.0),
+                    rng::random_range(-1.0, 1.0),
+                )
+                .normalise()
+            })
+            .collect();
+
+        Perlin {
+            vectors,
+            perm_x: Perlin::permutation(),
+            perm_y: Perlin::permutation(),
+            perm_z: Perlin::permutation(),
+        }
+    }
+
+    fn permutation() -> Vec<usize> {
+        let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+        for i in (1..POINT_COUNT).rev() {
+            let target = rng::random_range_u32(0, i as u32 + 1) as usize;
+            p.swap(i, target);
+        }
+
+        p
+    }
+
+    // Noise in [-1, 1], smoothly interpolated between the lattice points.
+    pub fn noise(&self, p: &Point3) -> f64 {
+        let (fx, fy, fz) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (u, v, w) = (p.x() - fx, p.y() - fy, p.z() - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+
+        // Hermite smoothing of the blending weights.
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index = self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize];
+                    let (a, b, c) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vector3::new(u - a, v - b, w - c);
+
+                    accum += (a * uu + (1.0 - a) * (1.0 - uu))
+                        * (b * vv + (1.0 - b) * (1.0 - vv))
+                        * (c * ww + (1.0 - c) * (1.0 - ww))
+                        * self.vectors[index].dot(&weight);
+                }
+            }
+        }
+
+        accum
+    }
+
+    // Sum of the absolute noise over the given number of octaves.
+    pub fn turbulence(&self, p: &Point3, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut p = p.clone();
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&p);
+            weight *= 0.5;
+            p = Point3::new(2.0 * p.x(), 2.0 * p.y(), 2.0 * p.z());
+        }
+
+        accum.abs()
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Perlin {
+        Perlin::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise() {
+        rng::seed(5);
+        let perlin = Perlin::new();
+
+        for _ in 0..1000 {
+            let p = Point3::new(
+                rng::random_range(-10.0, 10.0),
+                rng::random_range(-10.0, 10.0),
+                rng::random_range(-10.0, 10.0),
+            );
+            assert!(perlin.noise(&p).abs() <= 1.0);
+            assert!(perlin.turbulence(&p, 7) >= 0.0);
+        }
+
+        // The gradients vanish on the lattice.
+        assert_eq!(perlin.noise(&Point3::new(3.0, -2.0, 7.0)), 0.0);
+    }
+}